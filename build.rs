@@ -0,0 +1,75 @@
+//! Build script that compiles the Java test programs under
+//! `support/java_src/` into class files under `support/tests/`.
+//!
+//! Sources are only compiled when they are newer than their class files.
+//! If `javac` isn't available on `$PATH` compilation is skipped and the
+//! pre-compiled class files checked into the repository are used instead.
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::SystemTime;
+
+const JAVA_SOURCES_DIR: &str = "support/java_src";
+const CLASS_FILES_DIR: &str = "support/tests";
+
+fn main() {
+    println!("cargo:rerun-if-changed={JAVA_SOURCES_DIR}");
+
+    let root = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    compile(&root, &root.join(JAVA_SOURCES_DIR), &[]);
+    // Programs under `debug/` keep their debug information (local variable
+    // tables) for the tests that inspect it.
+    compile(&root, &root.join(JAVA_SOURCES_DIR).join("debug"), &["-g"]);
+}
+
+/// Compile the Java sources directly under `dir` with `javac`.
+fn compile(root: &Path, dir: &Path, flags: &[&str]) {
+    let Ok(entries) = dir.read_dir() else {
+        return;
+    };
+    let sources: Vec<PathBuf> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "java"))
+        .collect();
+    let class_files = root.join(CLASS_FILES_DIR);
+    if sources
+        .iter()
+        .all(|source| is_up_to_date(source, &class_files))
+    {
+        return;
+    }
+
+    match Command::new("javac")
+        .args(flags)
+        .arg("-d")
+        .arg(&class_files)
+        .args(&sources)
+        .status()
+    {
+        Ok(status) if status.success() => (),
+        Ok(status) => println!(
+            "cargo:warning=javac failed ({status}), using pre-compiled class files"
+        ),
+        Err(_) => println!(
+            "cargo:warning=javac not found on $PATH, using pre-compiled class files"
+        ),
+    }
+}
+
+/// Returns whether the class file compiled from `source` into `class_files`
+/// is at least as recent as the source.
+fn is_up_to_date(source: &Path, class_files: &Path) -> bool {
+    let modified = |path: &Path| -> Option<SystemTime> {
+        fs::metadata(path).ok()?.modified().ok()
+    };
+    let Some(name) = source.file_stem() else {
+        return false;
+    };
+    let class_file = class_files.join(name).with_extension("class");
+    match (modified(source), modified(&class_file)) {
+        (Some(source), Some(class_file)) => class_file >= source,
+        _ => false,
+    }
+}
@@ -3,8 +3,7 @@
 /// ARM64 (aarch64) registers, mainly used to keep track of available
 /// and used registers during compilation.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-#[cfg(target_arch = "aarch64")]
-enum Register {
+pub enum Register {
     // Arguments and return values.
     X0 = 0x0,
     X1 = 0x1,
@@ -48,6 +47,67 @@ enum Register {
     X31 = 0x1F,
 }
 
+impl Register {
+    /// Returns whether the register must be preserved across calls, X29
+    /// (frame pointer) is saved separately by the prologue.
+    #[must_use]
+    pub const fn is_callee_saved(self) -> bool {
+        Self::is_callee_saved_number(self as u8)
+    }
+
+    /// Returns whether the register numbered `reg` must be preserved across
+    /// calls.
+    const fn is_callee_saved_number(reg: u8) -> bool {
+        reg >= Self::X19 as u8 && reg <= Self::X28 as u8
+    }
+}
+
+impl From<Register> for u8 {
+    fn from(reg: Register) -> Self {
+        reg as Self
+    }
+}
+
+/// Bitset of registers, used to track the registers allocated to a trace so
+/// the prologue and epilogue only save and restore the callee-saved ones.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RegisterSet(u32);
+
+impl RegisterSet {
+    /// Add a register to the set.
+    pub fn insert(&mut self, reg: impl Into<u8>) {
+        self.0 |= 1 << reg.into();
+    }
+
+    /// Check if a register is in the set.
+    pub fn contains(self, reg: impl Into<u8>) -> bool {
+        self.0 & (1 << reg.into()) != 0
+    }
+
+    /// Returns the register numbers in the set in ascending order.
+    #[must_use]
+    pub fn registers(self) -> Vec<u8> {
+        (0..32).filter(|reg| self.0 & (1 << reg) != 0).collect()
+    }
+
+    /// Returns the callee-saved registers of the set (X19 to X28) in the
+    /// pairs saved by `stp`, an odd register is paired with `xzr`.
+    #[must_use]
+    pub fn callee_saved_pairs(self) -> Vec<(u8, u8)> {
+        let saved: Vec<u8> = self
+            .registers()
+            .into_iter()
+            .filter(|reg| Register::is_callee_saved_number(*reg))
+            .collect();
+        saved
+            .chunks(2)
+            .map(|pair| {
+                (pair[0], pair.get(1).copied().unwrap_or(Register::X31 as u8))
+            })
+            .collect()
+    }
+}
+
 /// Create a mask to extract n-bits of a given value from start.
 #[cfg(target_arch = "aarch64")]
 pub fn mask(len: u64, start: u64) -> u64 {
@@ -61,10 +121,39 @@ pub fn split(x: u64) -> (u32, u32) {
 }
 
 #[cfg(test)]
-#[cfg(target_arch = "aarch64")]
 mod tests {
     use super::*;
+
+    #[test]
+    fn can_track_callee_saved_registers() {
+        let mut used = RegisterSet::default();
+        for reg in [Register::X9, Register::X19, Register::X28] {
+            if reg.is_callee_saved() {
+                used.insert(reg);
+            }
+        }
+        assert!(used.contains(Register::X19));
+        assert!(!used.contains(Register::X9));
+        assert_eq!(used.registers(), vec![0x13, 0x1C]);
+    }
+
+    #[test]
+    fn only_callee_saved_registers_are_saved() {
+        let mut used = RegisterSet::default();
+        for reg in [Register::X3, Register::X12, Register::X20, Register::X9] {
+            used.insert(reg);
+        }
+        // Temporaries are clobbered freely, X20 is paired with `xzr`.
+        assert_eq!(used.callee_saved_pairs(), vec![(0x14, 0x1F)]);
+        for reg in [Register::X19, Register::X28] {
+            used.insert(reg);
+        }
+        assert_eq!(used.callee_saved_pairs(), vec![(0x13, 0x14), (0x1C, 0x1F)]);
+        assert!(RegisterSet::default().callee_saved_pairs().is_empty());
+    }
+
     #[test]
+    #[cfg(target_arch = "aarch64")]
     fn immediate_from_i32() {
         // Given the following immediate break it to separate bits to fit
         // an ARM64 instruction.
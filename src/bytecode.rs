@@ -1,326 +1,407 @@
 //! JVM bytecode definitions.
 use std::fmt;
 
-/// OPCodes supported by the JVM as documented in the spec document.
-/// ref: https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-7.html
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
-pub enum OPCode {
+/// Defines the `OPCode` enum from a table of `name = byte => mnemonic`
+/// entries and generates the conversions from and to raw bytes, the mnemonic
+/// lookup and the tests checking the table is consistent.
+macro_rules! define_opcodes {
+    ($($(#[$attr:meta])* $name:ident = $byte:literal => $mnemonic:literal,)*) => {
+        /// OPCodes supported by the JVM as documented in the spec document.
+        /// ref: https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-7.html
+        #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+        pub enum OPCode {
+            $($(#[$attr])* $name,)*
+            // Proxy value to signal unknown opcode values.
+            Unspecified,
+        }
+
+        impl OPCode {
+            /// All the opcodes defined in the table ordered by byte value.
+            pub const ALL: &'static [OPCode] = &[$(Self::$name,)*];
+
+            /// Returns the opcode mnemonic as written in the spec.
+            #[must_use]
+            pub const fn mnemonic(&self) -> &'static str {
+                match self {
+                    $(Self::$name => $mnemonic,)*
+                    Self::Unspecified => "unspecified",
+                }
+            }
+        }
+
+        impl From<u8> for OPCode {
+            fn from(byte: u8) -> Self {
+                match byte {
+                    $($byte => Self::$name,)*
+                    _ => Self::Unspecified,
+                }
+            }
+        }
+
+        // `Unspecified` is encoded as `0xff` which is reserved by the spec
+        // (`impdep2`) and never appears in class files.
+        impl From<OPCode> for u8 {
+            fn from(opcode: OPCode) -> Self {
+                match opcode {
+                    $(OPCode::$name => $byte,)*
+                    OPCode::Unspecified => u8::MAX,
+                }
+            }
+        }
+
+        #[cfg(test)]
+        mod opcode_table_tests {
+            use super::OPCode;
+
+            #[test]
+            fn opcode_bytes_round_trip() {
+                $(
+                    assert_eq!(OPCode::from($byte), OPCode::$name);
+                    assert_eq!(u8::from(OPCode::$name), $byte);
+                )*
+            }
+
+            #[test]
+            fn opcode_table_is_exhaustive() {
+                for (byte, opcode) in OPCode::ALL.iter().enumerate() {
+                    assert_eq!(usize::from(u8::from(*opcode)), byte);
+                }
+                for byte in OPCode::ALL.len()..=usize::from(u8::MAX) {
+                    let byte = u8::try_from(byte).unwrap();
+                    assert_eq!(OPCode::from(byte), OPCode::Unspecified);
+                }
+            }
+
+            #[test]
+            fn opcode_mnemonics_are_unique() {
+                let mut mnemonics: Vec<&str> =
+                    OPCode::ALL.iter().map(OPCode::mnemonic).collect();
+                mnemonics.sort_unstable();
+                mnemonics.dedup();
+                assert_eq!(mnemonics.len(), OPCode::ALL.len());
+            }
+        }
+    };
+}
+
+// Opcode table, entries are ordered by byte value.
+define_opcodes! {
     /// Nop designates a no operation, it's similar to a NOP (0x90).
-    Nop,
+    Nop = 0x00 => "nop",
     /// Push `null` into the stack.
-    AConstNull,
-    IconstM1,
-    Iconst0,
-    Iconst1,
-    Iconst2,
-    Iconst3,
-    Iconst4,
-    Iconst5,
-    Lconst0,
-    Lconst1,
-    Fconst0,
-    Fconst1,
-    Fconst2,
-    Dconst0,
-    Dconst1,
+    AConstNull = 0x01 => "aconst_null",
+    IconstM1 = 0x02 => "iconst_m1",
+    Iconst0 = 0x03 => "iconst_0",
+    Iconst1 = 0x04 => "iconst_1",
+    Iconst2 = 0x05 => "iconst_2",
+    Iconst3 = 0x06 => "iconst_3",
+    Iconst4 = 0x07 => "iconst_4",
+    Iconst5 = 0x08 => "iconst_5",
+    Lconst0 = 0x09 => "lconst_0",
+    Lconst1 = 0x0a => "lconst_1",
+    Fconst0 = 0x0b => "fconst_0",
+    Fconst1 = 0x0c => "fconst_1",
+    Fconst2 = 0x0d => "fconst_2",
+    Dconst0 = 0x0e => "dconst_0",
+    Dconst1 = 0x0f => "dconst_1",
     /// Push a single byte operand into the stack.
-    BiPush,
+    BiPush = 0x10 => "bipush",
     /// Push a two byte operand (short) into the stack.
-    SiPush,
+    SiPush = 0x11 => "sipush",
     /// Push an `int` or `float` value from the runtime constant pool at the
     /// given index (byte long) into the stack.
-    Ldc,
+    Ldc = 0x12 => "ldc",
     /// Push an `int` or `float` value from the runtime constant pool at the
     /// given index (two byte long) into the stack.
-    LdcW,
+    LdcW = 0x13 => "ldc_w",
     /// Push a `long` or `double` value from the runtime constant pool at the
     /// given index into the stack.
-    Ldc2W,
+    Ldc2W = 0x14 => "ldc2_w",
     /// Load an `int` from the local variables array of the current frame
     /// and push it into the stack, the index is given as an operand.
-    ILoad,
+    ILoad = 0x15 => "iload",
     /// Load a `long` from the local variables array of the current frame
     /// and push it into the stack, the index is given as an operand.
-    LLoad,
+    LLoad = 0x16 => "lload",
     /// Load a `float` from the local variables array of the current frame
     /// and push it into the stack, the index is given as an operand.
-    FLoad,
+    FLoad = 0x17 => "fload",
     /// Load a `double` from the local variables array of the current frame
     /// and push it into the stack, the index is given as an operand.
-    DLoad,
+    DLoad = 0x18 => "dload",
     /// Load a `reference` from a local variable.
-    ALoad,
+    ALoad = 0x19 => "aload",
     /// Load `int` at index 0 from the local variables array of the current
     /// frame and push it into the stack.
-    ILoad0,
+    ILoad0 = 0x1a => "iload_0",
     /// Load `int` at index 1 from the local variables array of the current
     /// frame and push it into the stack.
-    ILoad1,
+    ILoad1 = 0x1b => "iload_1",
     /// Load `int` at index 2 from the local variables array of the current
     /// frame and push it into the stack.
-    ILoad2,
+    ILoad2 = 0x1c => "iload_2",
     /// Load `int` at index 3 from the local variables array of the current
     /// frame and push it into the stack.
-    ILoad3,
+    ILoad3 = 0x1d => "iload_3",
     /// Load `long` at index 0 from the local variables array of the current
     /// frame and push it into the stack.
-    LLoad0,
+    LLoad0 = 0x1e => "lload_0",
     /// Load `long` at index 1 from the local variables array of the current
     /// frame and push it into the stack.
-    LLoad1,
+    LLoad1 = 0x1f => "lload_1",
     /// Load `long` at index 2 from the local variables array of the current
     /// frame and push it into the stack.
-    LLoad2,
+    LLoad2 = 0x20 => "lload_2",
     /// Load `long` at index 3 from the local variables array of the current
     /// frame and push it into the stack.
-    LLoad3,
+    LLoad3 = 0x21 => "lload_3",
     /// Load `float` at index 0 from the local variables array of the current
     /// frame and push it into the stack.
-    FLoad0,
+    FLoad0 = 0x22 => "fload_0",
     /// Load `float` at index 1 from the local variables array of the current
     /// frame and push it into the stack.
-    FLoad1,
+    FLoad1 = 0x23 => "fload_1",
     /// Load `float` at index 2 from the local variables array of the current
     /// frame and push it into the stack.
-    FLoad2,
+    FLoad2 = 0x24 => "fload_2",
     /// Load `float` at index 3 from the local variables array of the current
     /// frame and push it into the stack.
-    FLoad3,
+    FLoad3 = 0x25 => "fload_3",
     /// Load `double` at index 0 from the local variables array of the current
     /// frame and push it into the stack.
-    DLoad0,
+    DLoad0 = 0x26 => "dload_0",
     /// Load `double` at index 1 from the local variables array of the current
     /// frame and push it into the stack.
-    DLoad1,
+    DLoad1 = 0x27 => "dload_1",
     /// Load `double` at index 2 from the local variables array of the current
     /// frame and push it into the stack.
-    DLoad2,
+    DLoad2 = 0x28 => "dload_2",
     /// Load `double` at index 3 from the local variables array of the current
     /// frame and push it into the stack.
-    DLoad3,
+    DLoad3 = 0x29 => "dload_3",
     /// Load the value at index 0 in the local variable array of the current
     /// frame into the stack.
-    ALoad0,
+    ALoad0 = 0x2a => "aload_0",
     /// Load the value at index 1 in the local variable array of the current
     /// frame into the stack.
-    ALoad1,
+    ALoad1 = 0x2b => "aload_1",
     /// Load the value at index 2 in the local variable array of the current
     /// frame into the stack.
-    ALoad2,
+    ALoad2 = 0x2c => "aload_2",
     /// Load the value at index 3 in the local variable array of the current
     /// frame into the stack.
-    ALoad3,
-    IALoad,
-    LALoad,
-    FALoad,
-    DALoad,
+    ALoad3 = 0x2d => "aload_3",
+    IALoad = 0x2e => "iaload",
+    LALoad = 0x2f => "laload",
+    FALoad = 0x30 => "faload",
+    DALoad = 0x31 => "daload",
     /// Load `reference` from an array, the top tweo values on the stack are
     /// the `index` and `reference`. The loaded value is pushed back into the
     /// stack.
-    AALoad,
-    BALoad,
-    CALoad,
-    SALoad,
+    AALoad = 0x32 => "aaload",
+    BALoad = 0x33 => "baload",
+    CALoad = 0x34 => "caload",
+    SALoad = 0x35 => "saload",
     /// Store `int` from the local variables array of the current frame
     /// and push it into the stack, the index is given as operand.
-    IStore,
+    IStore = 0x36 => "istore",
     /// Store `long` from the local variables array of the current frame
     /// and push it into the stack, the index is given as operand.
-    LStore,
+    LStore = 0x37 => "lstore",
     /// Store `float` from the local variables array of the current frame
     /// and push it into the stack, the index is given as operand.
-    FStore,
+    FStore = 0x38 => "fstore",
     /// Store `double` from the local variables array of the current frame
     /// and push it into the stack, the index is given as operand.
-    DStore,
+    DStore = 0x39 => "dstore",
     /// Store `reference` into a local variable.
-    AStore,
+    AStore = 0x3a => "astore",
     /// Store `int` at index 0 in the local variables array of the current
     /// frame into the stack.
-    IStore0,
+    IStore0 = 0x3b => "istore_0",
     /// Store `int` at index 1 in the local variables array of the current
     /// frame into the stack.
-    IStore1,
+    IStore1 = 0x3c => "istore_1",
     /// Store `int` at index 2 in the local variables array of the current
     /// frame into the stack.
-    IStore2,
+    IStore2 = 0x3d => "istore_2",
     /// Store `int` at index 3 in the local variables array of the current
     /// frame into the stack.
-    IStore3,
+    IStore3 = 0x3e => "istore_3",
     /// Store `long` at index 0 in the local variables array of the current
     /// frame into the stack.
-    LStore0,
+    LStore0 = 0x3f => "lstore_0",
     /// Store `long` at index 1 in the local variables array of the current
     /// frame into the stack.
-    LStore1,
+    LStore1 = 0x40 => "lstore_1",
     /// Store `long` at index 2 in the local variables array of the current
     /// frame into the stack.
-    LStore2,
+    LStore2 = 0x41 => "lstore_2",
     /// Store `long` at index 3 in the local variables array of the current
     /// frame into the stack.
-    LStore3,
+    LStore3 = 0x42 => "lstore_3",
     /// Store `float` at index 0 in the local variables array of the current
     /// frame into the stack.
-    FStore0,
+    FStore0 = 0x43 => "fstore_0",
     /// Store `float` at index 1 in the local variables array of the current
     /// frame into the stack.
-    FStore1,
+    FStore1 = 0x44 => "fstore_1",
     /// Store `float` at index 2 in the local variables array of the current
     /// frame into the stack.
-    FStore2,
+    FStore2 = 0x45 => "fstore_2",
     /// Store `float` at index 3 in the local variables array of the current
     /// frame into the stack.
-    FStore3,
+    FStore3 = 0x46 => "fstore_3",
     /// Store `double` at index 0 in the local variables array of the current
     /// frame into the stack.
-    DStore0,
+    DStore0 = 0x47 => "dstore_0",
     /// Store `double` at index 1 in the local variables array of the current
     /// frame into the stack.
-    DStore1,
+    DStore1 = 0x48 => "dstore_1",
     /// Store `double` at index 2 in the local variables array of the current
     /// frame into the stack.
-    DStore2,
+    DStore2 = 0x49 => "dstore_2",
     /// Store `double` at index 3 in the local variables array of the current
     /// frame into the stack.
-    DStore3,
-    AStore0,
-    AStore1,
-    AStore2,
-    AStore3,
-    IAStore,
-    LAStore,
-    FAStore,
-    DAStore,
+    DStore3 = 0x4a => "dstore_3",
+    AStore0 = 0x4b => "astore_0",
+    AStore1 = 0x4c => "astore_1",
+    AStore2 = 0x4d => "astore_2",
+    AStore3 = 0x4e => "astore_3",
+    IAStore = 0x4f => "iastore",
+    LAStore = 0x50 => "lastore",
+    FAStore = 0x51 => "fastore",
+    DAStore = 0x52 => "dastore",
     /// Store into a `reference` array, the top three values on the stack are
     /// the value, index and reference to the array.
-    AAStore,
-    BAStore,
-    CAStore,
-    SAStore,
-    Pop,
-    Pop2,
-    Dup,
-    DupX1,
-    DupX2,
-    Dup2,
-    Dup2X1,
-    Dup2X2,
-    Swap,
+    AAStore = 0x53 => "aastore",
+    BAStore = 0x54 => "bastore",
+    CAStore = 0x55 => "castore",
+    SAStore = 0x56 => "sastore",
+    Pop = 0x57 => "pop",
+    Pop2 = 0x58 => "pop2",
+    Dup = 0x59 => "dup",
+    DupX1 = 0x5a => "dup_x1",
+    DupX2 = 0x5b => "dup_x2",
+    Dup2 = 0x5c => "dup2",
+    Dup2X1 = 0x5d => "dup2_x1",
+    Dup2X2 = 0x5e => "dup2_x2",
+    Swap = 0x5f => "swap",
     /// Pop the top two value from the stack (they must be of type `int`) then
     /// push their sum into the stack.
-    IAdd,
+    IAdd = 0x60 => "iadd",
     /// Pop the top two value from the stack (they must be of type `long`) then
     /// push their sum into the stack.
-    LAdd,
+    LAdd = 0x61 => "ladd",
     /// Pop the top two value from the stack (they must be of type `float`) then
     /// push their sum into the stack.
-    FAdd,
+    FAdd = 0x62 => "fadd",
     /// Pop the top two value from the stack (they must be of type `double`) then
     /// push their sum into the stack.
-    DAdd,
+    DAdd = 0x63 => "dadd",
     /// Pop the top two value from the stack (they must be of type `int`) then
     /// push their difference into the stack. The result is `value1` - `value2`
     /// and the values are laid as [`value1`, `value2`].
-    ISub,
+    ISub = 0x64 => "isub",
     /// Pop the top two value from the stack (they must be of type `long`) then
     /// push their difference into the stack. The result is `value1` - `value2`
     /// and the values are laid as [`value1`, `value2`].
-    LSub,
+    LSub = 0x65 => "lsub",
     /// Pop the top two value from the stack (they must be of type `float`) then
     /// push their difference into the stack. The result is `value1` - `value2`
     /// and the values are laid as [`value1`, `value2`].
-    FSub,
+    FSub = 0x66 => "fsub",
     /// Pop the top two value from the stack (they must be of type `double`) then
     /// push their difference into the stack. The result is `value1` - `value2`
     /// and the values are laid as [`value1`, `value2`].
-    DSub,
+    DSub = 0x67 => "dsub",
     /// Pop the top two value from the stack (they must be of type `int`) then
     /// push their product into the stack. The result is `value1` * `value2`
     /// and the values are laid as [`value1`, `value2`].
-    IMul,
+    IMul = 0x68 => "imul",
     /// Pop the top two value from the stack (they must be of type `long`) then
     /// push their product into the stack. The result is `value1` * `value2`
     /// and the values are laid as [`value1`, `value2`].
-    LMul,
+    LMul = 0x69 => "lmul",
     /// Pop the top two value from the stack (they must be of type `float`) then
     /// push their product into the stack. The result is `value1` * `value2`
     /// and the values are laid as [`value1`, `value2`].
-    FMul,
+    FMul = 0x6a => "fmul",
     /// Pop the top two value from the stack (they must be of type `double`) then
     /// push their product into the stack. The result is `value1` * `value2`
     /// and the values are laid as [`value1`, `value2`].
-    DMul,
+    DMul = 0x6b => "dmul",
     /// Pop the top two value from the stack (they must be of type `int`) then
     /// push their division into the stack. The result is `value1` / `value2`
     /// and the values are laid as [`value1`, `value2`].
-    IDiv,
+    IDiv = 0x6c => "idiv",
     /// Pop the top two value from the stack (they must be of type `long`) then
     /// push their division into the stack. The result is `value1` / `value2`
     /// and the values are laid as [`value1`, `value2`].
-    LDiv,
+    LDiv = 0x6d => "ldiv",
     /// Pop the top two value from the stack (they must be of type `float`) then
     /// push their division into the stack. The result is `value1` / `value2`
     /// and the values are laid as [`value1`, `value2`].
-    FDiv,
+    FDiv = 0x6e => "fdiv",
     /// Pop the top two value from the stack (they must be of type `double`) then
     /// push their division into the stack. The result is `value1` / `value2`
     /// and the values are laid as [`value1`, `value2`].
-    DDiv,
+    DDiv = 0x6f => "ddiv",
     /// Pop the top two value from the stack (they must be of type `int`) then
     /// push their modulo into the stack. The result is `value1` / `value2`
     /// and the values are laid as [`value1`, `value2`].
-    IRem,
+    IRem = 0x70 => "irem",
     /// Pop the top two value from the stack (they must be of type `long`) then
     /// push their modulo into the stack. The result is `value1` / `value2`
     /// and the values are laid as [`value1`, `value2`].
-    LRem,
+    LRem = 0x71 => "lrem",
     /// Pop the top two value from the stack (they must be of type `float`) then
     /// push their modulo into the stack. The result is `value1` / `value2`
     /// and the values are laid as [`value1`, `value2`].
-    FRem,
+    FRem = 0x72 => "frem",
     /// Pop the top two value from the stack (they must be of type `double`) then
     /// push their modulo into the stack. The result is `value1` / `value2`
     /// and the values are laid as [`value1`, `value2`].
-    DRem,
-    INeg,
-    LNeg,
-    FNeg,
-    DNeg,
-    IShl,
-    LShl,
-    IShr,
-    LShr,
-    IUShr,
-    LUShr,
-    Iand,
-    Land,
-    IOr,
-    LOr,
-    IXor,
-    LXor,
+    DRem = 0x73 => "drem",
+    INeg = 0x74 => "ineg",
+    LNeg = 0x75 => "lneg",
+    FNeg = 0x76 => "fneg",
+    DNeg = 0x77 => "dneg",
+    IShl = 0x78 => "ishl",
+    LShl = 0x79 => "lshl",
+    IShr = 0x7a => "ishr",
+    LShr = 0x7b => "lshr",
+    IUShr = 0x7c => "iushr",
+    LUShr = 0x7d => "lushr",
+    Iand = 0x7e => "iand",
+    Land = 0x7f => "land",
+    IOr = 0x80 => "ior",
+    LOr = 0x81 => "lor",
+    IXor = 0x82 => "ixor",
+    LXor = 0x83 => "lxor",
     /// Increment the value in the local variables array stored at `index` given
     /// as an operand by the constant `const` given as an operand.
-    IInc,
-    I2L,
-    I2F,
-    I2D,
-    L2I,
-    L2F,
-    L2D,
-    F2I,
-    F2L,
-    F2D,
-    D2I,
-    D2L,
-    D2F,
-    I2B,
-    I2C,
-    I2S,
-    LCmp,
-    FCmpL,
-    FCmpG,
-    DCmpL,
-    DCmpG,
+    IInc = 0x84 => "iinc",
+    I2L = 0x85 => "i2l",
+    I2F = 0x86 => "i2f",
+    I2D = 0x87 => "i2d",
+    L2I = 0x88 => "l2i",
+    L2F = 0x89 => "l2f",
+    L2D = 0x8a => "l2d",
+    F2I = 0x8b => "f2i",
+    F2L = 0x8c => "f2l",
+    F2D = 0x8d => "f2d",
+    D2I = 0x8e => "d2i",
+    D2L = 0x8f => "d2l",
+    D2F = 0x90 => "d2f",
+    I2B = 0x91 => "i2b",
+    I2C = 0x92 => "i2c",
+    I2S = 0x93 => "i2s",
+    LCmp = 0x94 => "lcmp",
+    FCmpL = 0x95 => "fcmpl",
+    FCmpG = 0x96 => "fcmpg",
+    DCmpL = 0x97 => "dcmpl",
+    DCmpG = 0x98 => "dcmpg",
     /// Branch to the target offset (given as operand) if the comparison is
     /// true, the compared values are the top value on the stack and 0.
     ///
@@ -329,17 +410,17 @@ pub enum OPCode {
     /// The value must be an `int` and the comparison is signed.
     ///
     /// Branch if `value` is equal to zero.
-    IfEq,
+    IfEq = 0x99 => "ifeq",
     /// Branch if `value` is not equal to zero.
-    IfNe,
+    IfNe = 0x9a => "ifne",
     /// Branch if `value` is less than zero.
-    IfLt,
+    IfLt = 0x9b => "iflt",
     /// Branch if `value` is greater than or equal to zero.
-    IfGe,
+    IfGe = 0x9c => "ifge",
     /// Branch if `value` is greater than zero.
-    IfGt,
+    IfGt = 0x9d => "ifgt",
     /// Branch if `value` is less than or equal to zero.
-    IfLe,
+    IfLe = 0x9e => "ifle",
     /// Branch to the target offset (given as operand) if the comparison is
     /// true, the compared values are the top two values in the stack laid
     /// out as (the values are interpreted as `int`). :
@@ -349,491 +430,460 @@ pub enum OPCode {
     /// All comparisons are signed.
     ///
     /// Branch if the two top values on the stack are equal.
-    IfICmpEq,
+    IfICmpEq = 0x9f => "if_icmpeq",
     /// Branch if the two top values on the stack are not equal.
-    IfICmpNe,
+    IfICmpNe = 0xa0 => "if_icmpne",
     /// Branch if the `value1` is less than `value2`.
-    IfICmpLt,
+    IfICmpLt = 0xa1 => "if_icmplt",
     /// Branch if `value1` is greater or equal than `value2`.
-    IfICmpGe,
+    IfICmpGe = 0xa2 => "if_icmpge",
     /// Branch if `value1` is greater than `value2`.
-    IfICmpGt,
+    IfICmpGt = 0xa3 => "if_icmpgt",
     /// Branch if `value1` is less then or equal `value2`.
-    IfICmpLe,
-    IfACmpEq,
-    IfACmpNe,
+    IfICmpLe = 0xa4 => "if_icmple",
+    IfACmpEq = 0xa5 => "if_acmpeq",
+    IfACmpNe = 0xa6 => "if_acmpne",
     /// Branch to the relative offset given as two 1 byte operands, execution
     /// continues at the relative offset from the address of the opcode of the
     /// goto instruction. The target address must be that of an opcode of an
     /// instruction within the method that contains this `goto` instruction.
-    Goto,
-    Jsr,
-    Ret,
-    TableSwitch,
-    LookupSwitch,
-    IReturn,
-    LReturn,
-    FReturn,
-    DReturn,
-    // REeturn `reference` from method.
-    AReturn,
-    Return,
-    GetStatic,
-    PutStatic,
-    GetField,
-    PutField,
-    InvokeVirtual,
-    InvokeSpecial,
-    InvokeStatic,
-    InvokeInterface,
-    InvokeDynamic,
-    New,
-    NewArray,
-    ANewArray,
+    Goto = 0xa7 => "goto",
+    Jsr = 0xa8 => "jsr",
+    Ret = 0xa9 => "ret",
+    TableSwitch = 0xaa => "tableswitch",
+    LookupSwitch = 0xab => "lookupswitch",
+    IReturn = 0xac => "ireturn",
+    LReturn = 0xad => "lreturn",
+    FReturn = 0xae => "freturn",
+    DReturn = 0xaf => "dreturn",
+    /// Return `reference` from method.
+    AReturn = 0xb0 => "areturn",
+    Return = 0xb1 => "return",
+    GetStatic = 0xb2 => "getstatic",
+    PutStatic = 0xb3 => "putstatic",
+    GetField = 0xb4 => "getfield",
+    PutField = 0xb5 => "putfield",
+    InvokeVirtual = 0xb6 => "invokevirtual",
+    InvokeSpecial = 0xb7 => "invokespecial",
+    InvokeStatic = 0xb8 => "invokestatic",
+    InvokeInterface = 0xb9 => "invokeinterface",
+    InvokeDynamic = 0xba => "invokedynamic",
+    New = 0xbb => "new",
+    NewArray = 0xbc => "newarray",
+    ANewArray = 0xbd => "anewarray",
     /// Pops the `reference` to the array from the stack and push its length
     /// into the stack.
-    ArrayLength,
-    AThrow,
-    CheckCast,
-    InstanceOf,
-    MonitorEnter,
-    MonitorExit,
-    Wide,
-    MultiANewArray,
-    IfNull,
-    IfNonNull,
+    ArrayLength = 0xbe => "arraylength",
+    AThrow = 0xbf => "athrow",
+    CheckCast = 0xc0 => "checkcast",
+    InstanceOf = 0xc1 => "instanceof",
+    MonitorEnter = 0xc2 => "monitorenter",
+    MonitorExit = 0xc3 => "monitorexit",
+    Wide = 0xc4 => "wide",
+    MultiANewArray = 0xc5 => "multianewarray",
+    IfNull = 0xc6 => "ifnull",
+    IfNonNull = 0xc7 => "ifnonnull",
     /// Similar to `goto` but the offset is given as a 4 byte value constructed
     /// from 4 1-byte operands. The constructed target address must be that of
     /// an opcode of an instruction within the method that contains the current
     /// `goto_w` instruction.
-    GotoW,
-    JsrW,
-    Breakpoint,
-    // Proxy value to signal unknown opcode values.
-    Unspecified,
+    GotoW = 0xc8 => "goto_w",
+    JsrW = 0xc9 => "jsr_w",
+    Breakpoint = 0xca => "breakpoint",
 }
 
-impl fmt::Display for OPCode {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+impl OPCode {
+    /// Returns the number of operands of a decoded instruction or `None` for
+    /// variable length instructions (`tableswitch`, `lookupswitch` and
+    /// `wide`).
+    ///
+    /// Branch offsets and constant pool indexes are decoded as a single
+    /// operand, except for the field and method references resolved at
+    /// runtime which keep their two index bytes as separate operands.
+    #[must_use]
+    pub const fn expected_operand_count(&self) -> Option<usize> {
         match self {
-            Self::Nop => write!(f, "nop"),
-            Self::AConstNull => write!(f, "aconst_null"),
-            Self::IconstM1 => write!(f, "iconst_m1"),
-            Self::Iconst0 => write!(f, "iconst_0"),
-            Self::Iconst1 => write!(f, "iconst_1"),
-            Self::Iconst2 => write!(f, "iconst_2"),
-            Self::Iconst3 => write!(f, "iconst_3"),
-            Self::Iconst4 => write!(f, "iconst_4"),
-            Self::Iconst5 => write!(f, "iconst_5"),
-            Self::Lconst0 => write!(f, "lconst_0"),
-            Self::Lconst1 => write!(f, "lconst_1"),
-            Self::Fconst0 => write!(f, "fconst_0"),
-            Self::Fconst1 => write!(f, "fconst_1"),
-            Self::Fconst2 => write!(f, "fconst_2"),
-            Self::Dconst0 => write!(f, "dconst_0"),
-            Self::Dconst1 => write!(f, "dconst_1"),
-            Self::BiPush => write!(f, "bipush"),
-            Self::SiPush => write!(f, "sipush"),
-            Self::Ldc => write!(f, "ldc"),
-            Self::LdcW => write!(f, "ldc_w"),
-            Self::Ldc2W => write!(f, "ldc2_w"),
-            Self::ILoad => write!(f, "iload"),
-            Self::LLoad => write!(f, "lload"),
-            Self::FLoad => write!(f, "fload"),
-            Self::DLoad => write!(f, "dload"),
-            Self::ALoad => write!(f, "aload"),
-            Self::ILoad0 => write!(f, "iload_0"),
-            Self::ILoad1 => write!(f, "iload_1"),
-            Self::ILoad2 => write!(f, "iload_2"),
-            Self::ILoad3 => write!(f, "iload_3"),
-            Self::LLoad0 => write!(f, "lload_0"),
-            Self::LLoad1 => write!(f, "lload_1"),
-            Self::LLoad2 => write!(f, "lload_2"),
-            Self::LLoad3 => write!(f, "lload_3"),
-            Self::FLoad0 => write!(f, "fload_0"),
-            Self::FLoad1 => write!(f, "fload_1"),
-            Self::FLoad2 => write!(f, "fload_2"),
-            Self::FLoad3 => write!(f, "fload_3"),
-            Self::DLoad0 => write!(f, "dload_0"),
-            Self::DLoad1 => write!(f, "dload_1"),
-            Self::DLoad2 => write!(f, "dload_2"),
-            Self::DLoad3 => write!(f, "dload_3"),
-            Self::ALoad0 => write!(f, "aload_0"),
-            Self::ALoad1 => write!(f, "aload_1"),
-            Self::ALoad2 => write!(f, "aload_2"),
-            Self::ALoad3 => write!(f, "aload_3"),
-            Self::IALoad => write!(f, "iaload"),
-            Self::LALoad => write!(f, "laload"),
-            Self::FALoad => write!(f, "faload"),
-            Self::DALoad => write!(f, "daload"),
-            Self::AALoad => write!(f, "aaload"),
-            Self::BALoad => write!(f, "baload"),
-            Self::CALoad => write!(f, "caload"),
-            Self::SALoad => write!(f, "saload"),
-            Self::IStore => write!(f, "istore"),
-            Self::LStore => write!(f, "lstore"),
-            Self::FStore => write!(f, "fstore"),
-            Self::DStore => write!(f, "dstore"),
-            Self::AStore => write!(f, "astore"),
-            Self::IStore0 => write!(f, "istore_0"),
-            Self::IStore1 => write!(f, "istore_1"),
-            Self::IStore2 => write!(f, "istore_2"),
-            Self::IStore3 => write!(f, "istore_3"),
-            Self::LStore0 => write!(f, "lstore_0"),
-            Self::LStore1 => write!(f, "lstore_1"),
-            Self::LStore2 => write!(f, "lstore_2"),
-            Self::LStore3 => write!(f, "lstore_3"),
-            Self::FStore0 => write!(f, "fstore_0"),
-            Self::FStore1 => write!(f, "fstore_1"),
-            Self::FStore2 => write!(f, "fstore_2"),
-            Self::FStore3 => write!(f, "fstore_3"),
-            Self::DStore0 => write!(f, "dstore_0"),
-            Self::DStore1 => write!(f, "dstore_1"),
-            Self::DStore2 => write!(f, "dstore_2"),
-            Self::DStore3 => write!(f, "dstore_3"),
-            Self::AStore0 => write!(f, "astore_0"),
-            Self::AStore1 => write!(f, "astore_1"),
-            Self::AStore2 => write!(f, "astore_2"),
-            Self::AStore3 => write!(f, "astore_3"),
-            Self::IAStore => write!(f, "iastore"),
-            Self::LAStore => write!(f, "lastore"),
-            Self::FAStore => write!(f, "fastore"),
-            Self::DAStore => write!(f, "dastore"),
-            Self::AAStore => write!(f, "aastore"),
-            Self::BAStore => write!(f, "bastore"),
-            Self::CAStore => write!(f, "castore"),
-            Self::SAStore => write!(f, "sastore"),
-            Self::Pop => write!(f, "pop"),
-            Self::Pop2 => write!(f, "pop_2"),
-            Self::Dup => write!(f, "dup"),
-            Self::DupX1 => write!(f, "dup_x1"),
-            Self::DupX2 => write!(f, "dup_x2"),
-            Self::Dup2 => write!(f, "dup2"),
-            Self::Dup2X1 => write!(f, "dup2_x1"),
-            Self::Dup2X2 => write!(f, "dup2_x2"),
-            Self::Swap => write!(f, "swap"),
-            Self::IAdd => write!(f, "iadd"),
-            Self::LAdd => write!(f, "ladd"),
-            Self::FAdd => write!(f, "fadd"),
-            Self::DAdd => write!(f, "dadd"),
-            Self::ISub => write!(f, "isub"),
-            Self::LSub => write!(f, "lsub"),
-            Self::FSub => write!(f, "fsub"),
-            Self::DSub => write!(f, "dsub"),
-            Self::IMul => write!(f, "imul"),
-            Self::LMul => write!(f, "lmul"),
-            Self::FMul => write!(f, "fmul"),
-            Self::DMul => write!(f, "dmul"),
-            Self::IDiv => write!(f, "idiv"),
-            Self::LDiv => write!(f, "ldiv"),
-            Self::FDiv => write!(f, "fdiv"),
-            Self::DDiv => write!(f, "ddiv"),
-            Self::IRem => write!(f, "irem"),
-            Self::LRem => write!(f, "lrem"),
-            Self::FRem => write!(f, "frem"),
-            Self::DRem => write!(f, "drem"),
-            Self::INeg => write!(f, "ineg"),
-            Self::LNeg => write!(f, "lneg"),
-            Self::FNeg => write!(f, "fneg"),
-            Self::DNeg => write!(f, "dneg"),
-            Self::IShl => write!(f, "ishl"),
-            Self::LShl => write!(f, "lshl"),
-            Self::IShr => write!(f, "ishr"),
-            Self::LShr => write!(f, "lshr"),
-            Self::IUShr => write!(f, "iushr"),
-            Self::LUShr => write!(f, "lushr"),
-            Self::Iand => write!(f, "iand"),
-            Self::Land => write!(f, "land"),
-            Self::IOr => write!(f, "ior"),
-            Self::LOr => write!(f, "lor"),
-            Self::IXor => write!(f, "ixor"),
-            Self::LXor => write!(f, "lxor"),
-            Self::IInc => write!(f, "iinc"),
-            Self::I2L => write!(f, "i2l"),
-            Self::I2F => write!(f, "i2f"),
-            Self::I2D => write!(f, "i2d"),
-            Self::L2I => write!(f, "l2i"),
-            Self::L2F => write!(f, "l2f"),
-            Self::L2D => write!(f, "l2d"),
-            Self::F2I => write!(f, "f2i"),
-            Self::F2L => write!(f, "f2l"),
-            Self::F2D => write!(f, "f2d"),
-            Self::D2I => write!(f, "d2i"),
-            Self::D2L => write!(f, "d2l"),
-            Self::D2F => write!(f, "d2f"),
-            Self::I2B => write!(f, "i2b"),
-            Self::I2C => write!(f, "i2c"),
-            Self::I2S => write!(f, "i2s"),
-            Self::LCmp => write!(f, "lcmp"),
-            Self::FCmpL => write!(f, "fcmpl"),
-            Self::FCmpG => write!(f, "fcmpg"),
-            Self::DCmpL => write!(f, "dcmpl"),
-            Self::DCmpG => write!(f, "dcmpg"),
-            Self::IfEq => write!(f, "ifeq"),
-            Self::IfNe => write!(f, "ifne"),
-            Self::IfLt => write!(f, "iflt"),
-            Self::IfGe => write!(f, "ifge"),
-            Self::IfGt => write!(f, "ifgt"),
-            Self::IfLe => write!(f, "ifle"),
-            Self::IfICmpEq => write!(f, "if_icmpeq"),
-            Self::IfICmpNe => write!(f, "if_icmpne"),
-            Self::IfICmpLt => write!(f, "if_icmplt"),
-            Self::IfICmpGe => write!(f, "if_icmpge"),
-            Self::IfICmpGt => write!(f, "if_icmpgt"),
-            Self::IfICmpLe => write!(f, "if_icmple"),
-            Self::IfACmpEq => write!(f, "if_acmpeq"),
-            Self::IfACmpNe => write!(f, "if_acmpne"),
-            Self::Goto => write!(f, "goto"),
-            Self::Jsr => write!(f, "jsr"),
-            Self::Ret => write!(f, "ret"),
-            Self::TableSwitch => write!(f, "tableswitch"),
-            Self::LookupSwitch => write!(f, "lookupswitch"),
-            Self::IReturn => write!(f, "ireturn"),
-            Self::LReturn => write!(f, "lreturn"),
-            Self::FReturn => write!(f, "freturn"),
-            Self::DReturn => write!(f, "dreturn"),
-            Self::AReturn => write!(f, "areturn"),
-            Self::Return => write!(f, "return"),
-            Self::GetStatic => write!(f, "getstatic"),
-            Self::PutStatic => write!(f, "putstatic"),
-            Self::GetField => write!(f, "getfield"),
-            Self::PutField => write!(f, "putfield"),
-            Self::InvokeVirtual => write!(f, "invokevirtual"),
-            Self::InvokeSpecial => write!(f, "invokespecial"),
-            Self::InvokeStatic => write!(f, "invokestatic"),
-            Self::InvokeInterface => write!(f, "invokeinterface"),
-            Self::InvokeDynamic => write!(f, "invokedynamic"),
-            Self::New => write!(f, "new"),
-            Self::NewArray => write!(f, "newarray"),
-            Self::ANewArray => write!(f, "anewarray"),
-            Self::ArrayLength => write!(f, "arraylength"),
-            Self::AThrow => write!(f, "athrow"),
-            Self::CheckCast => write!(f, "checkcast"),
-            Self::InstanceOf => write!(f, "instanceof"),
-            Self::MonitorEnter => write!(f, "monitorenter"),
-            Self::MonitorExit => write!(f, "monitorexit"),
-            Self::Wide => write!(f, "wide"),
-            Self::MultiANewArray => write!(f, "multianewarray"),
-            Self::IfNull => write!(f, "ifnull"),
-            Self::IfNonNull => write!(f, "ifnonnull"),
-            Self::GotoW => write!(f, "goto_w"),
-            Self::JsrW => write!(f, "jsr_w"),
-            Self::Breakpoint => write!(f, "breakpoint"),
-            _ => write!(f, "unspecified"),
+            Self::TableSwitch | Self::LookupSwitch | Self::Wide => None,
+            Self::IInc
+            | Self::GetStatic
+            | Self::PutStatic
+            | Self::GetField
+            | Self::PutField
+            | Self::InvokeVirtual
+            | Self::InvokeSpecial
+            | Self::InvokeInterface
+            | Self::MultiANewArray => Some(2),
+            Self::BiPush
+            | Self::SiPush
+            | Self::Ldc
+            | Self::LdcW
+            | Self::Ldc2W
+            | Self::ILoad
+            | Self::LLoad
+            | Self::FLoad
+            | Self::DLoad
+            | Self::ALoad
+            | Self::IStore
+            | Self::LStore
+            | Self::FStore
+            | Self::DStore
+            | Self::AStore
+            | Self::IfEq
+            | Self::IfNe
+            | Self::IfLt
+            | Self::IfGe
+            | Self::IfGt
+            | Self::IfLe
+            | Self::IfICmpEq
+            | Self::IfICmpNe
+            | Self::IfICmpLt
+            | Self::IfICmpGe
+            | Self::IfICmpGt
+            | Self::IfICmpLe
+            | Self::IfACmpEq
+            | Self::IfACmpNe
+            | Self::Goto
+            | Self::Jsr
+            | Self::Ret
+            | Self::InvokeStatic
+            | Self::InvokeDynamic
+            | Self::New
+            | Self::NewArray
+            | Self::ANewArray
+            | Self::CheckCast
+            | Self::InstanceOf
+            | Self::IfNull
+            | Self::IfNonNull
+            | Self::GotoW
+            | Self::JsrW => Some(1),
+            _ => Some(0),
         }
     }
 }
 
-// Since bytecode is initially loaded as `Vec<u8>` we need a way to convert it
-// to `OPCode` enum, this might be done better with a macro but copy paste and
-// move on for now.
-impl From<u8> for OPCode {
-    fn from(byte: u8) -> Self {
-        match byte {
-            0 => Self::Nop,
-            1 => Self::AConstNull,
-            2 => Self::IconstM1,
-            3 => Self::Iconst0,
-            4 => Self::Iconst1,
-            5 => Self::Iconst2,
-            6 => Self::Iconst3,
-            7 => Self::Iconst4,
-            8 => Self::Iconst5,
-            9 => Self::Lconst0,
-            10 => Self::Lconst1,
-            11 => Self::Fconst0,
-            12 => Self::Fconst1,
-            13 => Self::Fconst2,
-            14 => Self::Dconst0,
-            15 => Self::Dconst1,
-            16 => Self::BiPush,
-            17 => Self::SiPush,
-            18 => Self::Ldc,
-            19 => Self::LdcW,
-            20 => Self::Ldc2W,
-            21 => Self::ILoad,
-            22 => Self::LLoad,
-            23 => Self::FLoad,
-            24 => Self::DLoad,
-            25 => Self::ALoad,
-            26 => Self::ILoad0,
-            27 => Self::ILoad1,
-            28 => Self::ILoad2,
-            29 => Self::ILoad3,
-            30 => Self::LLoad0,
-            31 => Self::LLoad1,
-            32 => Self::LLoad2,
-            33 => Self::LLoad3,
-            34 => Self::FLoad0,
-            35 => Self::FLoad1,
-            36 => Self::FLoad2,
-            37 => Self::FLoad3,
-            38 => Self::DLoad0,
-            39 => Self::DLoad1,
-            40 => Self::DLoad2,
-            41 => Self::DLoad3,
-            42 => Self::ALoad0,
-            43 => Self::ALoad1,
-            44 => Self::ALoad2,
-            45 => Self::ALoad3,
-            46 => Self::IALoad,
-            47 => Self::LALoad,
-            48 => Self::FALoad,
-            49 => Self::DALoad,
-            50 => Self::AALoad,
-            51 => Self::BALoad,
-            52 => Self::CALoad,
-            53 => Self::SALoad,
-            54 => Self::IStore,
-            55 => Self::LStore,
-            56 => Self::FStore,
-            57 => Self::DStore,
-            58 => Self::AStore,
-            59 => Self::IStore0,
-            60 => Self::IStore1,
-            61 => Self::IStore2,
-            62 => Self::IStore3,
-            63 => Self::LStore0,
-            64 => Self::LStore1,
-            65 => Self::LStore2,
-            66 => Self::LStore3,
-            67 => Self::FStore0,
-            68 => Self::FStore1,
-            69 => Self::FStore2,
-            70 => Self::FStore3,
-            71 => Self::DStore0,
-            72 => Self::DStore1,
-            73 => Self::DStore2,
-            74 => Self::DStore3,
-            75 => Self::AStore0,
-            76 => Self::AStore1,
-            77 => Self::AStore2,
-            78 => Self::AStore3,
-            79 => Self::IAStore,
-            80 => Self::LAStore,
-            81 => Self::FAStore,
-            82 => Self::DAStore,
-            83 => Self::AAStore,
-            84 => Self::BAStore,
-            85 => Self::CAStore,
-            86 => Self::SAStore,
-            87 => Self::Pop,
-            88 => Self::Pop2,
-            89 => Self::Dup,
-            90 => Self::DupX1,
-            91 => Self::DupX2,
-            92 => Self::Dup2,
-            93 => Self::Dup2X1,
-            94 => Self::Dup2X2,
-            95 => Self::Swap,
-            96 => Self::IAdd,
-            97 => Self::LAdd,
-            98 => Self::FAdd,
-            99 => Self::DAdd,
-            100 => Self::ISub,
-            101 => Self::LSub,
-            102 => Self::FSub,
-            103 => Self::DSub,
-            104 => Self::IMul,
-            105 => Self::LMul,
-            106 => Self::FMul,
-            107 => Self::DMul,
-            108 => Self::IDiv,
-            109 => Self::LDiv,
-            110 => Self::FDiv,
-            111 => Self::DDiv,
-            112 => Self::IRem,
-            113 => Self::LRem,
-            114 => Self::FRem,
-            115 => Self::DRem,
-            116 => Self::INeg,
-            117 => Self::LNeg,
-            118 => Self::FNeg,
-            119 => Self::DNeg,
-            120 => Self::IShl,
-            121 => Self::LShl,
-            122 => Self::IShr,
-            123 => Self::LShr,
-            124 => Self::IUShr,
-            125 => Self::LUShr,
-            126 => Self::Iand,
-            127 => Self::Land,
-            128 => Self::IOr,
-            129 => Self::LOr,
-            130 => Self::IXor,
-            131 => Self::LXor,
-            132 => Self::IInc,
-            133 => Self::I2L,
-            134 => Self::I2F,
-            135 => Self::I2D,
-            136 => Self::L2I,
-            137 => Self::L2F,
-            138 => Self::L2D,
-            139 => Self::F2I,
-            140 => Self::F2L,
-            141 => Self::F2D,
-            142 => Self::D2I,
-            143 => Self::D2L,
-            144 => Self::D2F,
-            145 => Self::I2B,
-            146 => Self::I2C,
-            147 => Self::I2S,
-            148 => Self::LCmp,
-            149 => Self::FCmpL,
-            150 => Self::FCmpG,
-            151 => Self::DCmpL,
-            152 => Self::DCmpG,
-            153 => Self::IfEq,
-            154 => Self::IfNe,
-            155 => Self::IfLt,
-            156 => Self::IfGe,
-            157 => Self::IfGt,
-            158 => Self::IfLe,
-            159 => Self::IfICmpEq,
-            160 => Self::IfICmpNe,
-            161 => Self::IfICmpLt,
-            162 => Self::IfICmpGe,
-            163 => Self::IfICmpGt,
-            164 => Self::IfICmpLe,
-            165 => Self::IfACmpEq,
-            166 => Self::IfACmpNe,
-            167 => Self::Goto,
-            168 => Self::Jsr,
-            169 => Self::Ret,
-            170 => Self::TableSwitch,
-            171 => Self::LookupSwitch,
-            172 => Self::IReturn,
-            173 => Self::LReturn,
-            174 => Self::FReturn,
-            175 => Self::DReturn,
-            176 => Self::AReturn,
-            177 => Self::Return,
-            178 => Self::GetStatic,
-            179 => Self::PutStatic,
-            180 => Self::GetField,
-            181 => Self::PutField,
-            182 => Self::InvokeVirtual,
-            183 => Self::InvokeSpecial,
-            184 => Self::InvokeStatic,
-            185 => Self::InvokeInterface,
-            186 => Self::InvokeDynamic,
-            187 => Self::New,
-            188 => Self::NewArray,
-            189 => Self::ANewArray,
-            190 => Self::ArrayLength,
-            191 => Self::AThrow,
-            192 => Self::CheckCast,
-            193 => Self::InstanceOf,
-            194 => Self::MonitorEnter,
-            195 => Self::MonitorExit,
-            196 => Self::Wide,
-            197 => Self::MultiANewArray,
-            198 => Self::IfNull,
-            199 => Self::IfNonNull,
-            200 => Self::GotoW,
-            201 => Self::JsrW,
-            202 => Self::Breakpoint,
-            203..=u8::MAX => Self::Unspecified,
-        }
+impl OPCode {
+    /// Returns whether the opcode transfers control to a branch target,
+    /// conditionally or not.
+    #[must_use]
+    pub const fn is_branch(&self) -> bool {
+        matches!(
+            self,
+            Self::IfEq
+                | Self::IfNe
+                | Self::IfLt
+                | Self::IfGe
+                | Self::IfGt
+                | Self::IfLe
+                | Self::IfICmpEq
+                | Self::IfICmpNe
+                | Self::IfICmpLt
+                | Self::IfICmpGe
+                | Self::IfICmpGt
+                | Self::IfICmpLe
+                | Self::IfACmpEq
+                | Self::IfACmpNe
+                | Self::IfNull
+                | Self::IfNonNull
+                | Self::Goto
+                | Self::GotoW
+                | Self::Jsr
+                | Self::JsrW
+                | Self::Ret
+                | Self::TableSwitch
+                | Self::LookupSwitch
+        )
+    }
+
+    /// Returns whether the opcode returns from the current method.
+    #[must_use]
+    pub const fn is_return(&self) -> bool {
+        matches!(
+            self,
+            Self::IReturn
+                | Self::LReturn
+                | Self::FReturn
+                | Self::DReturn
+                | Self::AReturn
+                | Self::Return
+        )
+    }
+
+    /// Returns whether the opcode invokes a method.
+    #[must_use]
+    pub const fn is_invoke(&self) -> bool {
+        matches!(
+            self,
+            Self::InvokeVirtual
+                | Self::InvokeSpecial
+                | Self::InvokeStatic
+                | Self::InvokeInterface
+                | Self::InvokeDynamic
+        )
+    }
+
+    /// Returns the net change in operand stack slots of the current frame
+    /// after executing the opcode, `long` and `double` values take two slots.
+    ///
+    /// Returns `None` when the change depends on the constant pool or the
+    /// operands (field accesses, invocations, `multianewarray` and `wide`)
+    /// or when the stack is discarded (`athrow`).
+    #[must_use]
+    pub const fn stack_effect(&self) -> Option<i32> {
+        let effect = match self {
+            Self::Nop
+            | Self::INeg
+            | Self::LNeg
+            | Self::FNeg
+            | Self::DNeg
+            | Self::IInc
+            | Self::I2F
+            | Self::L2D
+            | Self::F2I
+            | Self::D2L
+            | Self::I2B
+            | Self::I2C
+            | Self::I2S
+            | Self::Swap
+            | Self::LALoad
+            | Self::DALoad
+            | Self::Goto
+            | Self::GotoW
+            | Self::Ret
+            | Self::Return
+            | Self::NewArray
+            | Self::ANewArray
+            | Self::ArrayLength
+            | Self::CheckCast
+            | Self::InstanceOf
+            | Self::Breakpoint => 0,
+            Self::AConstNull
+            | Self::IconstM1
+            | Self::Iconst0
+            | Self::Iconst1
+            | Self::Iconst2
+            | Self::Iconst3
+            | Self::Iconst4
+            | Self::Iconst5
+            | Self::Fconst0
+            | Self::Fconst1
+            | Self::Fconst2
+            | Self::BiPush
+            | Self::SiPush
+            | Self::Ldc
+            | Self::LdcW
+            | Self::ILoad
+            | Self::FLoad
+            | Self::ALoad
+            | Self::ILoad0
+            | Self::ILoad1
+            | Self::ILoad2
+            | Self::ILoad3
+            | Self::FLoad0
+            | Self::FLoad1
+            | Self::FLoad2
+            | Self::FLoad3
+            | Self::ALoad0
+            | Self::ALoad1
+            | Self::ALoad2
+            | Self::ALoad3
+            | Self::Dup
+            | Self::DupX1
+            | Self::DupX2
+            | Self::I2L
+            | Self::I2D
+            | Self::F2L
+            | Self::F2D
+            | Self::Jsr
+            | Self::JsrW
+            | Self::New => 1,
+            Self::Lconst0
+            | Self::Lconst1
+            | Self::Dconst0
+            | Self::Dconst1
+            | Self::Ldc2W
+            | Self::LLoad
+            | Self::DLoad
+            | Self::LLoad0
+            | Self::LLoad1
+            | Self::LLoad2
+            | Self::LLoad3
+            | Self::DLoad0
+            | Self::DLoad1
+            | Self::DLoad2
+            | Self::DLoad3
+            | Self::Dup2
+            | Self::Dup2X1
+            | Self::Dup2X2 => 2,
+            Self::IALoad
+            | Self::FALoad
+            | Self::AALoad
+            | Self::BALoad
+            | Self::CALoad
+            | Self::SALoad
+            | Self::IStore
+            | Self::FStore
+            | Self::AStore
+            | Self::IStore0
+            | Self::IStore1
+            | Self::IStore2
+            | Self::IStore3
+            | Self::FStore0
+            | Self::FStore1
+            | Self::FStore2
+            | Self::FStore3
+            | Self::AStore0
+            | Self::AStore1
+            | Self::AStore2
+            | Self::AStore3
+            | Self::Pop
+            | Self::IAdd
+            | Self::FAdd
+            | Self::ISub
+            | Self::FSub
+            | Self::IMul
+            | Self::FMul
+            | Self::IDiv
+            | Self::FDiv
+            | Self::IRem
+            | Self::FRem
+            | Self::IShl
+            | Self::LShl
+            | Self::IShr
+            | Self::LShr
+            | Self::IUShr
+            | Self::LUShr
+            | Self::Iand
+            | Self::IOr
+            | Self::IXor
+            | Self::L2I
+            | Self::L2F
+            | Self::D2I
+            | Self::D2F
+            | Self::FCmpL
+            | Self::FCmpG
+            | Self::IfEq
+            | Self::IfNe
+            | Self::IfLt
+            | Self::IfGe
+            | Self::IfGt
+            | Self::IfLe
+            | Self::IfNull
+            | Self::IfNonNull
+            | Self::TableSwitch
+            | Self::LookupSwitch
+            | Self::IReturn
+            | Self::FReturn
+            | Self::AReturn
+            | Self::MonitorEnter
+            | Self::MonitorExit => -1,
+            Self::LStore
+            | Self::DStore
+            | Self::LStore0
+            | Self::LStore1
+            | Self::LStore2
+            | Self::LStore3
+            | Self::DStore0
+            | Self::DStore1
+            | Self::DStore2
+            | Self::DStore3
+            | Self::Pop2
+            | Self::LAdd
+            | Self::DAdd
+            | Self::LSub
+            | Self::DSub
+            | Self::LMul
+            | Self::DMul
+            | Self::LDiv
+            | Self::DDiv
+            | Self::LRem
+            | Self::DRem
+            | Self::Land
+            | Self::LOr
+            | Self::LXor
+            | Self::IfICmpEq
+            | Self::IfICmpNe
+            | Self::IfICmpLt
+            | Self::IfICmpGe
+            | Self::IfICmpGt
+            | Self::IfICmpLe
+            | Self::IfACmpEq
+            | Self::IfACmpNe
+            | Self::LReturn
+            | Self::DReturn => -2,
+            Self::IAStore
+            | Self::FAStore
+            | Self::AAStore
+            | Self::BAStore
+            | Self::CAStore
+            | Self::SAStore
+            | Self::LCmp
+            | Self::DCmpL
+            | Self::DCmpG => -3,
+            Self::LAStore | Self::DAStore => -4,
+            Self::GetStatic
+            | Self::PutStatic
+            | Self::GetField
+            | Self::PutField
+            | Self::InvokeVirtual
+            | Self::InvokeSpecial
+            | Self::InvokeStatic
+            | Self::InvokeInterface
+            | Self::InvokeDynamic
+            | Self::AThrow
+            | Self::Wide
+            | Self::MultiANewArray
+            | Self::Unspecified => return None,
+        };
+        Some(effect)
+    }
+}
+
+impl fmt::Display for OPCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.mnemonic())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_get_expected_operand_count() {
+        assert_eq!(OPCode::IAdd.expected_operand_count(), Some(0));
+        assert_eq!(OPCode::ILoad0.expected_operand_count(), Some(0));
+        assert_eq!(OPCode::ILoad.expected_operand_count(), Some(1));
+        assert_eq!(OPCode::GotoW.expected_operand_count(), Some(1));
+        assert_eq!(OPCode::IInc.expected_operand_count(), Some(2));
+        assert_eq!(OPCode::TableSwitch.expected_operand_count(), None);
+        assert_eq!(OPCode::LookupSwitch.expected_operand_count(), None);
+    }
+
+    #[test]
+    fn can_classify_opcodes() {
+        assert!(OPCode::IfICmpGe.is_branch());
+        assert!(OPCode::GotoW.is_branch());
+        assert!(OPCode::TableSwitch.is_branch());
+        assert!(!OPCode::IAdd.is_branch());
+        assert!(!OPCode::IReturn.is_branch());
+        assert!(OPCode::IReturn.is_return());
+        assert!(OPCode::Return.is_return());
+        assert!(!OPCode::Goto.is_return());
+        assert!(OPCode::InvokeSpecial.is_invoke());
+        assert!(!OPCode::IReturn.is_invoke());
+    }
+
+    #[test]
+    fn can_get_stack_effect() {
+        assert_eq!(OPCode::Iconst0.stack_effect(), Some(1));
+        assert_eq!(OPCode::LLoad1.stack_effect(), Some(2));
+        assert_eq!(OPCode::IAdd.stack_effect(), Some(-1));
+        assert_eq!(OPCode::LAdd.stack_effect(), Some(-2));
+        assert_eq!(OPCode::LShl.stack_effect(), Some(-1));
+        assert_eq!(OPCode::LCmp.stack_effect(), Some(-3));
+        assert_eq!(OPCode::IfICmpGe.stack_effect(), Some(-2));
+        assert_eq!(OPCode::Goto.stack_effect(), Some(0));
+        assert_eq!(OPCode::InvokeStatic.stack_effect(), None);
+        assert_eq!(format!("{}", OPCode::IfICmpGe), "if_icmpge");
     }
 }
@@ -1,8 +1,11 @@
 //! JIT compiler for coldrew targeting x86_64.
-use std::collections::{HashMap, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 
+#[cfg(target_arch = "aarch64")]
+use crate::arm64::RegisterSet;
 use crate::bytecode::OPCode;
-use crate::runtime::{Frame, ProgramCounter, Value};
+use crate::program::BaseTypeKind;
+use crate::runtime::{FrameView, Instruction, ProgramCounter, Value};
 use crate::trace::Trace;
 
 use dynasmrt::x64::Assembler;
@@ -43,6 +46,22 @@ enum Register {
     R15,
 }
 
+impl Register {
+    /// Returns whether the register must be preserved across calls.
+    const fn is_callee_saved(self) -> bool {
+        matches!(
+            self,
+            Self::Rbx
+                | Self::Rsp
+                | Self::Rbp
+                | Self::R12
+                | Self::R13
+                | Self::R14
+                | Self::R15
+        )
+    }
+}
+
 /// Intel x86-64 shorthand for instructions.
 #[allow(dead_code)]
 #[derive(Debug, Clone, Copy)]
@@ -70,16 +89,26 @@ enum Operand {
     Memory(Register, i32),
 }
 
-/// x86_64 function prologue, allocates `max_locals` space on the stack even
-/// though they might not be all used.
+/// x86_64 function prologue, saves the callee-saved registers used by the
+/// trace and allocates `frame_size` bytes on the stack for the saved arguments
+/// and the spill slots.
+///
+/// On aarch64 the callee-saved registers are taken from the `RegisterSet` of
+/// the registers allocated to the trace.
 macro_rules! prologue {
-    ($ops:ident) => {{
+    ($ops:ident, $saved:expr, $arm64_allocated:expr, $frame_size:expr) => {{
         #[cfg(target_arch = "x86_64")]
         {
         let start = $ops.offset();
+        for reg in $saved.iter() {
+            dynasm!($ops
+                ; push Rq(*reg as u8)
+            );
+        }
         dynasm!($ops
             ; push rbp
             ; mov rbp, rsp
+            ; sub rsp, $frame_size
             ; mov QWORD [rbp-24], rdi
             ; mov QWORD [rbp-32], rsi
         );
@@ -88,6 +117,13 @@ macro_rules! prologue {
         #[cfg(target_arch = "aarch64")]
         {
         let start = $ops.offset();
+        // Callee-saved registers (X19 to X28) are saved in pairs, odd counts
+        // are padded with `xzr`.
+        for (first, second) in $arm64_allocated.callee_saved_pairs() {
+            dynasm!($ops
+                ; stp X(first), X(second), [sp, #-16]!
+            );
+        }
         dynasm!($ops
             ; sub sp, sp, #32
             ; str x0, [sp, 8]
@@ -98,30 +134,142 @@ macro_rules! prologue {
     }};
 }
 
-/// aarch64 function epilogue.
+/// Function epilogue, releases the stack frame and restores the callee-saved
+/// registers saved by the prologue in reverse order.
 macro_rules! epilogue {
-    ($ops:ident) => {{
+    ($ops:ident, $saved:expr, $arm64_allocated:expr) => {{
         let epilogue = $ops.offset();
         #[cfg(target_arch = "x86_64")]
+        {
         dynasm!($ops
+            ; mov rsp, rbp
             ; pop rbp
+        );
+        for reg in $saved.iter().rev() {
+            dynasm!($ops
+                ; pop Rq(*reg as u8)
+            );
+        }
+        dynasm!($ops
             ; ret
         );
+        }
         #[cfg(target_arch = "aarch64")]
+        {
         dynasm!($ops
             // Increment stack pointer to go back to where we were
             // before the function call.
             ; add sp, sp, #32
+        );
+        for (first, second) in
+            $arm64_allocated.callee_saved_pairs().into_iter().rev()
+        {
+            dynasm!($ops
+                ; ldp X(first), X(second), [sp], #16
+            );
+        }
+        dynasm!($ops
             ; ret
         );
+        }
         epilogue
     }};
 }
 
 /// `NativeTrace` is a pair of `usize` and `Assembler` that represents an entry
-/// point in the `Assembler` buffer.
+/// point in the `Assembler` buffer, along with the types of the locals stored
+/// by the trace and the link of its loop exit guard if it has one.
 #[derive(Debug)]
-pub struct NativeTrace(AssemblyOffset, ExecutableBuffer);
+pub struct NativeTrace(
+    AssemblyOffset,
+    ExecutableBuffer,
+    HashMap<usize, BaseTypeKind>,
+    Option<ExitLink>,
+);
+
+impl NativeTrace {
+    /// Returns a listing of the x86-64 instructions of the trace in Intel
+    /// syntax, one instruction per line prefixed by its address, the trace
+    /// entry point (prologue) is marked with `entry:`.
+    #[cfg(all(feature = "disasm", target_arch = "x86_64"))]
+    pub fn disassemble(&self) -> String {
+        use iced_x86::{Decoder, DecoderOptions, Formatter, IntelFormatter};
+        use std::fmt::Write;
+
+        let NativeTrace(entry, buf, ..) = self;
+        let base = buf.ptr(AssemblyOffset(0)) as u64;
+        let entry = buf.ptr(*entry) as u64;
+        let mut decoder = Decoder::with_ip(64, buf, base, DecoderOptions::NONE);
+        let mut formatter = IntelFormatter::new();
+        let mut listing = String::new();
+        let mut output = String::new();
+        for inst in &mut decoder {
+            if inst.ip() == entry {
+                listing.push_str("entry:\n");
+            }
+            output.clear();
+            formatter.format(&inst, &mut output);
+            writeln!(listing, "{:016x} {output}", inst.ip())
+                .expect("Expected writing to a `String` to succeed");
+        }
+        listing
+    }
+}
+
+/// `ExitLink` describes the patchable jump of the loop exit guard of a native
+/// trace, when linked the guard jumps into the native trace compiled at its
+/// exit program counter instead of returning to the interpreter.
+#[derive(Debug, Clone, Copy)]
+struct ExitLink {
+    // Program counter the loop exit guard exits at.
+    exit_pc: ProgramCounter,
+    // Offset of the 8 byte entry address of the linked trace in the code of
+    // the trace, the address is zero when unlinked.
+    slot: AssemblyOffset,
+    // Whether the guard jumps into the native trace at `exit_pc`.
+    linked: bool,
+}
+
+// Default upper bound on the size of the code of all cached native traces.
+const DEFAULT_MAX_CODE_CACHE_SIZE: usize = 1 << 20;
+
+/// Encodes the program counter a native trace exits at into the 64-bit value
+/// returned in `rax`, the method index takes the upper 32 bits and the
+/// instruction index the lower 32 bits.
+const fn encode_exit_pc(pc: ProgramCounter) -> u64 {
+    (pc.method_index as u64) << 32 | (pc.instruction_index as u64 & 0xffff_ffff)
+}
+
+/// Decodes the program counter returned by a native trace.
+const fn decode_exit_pc(exit: u64) -> ProgramCounter {
+    ProgramCounter {
+        method_index: (exit >> 32) as usize,
+        instruction_index: (exit & 0xffff_ffff) as usize,
+    }
+}
+
+/// Returns the conditional branch taken exactly when `cond` is not.
+const fn flip_branch(cond: OPCode) -> OPCode {
+    match cond {
+        OPCode::IfEq => OPCode::IfNe,
+        OPCode::IfNe => OPCode::IfEq,
+        OPCode::IfLt => OPCode::IfGe,
+        OPCode::IfGe => OPCode::IfLt,
+        OPCode::IfGt => OPCode::IfLe,
+        OPCode::IfLe => OPCode::IfGt,
+        OPCode::IfICmpEq => OPCode::IfICmpNe,
+        OPCode::IfICmpNe => OPCode::IfICmpEq,
+        OPCode::IfICmpLt => OPCode::IfICmpGe,
+        OPCode::IfICmpGe => OPCode::IfICmpLt,
+        OPCode::IfICmpGt => OPCode::IfICmpLe,
+        OPCode::IfICmpLe => OPCode::IfICmpGt,
+        other => other,
+    }
+}
+
+// Offset from `rbp` of the first spill slot, the slots above it hold the
+// arguments saved by the prologue.
+const SPILL_SLOTS_OFFSET: i32 = -40;
 
 /// `JitCache` is responsible for compiling, caching and executing the native
 /// traces.
@@ -129,10 +277,19 @@ pub struct NativeTrace(AssemblyOffset, ExecutableBuffer);
 /// The calling convention for our Jit is the following :
 ///
 /// - Rdi & Rsi are used to pass input arguments which are the local variables
-/// in the current frame and a guard program counter which is the entry point
-/// of our native trace.
+///   in the current frame and a guard program counter which is the entry point
+///   of our native trace.
+///
+/// - Rax, Rbx, Rcx and R9-R15 are used for intermediate operations, the
+///   callee-saved registers among them (Rbx and R12-R15) are saved in the
+///   prologue and restored in the epilogue when a trace uses them.
 ///
-/// - Rax, Rbx, Rcx and R9-R15 are used for intermediate operations.
+/// - When all registers are live values are spilled to 8 byte slots on the
+///   native stack below `rbp`, Rdx is used as a scratch register for
+///   operations whose operands are all in memory.
+///
+/// - Moves copy full 8 byte slots but `int` operations use 32-bit operands
+///   on the low half of a slot so they wrap like Java `int` arithmetic.
 ///
 /// Since every trace is self contained all register allocation is local and
 /// done with a simple queue based scheme.
@@ -145,6 +302,25 @@ pub struct JitCache {
     traces: HashMap<ProgramCounter, NativeTrace>,
     // Cache of `pc` entries to labels.
     labels: HashMap<ProgramCounter, DynamicLabel>,
+    // Callee-saved registers used by the trace being compiled.
+    used_callee_saved: Vec<Register>,
+    // Registers allocated to the trace being compiled, register numbers name
+    // the `X` registers on aarch64.
+    #[cfg(target_arch = "aarch64")]
+    arm64_allocated: RegisterSet,
+    // Number of spill slots reserved by the trace being compiled.
+    spill_slots: i32,
+    // Offsets of the reserved spill slots that hold no live value.
+    free_spill_slots: Vec<i32>,
+    // Number of traces compiled so far.
+    compiled_count: usize,
+    // Upper bound on the size of the code of all cached native traces, when
+    // exceeded the least recently executed traces are evicted.
+    max_code_cache_size: usize,
+    // Logical clock of the last execution (or compilation) of each trace.
+    last_executed: HashMap<ProgramCounter, u64>,
+    // Logical clock incremented on every trace execution.
+    clock: u64,
 }
 
 impl Default for JitCache {
@@ -174,54 +350,126 @@ impl JitCache {
             traces: HashMap::new(),
             operands: Vec::new(),
             labels: HashMap::new(),
+            used_callee_saved: Vec::new(),
+            #[cfg(target_arch = "aarch64")]
+            arm64_allocated: RegisterSet::default(),
+            spill_slots: 0,
+            free_spill_slots: Vec::new(),
+            compiled_count: 0,
+            max_code_cache_size: DEFAULT_MAX_CODE_CACHE_SIZE,
+            last_executed: HashMap::new(),
+            clock: 0,
         }
     }
 
-    /// Execute the trace at `pc` and return the mutated locals for the frame
-    /// and the program counter where the runtime should continue execution.
-    ///
-    /// Ideally we can just return the updated `locals` and exit but for now
-    /// let's take in the entire execution frame of VM and update it.
-    ///
-    /// Following the x86-64 convention the locals are passed in `rdi`, exit
-    /// information is passed in `rsi`.
-    pub fn execute(&mut self, pc: ProgramCounter, frame: &mut Frame) -> usize {
-        if self.traces.contains_key(&pc) {
-            // execute the assembled trace.
-            let trace = self
-                .traces
-                .get_mut(&pc)
-                .expect("Expected a native trace @ {pc}");
+    /// Create a new JIT cache whose native traces take at most
+    /// `max_code_cache_size` bytes.
+    pub fn with_max_code_cache_size(max_code_cache_size: usize) -> Self {
+        Self {
+            max_code_cache_size,
+            ..Self::new()
+        }
+    }
 
-            // Flatten the locals `HashMap` into a `i32` slice.
-            let mut locals = vec![0i32; frame.max_locals as usize * 8];
+    /// Execute the trace at `pc`, update the locals and the program counter
+    /// of the frame and return the program counter where the runtime should
+    /// continue execution.
+    ///
+    /// Following the x86-64 convention the locals are passed in `rdi` as an
+    /// array of 8 byte slots, exit information is passed in `rsi`. The exit
+    /// program counter is returned in `rax`, see `encode_exit_pc`.
+    pub(crate) fn execute(
+        &mut self,
+        pc: ProgramCounter,
+        frame: &mut FrameView,
+    ) -> ProgramCounter {
+        if let Some(trace) = self.traces.get(&pc) {
+            // Flatten the locals `HashMap` into an `i64` slice, each local
+            // takes a full 8 byte slot so `long` values aren't truncated and
+            // floating point values are kept as their bit patterns.
+            let mut locals = vec![0i64; frame.max_locals as usize];
             // Exit information, for now is empty.
             let exits = [0i32; 0];
 
             for (key, val) in frame.locals.iter() {
-                locals[*key] = match val {
-                    Value::Int(x) => *x,
-                    Value::Long(x) => *x as i32,
-                    Value::Float(x) => *x as i32,
-                    Value::Double(x) => *x as i32,
-                };
+                locals[*key] = Self::slot(val);
+            }
+
+            // Follow the stitched traces that can run after this one.
+            let mut stitched_types = HashMap::new();
+            let mut chain = HashSet::from([pc]);
+            let mut next = trace.3.filter(|link| link.linked);
+            while let Some(link) =
+                next.filter(|link| chain.insert(link.exit_pc))
+            {
+                let stitched = &self.traces[&link.exit_pc];
+                for (index, kind) in stitched.2.iter() {
+                    stitched_types.entry(*index).or_insert(*kind);
+                }
+                next = stitched.3.filter(|link| link.linked);
             }
 
             let entry = trace.0;
             let buf = &trace.1;
-            let execute: fn(*mut i32, *const i32) -> i32 =
+            let execute: fn(*mut i64, *const i32) -> u64 =
                 unsafe { std::mem::transmute(buf.ptr(entry)) };
 
-            let exit_pc = execute(locals.as_mut_ptr(), exits.as_ptr()) as usize;
-            frame.locals.clear();
+            let exit_pc =
+                decode_exit_pc(execute(locals.as_mut_ptr(), exits.as_ptr()));
+            // Write back the locals with the type of their last store in the
+            // trace, locals the trace doesn't store keep their original type.
             for (index, value) in locals.iter().enumerate() {
-                frame.locals.insert(index, Value::Int(*value));
+                let original = frame.locals.get(&index);
+                let unchanged = original
+                    .is_some_and(|original| Self::slot(original) == *value);
+                // Stitched traces may not have run, the locals they store
+                // that are left unchanged keep their original type.
+                let stored = trace.2.get(&index).or_else(|| {
+                    stitched_types.get(&index).filter(|_| !unchanged)
+                });
+                let original = match original {
+                    Some(Value::Long(_)) => BaseTypeKind::Long,
+                    Some(Value::Float(_)) => BaseTypeKind::Float,
+                    Some(Value::Double(_)) => BaseTypeKind::Double,
+                    Some(Value::Reference(_)) => BaseTypeKind::Reference,
+                    _ => BaseTypeKind::Int,
+                };
+                let value = match stored.unwrap_or(&original) {
+                    BaseTypeKind::Long => Value::Long(*value),
+                    BaseTypeKind::Float => {
+                        Value::Float(f32::from_bits(*value as u32))
+                    }
+                    BaseTypeKind::Double => {
+                        Value::Double(f64::from_bits(*value as u64))
+                    }
+                    BaseTypeKind::Reference => {
+                        Value::Reference(usize::try_from(*value).ok())
+                    }
+                    _ => Value::Int(*value as i32),
+                };
+                frame.locals.insert(index, value);
             }
 
-            frame.pc.instruction_index = exit_pc as usize;
+            self.clock += 1;
+            for pc in chain {
+                self.last_executed.insert(pc, self.clock);
+            }
+            *frame.pc = exit_pc;
             exit_pc
         } else {
-            pc.get_instruction_index()
+            pc
+        }
+    }
+
+    /// Returns the 8 byte slot holding `value` in the locals of a native trace.
+    fn slot(value: &Value) -> i64 {
+        match *value {
+            Value::Float(v) => i64::from(v.to_bits()),
+            Value::Double(v) => v.to_bits() as i64,
+            // Null references are kept as -1.
+            Value::Reference(r) => r.map_or(-1, |r| r as i64),
+            _ => i64::try_from(value.to_long())
+                .expect("Expected `to_long` to return a `long`"),
         }
     }
 
@@ -230,19 +478,86 @@ impl JitCache {
         self.traces.contains_key(&pc)
     }
 
+    /// Returns the number of traces compiled so far.
+    pub fn compiled_count(&self) -> usize {
+        self.compiled_count
+    }
+
+    /// Returns the size in bytes of the code of all cached native traces.
+    pub fn total_code_bytes(&self) -> usize {
+        self.traces.values().map(|trace| trace.1.len()).sum()
+    }
+
+    /// Returns the native traces compiled so far and the program counter of
+    /// the loop header each of them starts at.
+    pub fn native_traces(
+        &self,
+    ) -> impl Iterator<Item = (ProgramCounter, &NativeTrace)> {
+        self.traces.iter().map(|(pc, trace)| (*pc, trace))
+    }
+
+    /// Returns the listing of the native trace at this `pc`, see
+    /// `NativeTrace::disassemble`.
+    #[cfg(all(feature = "disasm", target_arch = "x86_64"))]
+    pub fn disassemble_trace(&self, pc: ProgramCounter) -> Option<String> {
+        self.traces.get(&pc).map(NativeTrace::disassemble)
+    }
+
+    /// Evict the native trace at this `pc`, returns whether a trace existed.
+    ///
+    /// The traces stitched to the evicted trace return to the interpreter
+    /// instead.
+    pub fn evict(&mut self, pc: ProgramCounter) -> bool {
+        self.last_executed.remove(&pc);
+        let stitched: Vec<ProgramCounter> = self
+            .traces
+            .iter()
+            .filter(|(_, trace)| {
+                trace
+                    .3
+                    .is_some_and(|link| link.linked && link.exit_pc == pc)
+            })
+            .map(|(from, _)| *from)
+            .collect();
+        for from in stitched {
+            self.patch_exit_link(from, None);
+        }
+        self.traces.remove(&pc).is_some()
+    }
+
+    /// Evict the least recently executed traces until `size` more bytes fit
+    /// in the code cache, returns the evicted traces.
+    fn make_room(&mut self, size: usize) -> Vec<ProgramCounter> {
+        let mut evicted = Vec::new();
+        while !self.traces.is_empty()
+            && self.total_code_bytes() + size > self.max_code_cache_size
+        {
+            let lru = *self
+                .traces
+                .keys()
+                .min_by_key(|pc| self.last_executed.get(pc))
+                .expect("Expected a cached trace");
+            self.evict(lru);
+            evicted.push(lru);
+        }
+        evicted
+    }
+
     /// Compile the trace given as argument and prepare a native trace
     /// for execution.
     ///
     /// Compile works as follows :
     /// 1. Build a dynasmrt Assembler object.
-    /// 2. Emits a static prologue for the jitted code.
-    /// 3. For each recorded instruction generate its equivalent x86 or arm64
+    /// 2. For each recorded instruction generate its equivalent x86 or arm64
     ///    instruction and create a label for it.
-    ///   3.1 If the instruction is a jump i.e `Goto` check if we have a label
-    ///   for it, since all recorded traces are straight lines with backward
-    ///   jumps we must have one, then emit the equivalent jump with the label
-    ///   as the target.
-    /// 4. Emits a static epilogue for the jitted code.
+    ///    2.1 If the instruction is a jump i.e `Goto` check if we have a label
+    ///    for it, since all recorded traces are straight lines with backward
+    ///    jumps we must have one, then emit the equivalent jump with the label
+    ///    as the target.
+    /// 3. Emits the epilogue for the jitted code.
+    /// 4. Emits the prologue which is the entry point of the native trace
+    ///    followed by a jump to the trace body, the prologue is emitted last
+    ///    since it needs to save the callee-saved registers used by the body.
     /// 5. When a trace recording is looked, run the jitted code.
     ///
     /// When we run the trace we need to return PC at which the interpreter
@@ -250,33 +565,72 @@ impl JitCache {
     ///
     /// How jumps are handled (in more details) :
     /// 1. At each trace.instruction()
-    ///     1.1 Create a DynasmLabel `inst_label_{pc}`
-    ///     1.2 Append the new label to the `global_jump_table`
+    ///    1.1 Create a DynasmLabel `inst_label_{pc}`
+    ///    1.2 Append the new label to the `global_jump_table`
     /// 2. If the trace.instruction() is a branch:
-    ///     1.1 Check if we have an existing entry in the `global_jump_table`.
-    ///     1.2 If an entry exists it means we've compiled a trace for this block.
-    ///         1.2.1 Fetch the label and mark the native trace with this label
-    ///         the trace will either be stitched if the jump is outside this trace
-    ///         or it will be local if it is inside this trace.
-    ///     1.3 If an entry doesn't exists it means we're exiting the JIT so we
-    ///     preserve the target `pc` in `rax` and return, when calling `execute`
-    ///     the assumption is that we will always exit back to the interpreter
-    ///     since we currently don't support trace stitching.
-    pub fn compile(&mut self, recording: &Trace) {
+    ///    1.1 Check if we have an existing entry in the `global_jump_table`.
+    ///    1.2 If an entry exists it means we've compiled a trace for this block.
+    ///    1.2.1 Fetch the label and mark the native trace with this label
+    ///    the trace will either be stitched if the jump is outside this trace
+    ///    or it will be local if it is inside this trace.
+    ///    1.3 If an entry doesn't exists it means we're exiting the JIT so we
+    ///    preserve the target `pc` in `rax` and return, when calling `execute`
+    ///    the assumption is that we will always exit back to the interpreter
+    ///    since we currently don't support trace stitching.
+    ///
+    /// Returns the program counters of the traces evicted to make room for
+    /// the new trace in the code cache. Traces with instructions that can't
+    /// be compiled aren't cached and keep running in the interpreter.
+    pub fn compile(&mut self, recording: &Trace) -> Vec<ProgramCounter> {
+        if !recording
+            .iter()
+            .all(|entry| Self::can_compile(entry.instruction()))
+            || !Self::exits_keep_operands(recording)
+        {
+            return Vec::new();
+        }
         // Reset Jit state.
         let pc = recording.start;
         let mut ops = dynasmrt::x64::Assembler::new().unwrap();
-        // Prologue for dynamically compiled code.
-        let offset = prologue!(ops);
-        let mut exit_pc = 0i32;
+        self.used_callee_saved.clear();
+        #[cfg(target_arch = "aarch64")]
+        {
+            self.arm64_allocated = RegisterSet::default();
+        }
+        self.spill_slots = 0;
+        self.free_spill_slots.clear();
+        // Labels belong to the assembler of the trace they were created for.
+        self.labels.clear();
+        let body = ops.new_dynamic_label();
+        #[cfg(target_arch = "x86_64")]
+        dynasm!(ops
+            ; =>body
+        );
+        let mut exit_pc = ProgramCounter::new(pc.get_method_index(), 0);
+        // Whether the trace has an `if_icmp<cond>` loop exit guard.
+        let mut guarded = false;
+        // Types of the locals stored by the trace.
+        let mut stored_types = HashMap::new();
         // Trace compilation :
         // For now we compile only the prologue and epilogue and ensure that
         // entering the Jit executing the assembled code and leaving the Jit
         // works correct.
-        for entry in &recording.trace {
+        // Operand stack depth and start of the expression being evaluated,
+        // division guards exit there.
+        let mut depth = 0;
+        let mut expression_start = pc;
+        for (position, entry) in recording.iter().enumerate() {
             // Record the instruction program counter to a new label.
             let inst_label = ops.new_dynamic_label();
             let _ = self.labels.insert(entry.pc(), inst_label);
+            if depth == 0 {
+                expression_start = entry.pc();
+            }
+            depth += entry
+                .instruction()
+                .get_mnemonic()
+                .stack_effect()
+                .unwrap_or_default();
             match entry.instruction().get_mnemonic() {
                 // Load operation loads a constant from the locals array at
                 // the position given by the opcode's operand.
@@ -284,15 +638,21 @@ impl JitCache {
                 // Since the locals array is the first argument to our JIT
                 // `execute` function the value can be fetched from memory
                 // using base addressing.
-                // We assume (for now) locals are 8 bytes long.
+                // Each local occupies an 8 byte slot so `long` values are
+                // loaded like `int` values.
                 OPCode::ILoad
                 | OPCode::ILoad0
                 | OPCode::ILoad1
                 | OPCode::ILoad2
-                | OPCode::ILoad3 => {
+                | OPCode::ILoad3
+                | OPCode::LLoad
+                | OPCode::LLoad0
+                | OPCode::LLoad1
+                | OPCode::LLoad2
+                | OPCode::LLoad3 => {
                     let value = match entry.instruction().nth(0) {
                         Some(Value::Int(x)) => x,
-                        _ => unreachable!("Operand to iload (index in locals) must be int in current implementation")
+                        _ => unreachable!("Operand to load (index in locals) must be int in current implementation")
                     };
                     let dst = self.first_available_register();
 
@@ -303,26 +663,47 @@ impl JitCache {
                     Self::emit_mov(
                         &mut ops,
                         &dst,
-                        &Operand::Memory(Register::Rdi, 4 * value),
+                        &Operand::Memory(Register::Rdi, 8 * value),
                     );
                     self.operands.push(dst);
                 }
+                // Stores are untyped in native code, every local occupies
+                // an 8 byte slot regardless of its type.
                 OPCode::IStore
                 | OPCode::IStore0
                 | OPCode::IStore1
                 | OPCode::IStore2
-                | OPCode::IStore3 => {
+                | OPCode::IStore3
+                | OPCode::LStore
+                | OPCode::LStore0
+                | OPCode::LStore1
+                | OPCode::LStore2
+                | OPCode::LStore3
+                | OPCode::FStore
+                | OPCode::FStore0
+                | OPCode::FStore1
+                | OPCode::FStore2
+                | OPCode::FStore3
+                | OPCode::DStore
+                | OPCode::DStore0
+                | OPCode::DStore1
+                | OPCode::DStore2
+                | OPCode::DStore3 => {
                     let value = match entry.instruction().nth(0) {
                         Some(Value::Int(x)) => x,
-                            _ => unreachable!("Operand to istore (index in locals) must be int in current implementation")
+                            _ => unreachable!("Operand to store (index in locals) must be int in current implementation")
                     };
+                    stored_types.insert(
+                        value as usize,
+                        Self::stored_type(entry.instruction().get_mnemonic()),
+                    );
                     if let Some(src) = self.free_register() {
                         dynasm!(ops
                             ; =>inst_label
                         );
                         Self::emit_mov(
                             &mut ops,
-                            &Operand::Memory(Register::Rdi, 4 * value),
+                            &Operand::Memory(Register::Rdi, 8 * value),
                             &src,
                         );
                     }
@@ -334,40 +715,105 @@ impl JitCache {
                     };
                     self.operands.push(Operand::Immediate(imm));
                 }
+                // `lconst_<n>` is recorded as `ldc2_w`, long immediates are
+                // sign extended to 64 bits.
+                OPCode::Ldc2W => {
+                    let imm = match entry.instruction().nth(0) {
+                        Some(Value::Long(imm)) => imm as i32,
+                        _ => unreachable!("Operand to ldc2_w must be a long that fits an int in current implementation")
+                    };
+                    self.operands.push(Operand::Immediate(imm));
+                }
+                // Stack manipulation only changes the operand stack, `dup`
+                // copies registers so both copies can be mutated separately.
+                OPCode::Dup => {
+                    let top = *self
+                        .operands
+                        .last()
+                        .expect("expected operand for dup found None");
+                    match top {
+                        Operand::Immediate(_) => self.operands.push(top),
+                        _ => {
+                            #[cfg(target_arch = "x86_64")]
+                            dynasm!(ops
+                                ; =>inst_label
+                            );
+                            let dst = self.first_available_register();
+                            Self::emit_mov(&mut ops, &dst, &top);
+                            self.operands.push(dst);
+                        }
+                    }
+                }
+                OPCode::Pop => {
+                    self.free_register();
+                }
+                OPCode::Swap => {
+                    let len = self.operands.len();
+                    assert!(len >= 2, "expected two operands for swap");
+                    self.operands.swap(len - 1, len - 2);
+                }
                 OPCode::IAdd => {
                     #[cfg(target_arch = "x86_64")]
                     dynasm!(ops
                         ; =>inst_label
                     );
-                    self.emit_arithmetic(&mut ops, Inst::Add);
+                    self.emit_arithmetic(&mut ops, Inst::Add, false);
                 }
                 OPCode::ISub => {
                     #[cfg(target_arch = "x86_64")]
                     dynasm!(ops
                         ; =>inst_label
                     );
-                    self.emit_arithmetic(&mut ops, Inst::Sub);
+                    self.emit_arithmetic(&mut ops, Inst::Sub, false);
                 }
                 OPCode::IMul => {
                     #[cfg(target_arch = "x86_64")]
                     dynasm!(ops
                         ; =>inst_label
                     );
-                    self.emit_arithmetic(&mut ops, Inst::IMul);
+                    self.emit_arithmetic(&mut ops, Inst::IMul, false);
+                }
+                OPCode::LAdd => {
+                    #[cfg(target_arch = "x86_64")]
+                    dynasm!(ops
+                        ; =>inst_label
+                    );
+                    self.emit_arithmetic(&mut ops, Inst::Add, true);
+                }
+                OPCode::LSub => {
+                    #[cfg(target_arch = "x86_64")]
+                    dynasm!(ops
+                        ; =>inst_label
+                    );
+                    self.emit_arithmetic(&mut ops, Inst::Sub, true);
+                }
+                OPCode::LMul => {
+                    #[cfg(target_arch = "x86_64")]
+                    dynasm!(ops
+                        ; =>inst_label
+                    );
+                    self.emit_arithmetic(&mut ops, Inst::IMul, true);
+                }
+                OPCode::LCmp => {
+                    #[cfg(target_arch = "x86_64")]
+                    dynasm!(ops
+                        ; =>inst_label
+                    );
+                    self.emit_lcmp(&mut ops);
                 }
                 OPCode::IDiv => {
                     #[cfg(target_arch = "x86_64")]
                     dynasm!(ops
                         ; =>inst_label
                     );
-                    self.emit_div(&mut ops, Inst::IDiv);
+                    self.emit_div(&mut ops, Inst::IDiv, expression_start);
                 }
                 OPCode::IRem => {
                     #[cfg(target_arch = "x86_64")]
                     dynasm!(ops
                         ; =>inst_label
                     );
-                    self.emit_div(&mut ops, Inst::IRem);
+                    self.emit_div(&mut ops, Inst::IRem, expression_start);
                 }
                 OPCode::IInc => {
                     let index = match entry.instruction().nth(0) {
@@ -383,10 +829,10 @@ impl JitCache {
                         ; =>inst_label
                     );
                     dynasm!(ops
-                        ; add [Rq(Register::Rdi as u8) + 4* index], constant as _
+                        ; add DWORD [Rq(Register::Rdi as u8) + 8 * index], constant as _
                     );
                 }
-                OPCode::Goto => {
+                OPCode::Goto | OPCode::GotoW => {
                     // let target = match ...
                     // if let Some(pc) = trace.contains(target) {
                     // the target jump is inside the trace
@@ -415,6 +861,20 @@ impl JitCache {
                         dynasm!(ops
                             ; jmp =>*label
                         );
+                    } else {
+                        // The target wasn't recorded, exit the trace and
+                        // continue at the target in the interpreter.
+                        let target_pc = ProgramCounter::new(
+                            entry.pc().get_method_index(),
+                            (entry.pc().get_instruction_index() as isize
+                                + target as isize)
+                                as usize,
+                        );
+                        #[cfg(target_arch = "x86_64")]
+                        dynasm!(ops
+                            ; mov rax, QWORD encode_exit_pc(target_pc) as i64
+                            ; jmp ->exit
+                        );
                     }
                 }
                 // if_icmp{cond} compares the top two values on the stack
@@ -426,76 +886,338 @@ impl JitCache {
                 OPCode::IfICmpGe
                 | OPCode::IfICmpGt
                 | OPCode::IfICmpLe
-                | OPCode::IfICmpEq => {
+                | OPCode::IfICmpLt
+                | OPCode::IfICmpEq
+                | OPCode::IfICmpNe => {
                     let target = match entry.instruction().nth(0) {
                         Some(Value::Int(x)) => x,
                             _ => unreachable!("First operand to if_icmpge (relative offset) must be int")
                     };
                     let mnemonic = entry.instruction().get_mnemonic();
-                    exit_pc = (entry.pc().get_instruction_index() as isize
-                        + target as isize) as i32;
+                    guarded = true;
+                    exit_pc = ProgramCounter::new(
+                        entry.pc().get_method_index(),
+                        (entry.pc().get_instruction_index() as isize
+                            + target as isize) as usize,
+                    );
 
                     self.emit_cond_branch(&mut ops, mnemonic);
                 }
-                OPCode::IfEq => {
-                    let operand = self.free_register();
-                    match operand {
-                        Some(Operand::Register(reg)) => {
-                            #[cfg(target_arch = "x86_64")]
-                            dynasm!(ops
-                                ; cmp Rq(reg as u8), 0
-                                ; je ->abort_guard
-                            );
-                        }
-                        Some(Operand::Memory(base, offset)) => {
-                            #[cfg(target_arch = "x86_64")]
-                            dynasm!(ops
-                                ; cmp [Rq(base as u8) + offset], 0
-                                ; je ->abort_guard
-                            );
-                        }
-                        _ => unreachable!("expected operand for if_eq to be either `Operand::Memory` or `Operand::Register`"),
-                    }
-                }
-                OPCode::IfNe => {
-                    let operand = self.free_register();
-                    match operand {
-                        Some(Operand::Register(reg)) => {
-                            #[cfg(target_arch = "x86_64")]
-                            dynasm!(ops
-                                ; cmp Rq(reg as u8), 0
-                                ; jz ->abort_guard
-                            );
-                        }
-                        Some(Operand::Memory(base, offset)) => {
-                            #[cfg(target_arch = "x86_64")]
-                            dynasm!(ops
-                                ; cmp [Rq(base as u8) + offset], 0
-                                ; jz ->abort_guard
-                            );
-                        }
-                        _ => unreachable!("expected operand for if_eq to be either `Operand::Memory` or `Operand::Register`"),
-                    }
+                // if{cond} compares the value on top of the stack against
+                // zero. The trace follows the direction the branch took when
+                // it was recorded, so the guard exits to the interpreter at
+                // the other direction when the flipped condition holds.
+                OPCode::IfEq
+                | OPCode::IfNe
+                | OPCode::IfLt
+                | OPCode::IfGe
+                | OPCode::IfGt
+                | OPCode::IfLe => {
+                    let offset = match entry.instruction().nth(0) {
+                        Some(Value::Int(x)) => x,
+                        _ => unreachable!(
+                            "First operand to if<cond> (relative offset) must be int"
+                        ),
+                    };
+                    let method_index = entry.pc().get_method_index();
+                    let index = entry.pc().get_instruction_index();
+                    let target = ProgramCounter::new(
+                        method_index,
+                        (index as isize + offset as isize) as usize,
+                    );
+                    // if<cond> is encoded in 3 bytes.
+                    let next = ProgramCounter::new(method_index, index + 3);
+                    let taken = recording
+                        .trace
+                        .get(position + 1)
+                        .is_some_and(|record| record.pc() == target);
+                    let mnemonic = entry.instruction().get_mnemonic();
+                    let (exit_cond, exit) = if taken {
+                        (flip_branch(mnemonic), next)
+                    } else {
+                        (mnemonic, target)
+                    };
+                    self.emit_zero_cond_exit(&mut ops, exit_cond, exit);
                 }
-                _ => (),
+                opcode => unreachable!("{opcode} can't be compiled"),
             }
         }
+        // The loop exit guard returns to the interpreter unless a native
+        // trace is stitched to it, in which case the frame is released and
+        // the guard jumps to the entry of the stitched trace whose address
+        // is patched in the `mov rdx` immediate by `link`.
         #[cfg(target_arch = "x86_64")]
         dynasm!(ops
             ; ->abort_guard:
-            ; mov rax, exit_pc as _
+            ; mov rax, QWORD encode_exit_pc(exit_pc) as i64
+        );
+        // `mov rdx, imm64` is encoded as `REX.W B8+r` followed by the
+        // immediate.
+        let slot = AssemblyOffset(ops.offset().0 + 2);
+        #[cfg(target_arch = "x86_64")]
+        dynasm!(ops
+            ; mov rdx, QWORD 0
+            ; test rdx, rdx
+            ; jz ->exit
+            ; mov rsp, rbp
+            ; pop rbp
+        );
+        for reg in self.used_callee_saved.iter().rev() {
+            #[cfg(target_arch = "x86_64")]
+            dynasm!(ops
+                ; pop Rq(*reg as u8)
+            );
+        }
+        #[cfg(target_arch = "x86_64")]
+        dynasm!(ops
+            ; jmp rdx
+            ; ->exit:
         );
         // Epilogue for dynamically compiled code.
-        epilogue!(ops);
+        epilogue!(ops, self.used_callee_saved, self.arm64_allocated);
+        // Prologue for dynamically compiled code, this is the trace entry.
+        // The saved arguments take 32 bytes followed by the spill slots, the
+        // size is rounded up to a multiple of 16 bytes.
+        let frame_size = (32 + 8 * self.spill_slots + 15) & !15;
+        let offset = prologue!(
+            ops,
+            self.used_callee_saved,
+            self.arm64_allocated,
+            frame_size
+        );
+        #[cfg(target_arch = "x86_64")]
+        dynasm!(ops
+            ; jmp =>body
+        );
 
         let buf = ops.finalize().unwrap();
 
-        let native_trace = NativeTrace(offset, buf);
+        self.compiled_count += 1;
+        // Traces that don't fit in the code cache even when empty aren't
+        // cached.
+        if buf.len() > self.max_code_cache_size {
+            return Vec::new();
+        }
+        let evicted = self.make_room(buf.len());
+        // Traces exiting at their own entry aren't stitched to themselves.
+        let link = (guarded && exit_pc != pc).then_some(ExitLink {
+            exit_pc,
+            slot,
+            linked: false,
+        });
+        let native_trace = NativeTrace(offset, buf, stored_types, link);
         self.traces.insert(pc, native_trace);
+        self.link(pc);
+        self.clock += 1;
+        self.last_executed.insert(pc, self.clock);
+        evicted
+    }
+
+    /// Stitch the native trace at `pc` to the cached traces, its loop exit
+    /// guard jumps into the trace at its exit program counter and the guards
+    /// of the traces exiting at `pc` jump into it.
+    ///
+    /// Traces are only stitched when they agree on the types of the locals
+    /// they both store.
+    fn link(&mut self, pc: ProgramCounter) {
+        let links: Vec<(ProgramCounter, ProgramCounter)> = self
+            .traces
+            .iter()
+            .filter_map(|(from, trace)| Some((*from, trace.3?.exit_pc)))
+            .filter(|(from, to)| *from == pc || *to == pc)
+            .collect();
+        for (from, to) in links {
+            let compatible = self.traces.get(&to).is_some_and(|to| {
+                let from = &self.traces[&from].2;
+                to.2.iter().all(|(index, kind)| {
+                    from.get(index).is_none_or(|other| other == kind)
+                })
+            });
+            if compatible {
+                self.patch_exit_link(from, Some(to));
+            } else if from != pc {
+                // The trace at `pc` replaced the one this guard jumped to.
+                self.patch_exit_link(from, None);
+            }
+        }
+    }
+
+    /// Patch the loop exit guard of the native trace at `pc` to jump into the
+    /// native trace at `target`, or to return to the interpreter if `None`.
+    fn patch_exit_link(
+        &mut self,
+        pc: ProgramCounter,
+        target: Option<ProgramCounter>,
+    ) {
+        let address = target.map_or(0, |target| {
+            let NativeTrace(entry, buf, ..) = &self.traces[&target];
+            buf.ptr(*entry) as u64
+        });
+        let trace = self
+            .traces
+            .get_mut(&pc)
+            .expect("Expected a native trace to patch");
+        let mut buf = std::mem::take(&mut trace.1)
+            .make_mut()
+            .expect("Expected the native trace to be writable");
+        let link = trace.3.as_mut().expect("Expected an exit link to patch");
+        let slot = link.slot.0;
+        buf[slot..slot + 8].copy_from_slice(&address.to_le_bytes());
+        link.linked = target.is_some();
+        trace.1 = buf
+            .make_exec()
+            .expect("Expected the native trace to be executable");
+    }
+
+    /// Returns whether `compile` can translate the instruction to native
+    /// code, traces with other instructions are left to the interpreter.
+    fn can_compile(inst: &Instruction) -> bool {
+        match inst.get_mnemonic() {
+            OPCode::BiPush | OPCode::SiPush | OPCode::Ldc => {
+                matches!(inst.nth(0), Some(Value::Int(_)))
+            }
+            OPCode::Ldc2W => matches!(
+                inst.nth(0),
+                Some(Value::Long(v)) if i32::try_from(v).is_ok()
+            ),
+            OPCode::ILoad
+            | OPCode::ILoad0
+            | OPCode::ILoad1
+            | OPCode::ILoad2
+            | OPCode::ILoad3
+            | OPCode::LLoad
+            | OPCode::LLoad0
+            | OPCode::LLoad1
+            | OPCode::LLoad2
+            | OPCode::LLoad3
+            | OPCode::IStore
+            | OPCode::IStore0
+            | OPCode::IStore1
+            | OPCode::IStore2
+            | OPCode::IStore3
+            | OPCode::LStore
+            | OPCode::LStore0
+            | OPCode::LStore1
+            | OPCode::LStore2
+            | OPCode::LStore3
+            | OPCode::FStore
+            | OPCode::FStore0
+            | OPCode::FStore1
+            | OPCode::FStore2
+            | OPCode::FStore3
+            | OPCode::DStore
+            | OPCode::DStore0
+            | OPCode::DStore1
+            | OPCode::DStore2
+            | OPCode::DStore3
+            | OPCode::Dup
+            | OPCode::Pop
+            | OPCode::Swap
+            | OPCode::IAdd
+            | OPCode::ISub
+            | OPCode::IMul
+            | OPCode::LAdd
+            | OPCode::LSub
+            | OPCode::LMul
+            | OPCode::LCmp
+            | OPCode::IDiv
+            | OPCode::IRem
+            | OPCode::IInc
+            | OPCode::Goto
+            | OPCode::GotoW
+            | OPCode::IfICmpGe
+            | OPCode::IfICmpGt
+            | OPCode::IfICmpLe
+            | OPCode::IfICmpLt
+            | OPCode::IfICmpEq
+            | OPCode::IfICmpNe
+            | OPCode::IfEq
+            | OPCode::IfNe
+            | OPCode::IfLt
+            | OPCode::IfGe
+            | OPCode::IfGt
+            | OPCode::IfLe => true,
+            _ => false,
+        }
+    }
+
+    /// Returns whether the trace exits without losing operands, operands
+    /// live in registers and exits only write back the locals.
+    ///
+    /// The operand stack must be empty after every branch, and divisions
+    /// exit at the start of their expression so the interpreter evaluates
+    /// it again, which requires the expression not to store any local.
+    fn exits_keep_operands(recording: &Trace) -> bool {
+        let mut depth = 0;
+        // Whether the expression being evaluated stored a local.
+        let mut stored = false;
+        for entry in recording.iter() {
+            let opcode = entry.instruction().get_mnemonic();
+            if depth == 0 {
+                stored = false;
+            }
+            match opcode {
+                OPCode::IDiv | OPCode::IRem if stored => return false,
+                OPCode::IInc
+                | OPCode::IStore
+                | OPCode::IStore0
+                | OPCode::IStore1
+                | OPCode::IStore2
+                | OPCode::IStore3
+                | OPCode::LStore
+                | OPCode::LStore0
+                | OPCode::LStore1
+                | OPCode::LStore2
+                | OPCode::LStore3
+                | OPCode::FStore
+                | OPCode::FStore0
+                | OPCode::FStore1
+                | OPCode::FStore2
+                | OPCode::FStore3
+                | OPCode::DStore
+                | OPCode::DStore0
+                | OPCode::DStore1
+                | OPCode::DStore2
+                | OPCode::DStore3 => stored = true,
+                _ => (),
+            }
+            match opcode.stack_effect() {
+                Some(effect) => depth += effect,
+                None => return false,
+            }
+            if opcode.is_branch() && depth != 0 {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Returns the type of the value stored by a store instruction, long and
+    /// double values take two locals but a single 8 byte slot in native code.
+    const fn stored_type(opcode: OPCode) -> BaseTypeKind {
+        match opcode {
+            OPCode::LStore
+            | OPCode::LStore0
+            | OPCode::LStore1
+            | OPCode::LStore2
+            | OPCode::LStore3 => BaseTypeKind::Long,
+            OPCode::FStore
+            | OPCode::FStore0
+            | OPCode::FStore1
+            | OPCode::FStore2
+            | OPCode::FStore3 => BaseTypeKind::Float,
+            OPCode::DStore
+            | OPCode::DStore0
+            | OPCode::DStore1
+            | OPCode::DStore2
+            | OPCode::DStore3 => BaseTypeKind::Double,
+            _ => BaseTypeKind::Int,
+        }
     }
 
     /// Emit a move operation, this includes all data movement operations
-    /// register to register and immediate to register.
+    /// register to register and immediate to register. Memory to memory moves
+    /// go through the `rdx` scratch register.
     fn emit_mov(ops: &mut Assembler, dst: &Operand, src: &Operand) {
         match (dst, src) {
             (Operand::Register(dst), Operand::Register(src)) => {
@@ -525,9 +1247,14 @@ impl JitCache {
             (Operand::Memory(base, offset), Operand::Immediate(imm)) => {
                 #[cfg(target_arch = "x86_64")]
                 dynasm!(ops
-                        ; mov DWORD [Rq(*base as u8) + *offset], *imm as _
+                        ; mov QWORD [Rq(*base as u8) + *offset], *imm as _
                 );
             }
+            (Operand::Memory(..), Operand::Memory(..)) => {
+                let scratch = Operand::Register(Register::Rdx);
+                Self::emit_mov(ops, &scratch, src);
+                Self::emit_mov(ops, dst, &scratch);
+            }
             _ => unreachable!(
                 "Unexpected operands for `mov` `dst`={:?}, `src`={:?})",
                 dst, src
@@ -536,8 +1263,12 @@ impl JitCache {
     }
 
     /// Emit an arithmetic operation, covers only simple instructions such as
-    /// `add`, `mul` and `sub`.
-    fn emit_arithmetic(&mut self, ops: &mut Assembler, op: Inst) {
+    /// `add`, `mul` and `sub` on 32-bit `int` operands or 64-bit `long`
+    /// operands when `long` is set.
+    ///
+    /// When the destination is a spill slot the operation is computed in the
+    /// `rdx` scratch register and stored back to the slot.
+    fn emit_arithmetic(&mut self, ops: &mut Assembler, op: Inst, long: bool) {
         let rhs = match self.operands.pop() {
             Some(rhs) => rhs,
             None => panic!("expected operand found None"),
@@ -547,127 +1278,266 @@ impl JitCache {
             None => panic!("expected operand found None"),
         };
 
-        let dst = match &lhs {
-            &Operand::Register(reg) => Operand::Register(reg),
-            // TODO: need to mov lhs operand to the first free register.
+        let dst = match lhs {
+            Operand::Register(_) | Operand::Memory(Register::Rbp, _) => lhs,
             _ => {
                 let dst = self.first_available_register();
                 JitCache::emit_mov(ops, &dst, &lhs);
                 dst
             }
         };
-        if let Operand::Register(reg) = &rhs {
-            self.registers.push_back(*reg)
-        }
-
+        self.release(rhs);
         self.operands.push(dst);
 
-        match op {
-            Inst::Add => {
-                let Operand::Register(dst) = dst else {
-                    unreachable!("Unexpected enum variant for `Operand` expected `Register` got {:?}", dst)
-                };
-
-                match rhs {
-                    Operand::Register(src) => {
-                        #[cfg(target_arch = "x86_64")]
-                        dynasm!(ops
-                                ; add Rq(dst as u8), Rq(src as u8)
-                        );
-                    },
-                    Operand::Immediate(val) => {
-                        #[cfg(target_arch = "x86_64")]
-                        dynasm!(ops
-                                ; add Rq(dst as u8), val as _
-                        );
-                    },
-                    Operand::Memory(base, offset) => {
-                        #[cfg(target_arch = "x86_64")]
-                        dynasm!(ops
-                                ; add Rq(dst as u8), [Rq(base as u8) + offset]
-                        );
-                    },
-                }
-            }
-            Inst::Sub => {
-                let Operand::Register(dst) = dst else {
-                    unreachable!("Unexpected enum variant for `Operand` expected `Register` got {:?}", dst)
-                };
-
-                match rhs {
-                    Operand::Register(src) => {
-                        #[cfg(target_arch = "x86_64")]
-                        dynasm!(ops
-                                ; sub Rq(dst as u8), Rq(src as u8)
-                        );
-                    },
-                    Operand::Immediate(val) => {
-                        #[cfg(target_arch = "x86_64")]
-                        dynasm!(ops
-                                ; sub Rq(dst as u8), val as _
-                        );
-                    },
-                    Operand::Memory(base, offset) => {
-                        #[cfg(target_arch = "x86_64")]
-                        dynasm!(ops
-                                ; sub Rq(dst as u8), [Rq(base as u8) + offset]
-                        );
-                    },
-                }
-            }
-            Inst::IMul => {
-                let Operand::Register(dst) = dst else {
-                    unreachable!("Unexpected enum variant for `Operand` expected `Register` got {:?}", dst)
-                };
-                match rhs {
-                    Operand::Register(src) => {
-                        #[cfg(target_arch = "x86_64")]
-                        dynasm!(ops
-                                ; imul Rq(dst as u8), Rq(src as u8)
-                        );
-                    },
-                    Operand::Immediate(val) => {
-                        #[cfg(target_arch = "x86_64")]
-                        dynasm!(ops
-                                ; imul Rq(dst as u8), Rq(dst as u8), val as _
-                        );
-                    },
-                    Operand::Memory(base, offset) => {
-                        #[cfg(target_arch = "x86_64")]
-                        dynasm!(ops
-                                ; imul Rq(dst as u8), [Rq(base as u8) + offset]
-                        );
-                    },
-                }
-            }
-            _ => unreachable!("emit_arithmetic only supports simple x86-64 arithmetic (add, sub and mul).)"),
-        }
-    }
-
-    /// Emit division operation.
-    fn emit_div(&mut self, ops: &mut Assembler, op: Inst) {
-        let rdx = Register::Rdx;
-        let rax = Register::Rax;
-
-        let denom = match self.operands.pop() {
-            Some(operand) => operand,
+        let acc = match dst {
+            Operand::Register(reg) => reg,
             _ => {
-                unreachable!("Expected operand for `idiv` and `irem` got None")
+                JitCache::emit_mov(
+                    ops,
+                    &Operand::Register(Register::Rdx),
+                    &dst,
+                );
+                Register::Rdx
             }
         };
 
-        if let Some(nom) = self.free_register() {
-            JitCache::emit_mov(ops, &Operand::Register(Register::Rax), &nom);
+        if long {
+            Self::emit_long_arithmetic(ops, op, acc, rhs);
+        } else {
+            Self::emit_int_arithmetic(ops, op, acc, rhs);
         }
-        let dst = match denom {
-            Operand::Register(reg) => Operand::Register(reg),
-            _ => {
-                let reg = self.first_available_register();
-                JitCache::emit_mov(ops, &reg, &denom);
-                reg
-            }
-        };
-
+        if acc == Register::Rdx {
+            JitCache::emit_mov(ops, &dst, &Operand::Register(Register::Rdx));
+        }
+    }
+
+    /// Emit `acc = acc op rhs` on the low 32 bits of the operands.
+    fn emit_int_arithmetic(
+        ops: &mut Assembler,
+        op: Inst,
+        acc: Register,
+        rhs: Operand,
+    ) {
+        match op {
+            Inst::Add => match rhs {
+                Operand::Register(src) => {
+                    #[cfg(target_arch = "x86_64")]
+                    dynasm!(ops
+                            ; add Rd(acc as u8), Rd(src as u8)
+                    );
+                }
+                Operand::Immediate(val) => {
+                    #[cfg(target_arch = "x86_64")]
+                    dynasm!(ops
+                            ; add Rd(acc as u8), val as _
+                    );
+                }
+                Operand::Memory(base, offset) => {
+                    #[cfg(target_arch = "x86_64")]
+                    dynasm!(ops
+                            ; add Rd(acc as u8), DWORD [Rq(base as u8) + offset]
+                    );
+                }
+            },
+            Inst::Sub => match rhs {
+                Operand::Register(src) => {
+                    #[cfg(target_arch = "x86_64")]
+                    dynasm!(ops
+                            ; sub Rd(acc as u8), Rd(src as u8)
+                    );
+                }
+                Operand::Immediate(val) => {
+                    #[cfg(target_arch = "x86_64")]
+                    dynasm!(ops
+                            ; sub Rd(acc as u8), val as _
+                    );
+                }
+                Operand::Memory(base, offset) => {
+                    #[cfg(target_arch = "x86_64")]
+                    dynasm!(ops
+                            ; sub Rd(acc as u8), DWORD [Rq(base as u8) + offset]
+                    );
+                }
+            },
+            Inst::IMul => match rhs {
+                Operand::Register(src) => {
+                    #[cfg(target_arch = "x86_64")]
+                    dynasm!(ops
+                            ; imul Rd(acc as u8), Rd(src as u8)
+                    );
+                }
+                Operand::Immediate(val) => {
+                    #[cfg(target_arch = "x86_64")]
+                    dynasm!(ops
+                            ; imul Rd(acc as u8), Rd(acc as u8), val as _
+                    );
+                }
+                Operand::Memory(base, offset) => {
+                    #[cfg(target_arch = "x86_64")]
+                    dynasm!(ops
+                            ; imul Rd(acc as u8), DWORD [Rq(base as u8) + offset]
+                    );
+                }
+            },
+            _ => unreachable!("emit_arithmetic only supports simple x86-64 arithmetic (add, sub and mul).)"),
+        }
+    }
+
+    /// Emit `acc = acc op rhs` on the full 64 bits of the operands.
+    fn emit_long_arithmetic(
+        ops: &mut Assembler,
+        op: Inst,
+        acc: Register,
+        rhs: Operand,
+    ) {
+        match op {
+            Inst::Add => match rhs {
+                Operand::Register(src) => {
+                    #[cfg(target_arch = "x86_64")]
+                    dynasm!(ops
+                            ; add Rq(acc as u8), Rq(src as u8)
+                    );
+                }
+                Operand::Immediate(val) => {
+                    #[cfg(target_arch = "x86_64")]
+                    dynasm!(ops
+                            ; add Rq(acc as u8), val as _
+                    );
+                }
+                Operand::Memory(base, offset) => {
+                    #[cfg(target_arch = "x86_64")]
+                    dynasm!(ops
+                            ; add Rq(acc as u8), QWORD [Rq(base as u8) + offset]
+                    );
+                }
+            },
+            Inst::Sub => match rhs {
+                Operand::Register(src) => {
+                    #[cfg(target_arch = "x86_64")]
+                    dynasm!(ops
+                            ; sub Rq(acc as u8), Rq(src as u8)
+                    );
+                }
+                Operand::Immediate(val) => {
+                    #[cfg(target_arch = "x86_64")]
+                    dynasm!(ops
+                            ; sub Rq(acc as u8), val as _
+                    );
+                }
+                Operand::Memory(base, offset) => {
+                    #[cfg(target_arch = "x86_64")]
+                    dynasm!(ops
+                            ; sub Rq(acc as u8), QWORD [Rq(base as u8) + offset]
+                    );
+                }
+            },
+            Inst::IMul => match rhs {
+                Operand::Register(src) => {
+                    #[cfg(target_arch = "x86_64")]
+                    dynasm!(ops
+                            ; imul Rq(acc as u8), Rq(src as u8)
+                    );
+                }
+                Operand::Immediate(val) => {
+                    #[cfg(target_arch = "x86_64")]
+                    dynasm!(ops
+                            ; imul Rq(acc as u8), Rq(acc as u8), val as _
+                    );
+                }
+                Operand::Memory(base, offset) => {
+                    #[cfg(target_arch = "x86_64")]
+                    dynasm!(ops
+                            ; imul Rq(acc as u8), QWORD [Rq(base as u8) + offset]
+                    );
+                }
+            },
+            _ => unreachable!("emit_arithmetic only supports simple x86-64 arithmetic (add, sub and mul).)"),
+        }
+    }
+
+    /// Emit `lcmp`, the comparison of the two longs on top of the stack is
+    /// computed in the `rdx` scratch register as -1, 0 or 1.
+    fn emit_lcmp(&mut self, ops: &mut Assembler) {
+        let rhs = match self.operands.pop() {
+            Some(rhs) => rhs,
+            None => panic!("expected operand found None"),
+        };
+        let lhs = match self.operands.pop() {
+            Some(lhs) => lhs,
+            None => panic!("expected operand found None"),
+        };
+        let scratch = Register::Rdx;
+        JitCache::emit_mov(ops, &Operand::Register(scratch), &lhs);
+        match rhs {
+            Operand::Register(src) => {
+                #[cfg(target_arch = "x86_64")]
+                dynasm!(ops
+                    ; cmp Rq(scratch as u8), Rq(src as u8)
+                );
+            }
+            Operand::Immediate(val) => {
+                #[cfg(target_arch = "x86_64")]
+                dynasm!(ops
+                    ; cmp Rq(scratch as u8), val
+                );
+            }
+            Operand::Memory(base, offset) => {
+                #[cfg(target_arch = "x86_64")]
+                dynasm!(ops
+                    ; cmp Rq(scratch as u8), QWORD [Rq(base as u8) + offset]
+                );
+            }
+        }
+        // `mov` leaves the flags untouched.
+        #[cfg(target_arch = "x86_64")]
+        dynasm!(ops
+            ; mov Rd(scratch as u8), 0
+            ; je >done
+            ; mov Rd(scratch as u8), 1
+            ; jg >done
+            ; mov Rd(scratch as u8), -1
+            ; done:
+        );
+        self.release(rhs);
+        self.release(lhs);
+        let dst = self.first_available_register();
+        JitCache::emit_mov(ops, &dst, &Operand::Register(scratch));
+        self.operands.push(dst);
+    }
+
+    /// Emit a signed 32-bit division operation, the dividend is sign
+    /// extended into `edx` before dividing.
+    ///
+    /// Divisions by zero and of `i32::MIN` by -1 trap in native code, they
+    /// exit the trace at `exit` and are evaluated by the interpreter.
+    fn emit_div(
+        &mut self,
+        ops: &mut Assembler,
+        op: Inst,
+        exit: ProgramCounter,
+    ) {
+        let rdx = Register::Rdx;
+        let rax = Register::Rax;
+
+        let denom = match self.operands.pop() {
+            Some(operand) => operand,
+            _ => {
+                unreachable!("Expected operand for `idiv` and `irem` got None")
+            }
+        };
+
+        if let Some(nom) = self.free_register() {
+            JitCache::emit_mov(ops, &Operand::Register(Register::Rax), &nom);
+        }
+        let dst = match denom {
+            Operand::Register(_) | Operand::Memory(Register::Rbp, _) => denom,
+            _ => {
+                let reg = self.first_available_register();
+                JitCache::emit_mov(ops, &reg, &denom);
+                reg
+            }
+        };
+
         let src = match op {
             // x86 division rax holds divident rdx holds modulo.
             Inst::IDiv => rax,
@@ -675,15 +1545,48 @@ impl JitCache {
             _ => unreachable!("emit_div expected op to be idiv or irem"),
         };
 
-        #[cfg(target_arch = "x86_64")]
-        let Operand::Register(dst_reg) = dst
-        else {
-            unreachable!("Unexpected enum variant for `Operand` expected `Register` got {:?}", dst)
-        };
-        dynasm!(ops
-            ; mov Rq(rdx as u8), 0
-            ; div Rq(dst_reg as u8)
-        );
+        let trap = ops.new_dynamic_label();
+        let divide = ops.new_dynamic_label();
+        match dst {
+            Operand::Register(dst_reg) => {
+                #[cfg(target_arch = "x86_64")]
+                dynasm!(ops
+                    ; cmp Rd(dst_reg as u8), 0
+                    ; je =>trap
+                    ; cmp Rd(dst_reg as u8), -1
+                    ; jne =>divide
+                    ; cmp eax, i32::MIN
+                    ; jne =>divide
+                    ; =>trap
+                    ; mov rax, QWORD encode_exit_pc(exit) as i64
+                    ; jmp ->exit
+                    ; =>divide
+                    ; cdq
+                    ; idiv Rd(dst_reg as u8)
+                );
+            }
+            Operand::Memory(base, offset) => {
+                #[cfg(target_arch = "x86_64")]
+                dynasm!(ops
+                    ; cmp DWORD [Rq(base as u8) + offset], 0
+                    ; je =>trap
+                    ; cmp DWORD [Rq(base as u8) + offset], -1
+                    ; jne =>divide
+                    ; cmp eax, i32::MIN
+                    ; jne =>divide
+                    ; =>trap
+                    ; mov rax, QWORD encode_exit_pc(exit) as i64
+                    ; jmp ->exit
+                    ; =>divide
+                    ; cdq
+                    ; idiv DWORD [Rq(base as u8) + offset]
+                );
+            }
+            Operand::Immediate(_) => unreachable!(
+                "Unexpected enum variant for `Operand` expected `Register` or `Memory` got {:?}",
+                dst
+            ),
+        }
         JitCache::emit_mov(ops, &dst, &Operand::Register(src));
         self.operands.push(dst);
     }
@@ -702,27 +1605,34 @@ impl JitCache {
         match (lhs, rhs) {
             (Operand::Register(lhs), Operand::Register(rhs)) => {
                 dynasm!(ops
-                    ; cmp Rq(lhs as u8), Rq(rhs as u8)
+                    ; cmp Rd(lhs as u8), Rd(rhs as u8)
                 );
             }
             (Operand::Register(lhs), Operand::Memory(base, offset)) => {
                 dynasm!(ops
-                    ; cmp Rq(lhs as u8), [Rq(base as u8) + offset]
+                    ; cmp Rd(lhs as u8), DWORD [Rq(base as u8) + offset]
                 );
             }
             (Operand::Register(lhs), Operand::Immediate(imm)) => {
                 dynasm!(ops
-                    ; cmp Rq(lhs as u8), imm as _
+                    ; cmp Rd(lhs as u8), imm as _
                 );
             }
             (Operand::Memory(base, offset), Operand::Register(rhs)) => {
                 dynasm!(ops
-                    ; cmp [Rq(base as u8) + offset], Rq(rhs as u8)
+                    ; cmp DWORD [Rq(base as u8) + offset], Rd(rhs as u8)
                 );
             }
             (Operand::Memory(base, offset), Operand::Immediate(imm)) => {
                 dynasm!(ops
-                    ; cmp [Rq(base as u8) + offset], imm as _
+                    ; cmp DWORD [Rq(base as u8) + offset], imm as _
+                );
+            }
+            (Operand::Memory(..), Operand::Memory(base, offset)) => {
+                let scratch = Register::Rdx;
+                JitCache::emit_mov(ops, &Operand::Register(scratch), &lhs);
+                dynasm!(ops
+                    ; cmp Rd(scratch as u8), DWORD [Rq(base as u8) + offset]
                 );
             }
             _ => unreachable!(
@@ -747,33 +1657,135 @@ impl JitCache {
                     ; jle -> abort_guard
                 );
             }
+            OPCode::IfICmpLt => {
+                dynasm!(ops
+                    ; jl ->abort_guard
+                );
+            }
             OPCode::IfICmpEq => {
                 dynasm!(ops
                     ; je -> abort_guard
                 );
             }
+            OPCode::IfICmpNe => {
+                dynasm!(ops
+                    ; jne ->abort_guard
+                );
+            }
             _ => unreachable!("Expected instruction for conditional branch to be a if_icmp<cond> {:?}", cond)
         }
     }
 
-    /// Returns the first available register.
+    /// Emit a guard comparing the value on top of the stack against zero
+    /// that exits the trace at `exit` when `cond` holds.
+    fn emit_zero_cond_exit(
+        &mut self,
+        ops: &mut Assembler,
+        cond: OPCode,
+        exit: ProgramCounter,
+    ) {
+        match self.free_register() {
+            Some(Operand::Register(reg)) => {
+                dynasm!(ops
+                    ; cmp Rd(reg as u8), 0
+                );
+            }
+            Some(Operand::Memory(base, offset)) => {
+                dynasm!(ops
+                    ; cmp DWORD [Rq(base as u8) + offset], 0
+                );
+            }
+            operand => unreachable!(
+                "expected operand for if<cond> to be either `Operand::Memory` or `Operand::Register` found {:?}",
+                operand
+            ),
+        }
+        // Skip the exit when the condition doesn't hold.
+        let stay = ops.new_dynamic_label();
+        match flip_branch(cond) {
+            OPCode::IfEq => {
+                dynasm!(ops
+                    ; je =>stay
+                );
+            }
+            OPCode::IfNe => {
+                dynasm!(ops
+                    ; jne =>stay
+                );
+            }
+            OPCode::IfLt => {
+                dynasm!(ops
+                    ; jl =>stay
+                );
+            }
+            OPCode::IfGe => {
+                dynasm!(ops
+                    ; jge =>stay
+                );
+            }
+            OPCode::IfGt => {
+                dynasm!(ops
+                    ; jg =>stay
+                );
+            }
+            OPCode::IfLe => {
+                dynasm!(ops
+                    ; jle =>stay
+                );
+            }
+            _ => unreachable!(
+                "Expected instruction for conditional branch to be a if<cond> {:?}",
+                cond
+            ),
+        }
+        dynasm!(ops
+            ; mov rax, QWORD encode_exit_pc(exit) as i64
+            ; jmp ->exit
+            ; =>stay
+        );
+    }
+
+    /// Returns the first available register, or a spill slot on the native
+    /// stack when all registers are live.
     fn first_available_register(&mut self) -> Operand {
         if !self.registers.is_empty() {
             let reg = self.registers.pop_front().unwrap();
+            if reg.is_callee_saved() && !self.used_callee_saved.contains(&reg) {
+                self.used_callee_saved.push(reg);
+            }
+            #[cfg(target_arch = "aarch64")]
+            self.arm64_allocated.insert(reg as u8);
             Operand::Register(reg)
         } else {
-            panic!("no available registers")
+            // All registers are live, spill to a free stack slot or reserve
+            // a new one.
+            let offset = self.free_spill_slots.pop().unwrap_or_else(|| {
+                self.spill_slots += 1;
+                SPILL_SLOTS_OFFSET - 8 * (self.spill_slots - 1)
+            });
+            Operand::Memory(Register::Rbp, offset)
         }
     }
 
     /// Free the top most register in the operand stack.
     fn free_register(&mut self) -> Option<Operand> {
         let op = self.operands.pop();
-        if let Some(Operand::Register(reg)) = op {
-            self.registers.push_back(reg)
+        if let Some(op) = op {
+            self.release(op);
         }
         op
     }
+
+    /// Return the register or spill slot holding `op` to the allocator.
+    fn release(&mut self, op: Operand) {
+        match op {
+            Operand::Register(reg) => self.registers.push_back(reg),
+            Operand::Memory(Register::Rbp, offset) => {
+                self.free_spill_slots.push(offset)
+            }
+            _ => (),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -781,10 +1793,12 @@ mod tests {
     use std::env;
     use std::path::Path;
 
+    use super::*;
     use crate::jvm::read_class_file;
     use crate::jvm::JVMParser;
     use crate::program::Program;
-    use crate::runtime::{Runtime, Value};
+    use crate::runtime::{Frame, Runtime, RuntimeConfig, RuntimeErrorKind};
+    use crate::trace::{Record, Recorder};
 
     macro_rules! run_jit_test_case {
         ($name: ident, $test_file:expr, $expected:expr) => {
@@ -799,7 +1813,13 @@ mod tests {
                 let class_file = JVMParser::parse(&class_file_bytes);
                 assert!(class_file.is_ok());
                 let program = Program::new(&class_file.unwrap());
-                let mut runtime = Runtime::new(program);
+                // Test loops are short so they are compiled after the first
+                // iteration.
+                let config = RuntimeConfig {
+                    jit_threshold: 1,
+                    ..RuntimeConfig::default()
+                };
+                let mut runtime = Runtime::new(program, config);
                 assert!(runtime.run(true).is_ok());
                 assert_eq!(runtime.top_return_value(), $expected);
             }
@@ -811,4 +1831,747 @@ mod tests {
         "support/tests/HotLoop.class",
         Some(Value::Int(55))
     );
+
+    run_jit_test_case!(
+        count_down_loops,
+        "support/tests/CountDown.class",
+        Some(Value::Int(300))
+    );
+
+    run_jit_test_case!(
+        int_arithmetic_wraps,
+        "support/tests/IntOverflow.class",
+        Some(Value::Int(1926378686))
+    );
+
+    // The loop variable is stored in local 5 and the sum in local 4.
+    run_jit_test_case!(
+        high_local_slots,
+        "support/tests/HighSlots.class",
+        Some(Value::Int(550))
+    );
+
+    run_jit_test_case!(
+        long_arithmetic,
+        "support/tests/LongLoop.class",
+        Some(Value::Long(-1017498427164428007))
+    );
+
+    // The exit of the first inner loop is the header of the second one.
+    run_jit_test_case!(
+        chained_loops,
+        "support/tests/ChainedLoops.class",
+        Some(Value::Int(675))
+    );
+
+    // The loop body has more live values than there are registers.
+    run_jit_test_case!(
+        spilled_operands,
+        "support/tests/Spill.class",
+        Some(Value::Int(5930))
+    );
+
+    #[test]
+    fn callee_saved_registers_are_preserved() {
+        let mut jit = JitCache::new();
+        // Force the allocator to hand out callee-saved registers.
+        jit.registers = VecDeque::from(vec![Register::Rbx, Register::R12]);
+        let start = ProgramCounter::new(0, 0);
+        let trace = Trace {
+            start,
+            trace: vec![
+                Record::new(
+                    ProgramCounter::new(0, 0),
+                    Instruction::new(OPCode::ILoad, Some(vec![Value::Int(0)])),
+                ),
+                Record::new(
+                    ProgramCounter::new(0, 1),
+                    Instruction::new(OPCode::ILoad, Some(vec![Value::Int(1)])),
+                ),
+                Record::new(
+                    ProgramCounter::new(0, 2),
+                    Instruction::new(OPCode::IAdd, None),
+                ),
+                Record::new(
+                    ProgramCounter::new(0, 3),
+                    Instruction::new(OPCode::IStore, Some(vec![Value::Int(2)])),
+                ),
+            ],
+        };
+        jit.compile(&trace);
+        assert_eq!(jit.used_callee_saved, vec![Register::Rbx, Register::R12]);
+
+        let mut frame = Frame::default();
+        frame.locals =
+            HashMap::from([(0, Value::Int(20)), (1, Value::Int(22))]);
+        frame.max_locals = 3;
+        jit.execute(start, &mut frame.view());
+        assert_eq!(frame.locals.get(&2), Some(&Value::Int(42)));
+    }
+
+    #[test]
+    fn long_locals_are_not_truncated() {
+        let mut jit = JitCache::new();
+        let start = ProgramCounter::new(0, 0);
+        let trace = Trace {
+            start,
+            trace: vec![
+                Record::new(
+                    ProgramCounter::new(0, 0),
+                    Instruction::new(OPCode::ILoad, Some(vec![Value::Int(0)])),
+                ),
+                Record::new(
+                    ProgramCounter::new(0, 1),
+                    Instruction::new(OPCode::IStore, Some(vec![Value::Int(1)])),
+                ),
+                Record::new(
+                    ProgramCounter::new(0, 2),
+                    Instruction::new(
+                        OPCode::IInc,
+                        Some(vec![Value::Int(1), Value::Int(1)]),
+                    ),
+                ),
+            ],
+        };
+        jit.compile(&trace);
+
+        let long = i64::from(i32::MAX) << 8;
+        let mut frame = Frame::default();
+        frame.locals = HashMap::from([
+            (0, Value::Int(41)),
+            (2, Value::Long(long)),
+            (4, Value::Double(1.5)),
+        ]);
+        frame.max_locals = 5;
+        jit.execute(start, &mut frame.view());
+        assert_eq!(frame.locals.get(&1), Some(&Value::Int(42)));
+        assert_eq!(frame.locals.get(&2), Some(&Value::Long(long)));
+        assert_eq!(frame.locals.get(&4), Some(&Value::Double(1.5)));
+    }
+
+    #[test]
+    fn long_stores_are_compiled() {
+        let mut recorder = Recorder::new();
+        let start = ProgramCounter::new(0, 0);
+        recorder.init(start, start);
+        recorder.record(
+            ProgramCounter::new(0, 0),
+            Instruction::new(OPCode::SiPush, Some(vec![Value::Int(300)])),
+        );
+        recorder.record(
+            ProgramCounter::new(0, 3),
+            Instruction::new(OPCode::LStore2, None),
+        );
+        let trace = recorder.recording().unwrap();
+        let mut jit = JitCache::new();
+        jit.compile(&trace);
+
+        let mut frame = Frame::default();
+        frame.locals =
+            HashMap::from([(0, Value::Int(1)), (2, Value::Long(i64::MAX))]);
+        frame.max_locals = 4;
+        jit.execute(start, &mut frame.view());
+        assert_eq!(frame.locals.get(&0), Some(&Value::Int(1)));
+        assert_eq!(frame.locals.get(&2), Some(&Value::Long(300)));
+
+        // Locals that didn't hold a long before the store are typed by it.
+        for previous in [None, Some(Value::Int(7))] {
+            let mut frame = Frame::default();
+            frame.locals = HashMap::from_iter(previous.map(|v| (2, v)));
+            frame.max_locals = 4;
+            jit.execute(start, &mut frame.view());
+            assert_eq!(frame.locals.get(&2), Some(&Value::Long(300)));
+        }
+    }
+
+    #[test]
+    fn float_stores_are_compiled() {
+        let start = ProgramCounter::new(0, 0);
+        // Native code doesn't distinguish between load types, loading the
+        // slot with `iload` copies the bit pattern of the `float`.
+        let trace = Trace {
+            start,
+            trace: vec![
+                Record::new(
+                    ProgramCounter::new(0, 0),
+                    Instruction::new(OPCode::ILoad, Some(vec![Value::Int(0)])),
+                ),
+                Record::new(
+                    ProgramCounter::new(0, 1),
+                    Instruction::new(OPCode::FStore, Some(vec![Value::Int(1)])),
+                ),
+            ],
+        };
+        let mut jit = JitCache::new();
+        jit.compile(&trace);
+
+        let mut frame = Frame::default();
+        frame.locals = HashMap::from([
+            (0, Value::Float(1.5)),
+            (1, Value::Float(0.)),
+            (2, Value::Double(-2.25)),
+        ]);
+        frame.max_locals = 4;
+        jit.execute(start, &mut frame.view());
+        assert_eq!(frame.locals.get(&0), Some(&Value::Float(1.5)));
+        assert_eq!(frame.locals.get(&1), Some(&Value::Float(1.5)));
+        assert_eq!(frame.locals.get(&2), Some(&Value::Double(-2.25)));
+    }
+
+    #[test]
+    fn least_recently_executed_traces_are_evicted() {
+        let trace = |index| {
+            let start = ProgramCounter::new(0, index);
+            Trace {
+                start,
+                trace: vec![
+                    Record::new(
+                        start,
+                        Instruction::new(
+                            OPCode::SiPush,
+                            Some(vec![Value::Int(7)]),
+                        ),
+                    ),
+                    Record::new(
+                        start,
+                        Instruction::new(
+                            OPCode::IStore,
+                            Some(vec![Value::Int(0)]),
+                        ),
+                    ),
+                ],
+            }
+        };
+        let mut jit = JitCache::new();
+        assert!(jit.compile(&trace(0)).is_empty());
+        let size = jit.total_code_bytes();
+
+        let mut jit = JitCache::with_max_code_cache_size(2 * size);
+        assert!(jit.compile(&trace(0)).is_empty());
+        assert!(jit.compile(&trace(10)).is_empty());
+        let mut frame = Frame::default();
+        frame.max_locals = 1;
+        jit.execute(ProgramCounter::new(0, 0), &mut frame.view());
+        assert_eq!(jit.compile(&trace(20)), vec![ProgramCounter::new(0, 10)]);
+        assert_eq!(jit.total_code_bytes(), 2 * size);
+        assert!(jit.has_native_trace(ProgramCounter::new(0, 0)));
+        assert!(jit.has_native_trace(ProgramCounter::new(0, 20)));
+
+        // Traces larger than the code cache aren't cached.
+        let mut jit = JitCache::with_max_code_cache_size(size - 1);
+        assert!(jit.compile(&trace(0)).is_empty());
+        assert_eq!(jit.total_code_bytes(), 0);
+    }
+
+    #[test]
+    fn exit_pcs_keep_the_method_index() {
+        let pc = |index| ProgramCounter::new(3, index);
+        let trace = Trace {
+            start: pc(4),
+            trace: vec![
+                Record::new(
+                    pc(4),
+                    Instruction::new(OPCode::ILoad, Some(vec![Value::Int(0)])),
+                ),
+                Record::new(
+                    pc(5),
+                    Instruction::new(OPCode::BiPush, Some(vec![Value::Int(5)])),
+                ),
+                Record::new(
+                    pc(7),
+                    Instruction::new(
+                        OPCode::IfICmpGe,
+                        Some(vec![Value::Int(10)]),
+                    ),
+                ),
+                Record::new(
+                    pc(10),
+                    Instruction::new(
+                        OPCode::IInc,
+                        Some(vec![Value::Int(0), Value::Int(1)]),
+                    ),
+                ),
+                Record::new(
+                    pc(13),
+                    Instruction::new(OPCode::Goto, Some(vec![Value::Int(-9)])),
+                ),
+            ],
+        };
+        let mut jit = JitCache::new();
+        jit.compile(&trace);
+        let mut frame = Frame::default();
+        frame.locals = HashMap::from([(0, Value::Int(0))]);
+        frame.max_locals = 1;
+        assert_eq!(jit.execute(pc(4), &mut frame.view()), pc(17));
+        assert_eq!(frame.pc, pc(17));
+        assert_eq!(frame.locals.get(&0), Some(&Value::Int(5)));
+
+        let exit = ProgramCounter::new(1 << 31, u32::MAX as usize);
+        assert_eq!(decode_exit_pc(encode_exit_pc(exit)), exit);
+    }
+
+    #[test]
+    fn loop_exit_guards_are_stitched_to_the_trace_at_their_exit() {
+        // Counts the local at `index` up to `bound`, the loop exits 16 bytes
+        // after its header at `start`.
+        let counter = |start: usize, index: i32, bound: i32| {
+            let pc = |offset| ProgramCounter::new(0, start + offset);
+            Trace {
+                start: pc(0),
+                trace: vec![
+                    Record::new(
+                        pc(0),
+                        Instruction::new(
+                            OPCode::ILoad,
+                            Some(vec![Value::Int(index)]),
+                        ),
+                    ),
+                    Record::new(
+                        pc(2),
+                        Instruction::new(
+                            OPCode::BiPush,
+                            Some(vec![Value::Int(bound)]),
+                        ),
+                    ),
+                    Record::new(
+                        pc(4),
+                        Instruction::new(
+                            OPCode::IfICmpGe,
+                            Some(vec![Value::Int(12)]),
+                        ),
+                    ),
+                    Record::new(
+                        pc(7),
+                        Instruction::new(
+                            OPCode::IInc,
+                            Some(vec![Value::Int(index), Value::Int(1)]),
+                        ),
+                    ),
+                    Record::new(
+                        pc(10),
+                        Instruction::new(
+                            OPCode::Goto,
+                            Some(vec![Value::Int(-10)]),
+                        ),
+                    ),
+                ],
+            }
+        };
+        let first = counter(0, 0, 5);
+        let second = counter(16, 1, 10);
+        let run = |jit: &mut JitCache| {
+            let mut frame = Frame::default();
+            frame.locals =
+                HashMap::from([(0, Value::Int(0)), (1, Value::Int(0))]);
+            frame.max_locals = 2;
+            let exit = jit.execute(first.start, &mut frame.view());
+            (
+                exit.get_instruction_index(),
+                frame.locals[&0],
+                frame.locals[&1],
+            )
+        };
+
+        // The second trace is stitched after the first one is compiled.
+        let mut jit = JitCache::new();
+        jit.compile(&first);
+        assert_eq!(run(&mut jit), (16, Value::Int(5), Value::Int(0)));
+        jit.compile(&second);
+        let NativeTrace(_, buf, _, link) = &jit.traces[&first.start];
+        let link = link.expect("Expected the guard to have an exit link");
+        // The entry address is the immediate of a `mov rdx, imm64`.
+        assert_eq!(buf[link.slot.0 - 2..link.slot.0], [0x48, 0xba]);
+        assert!(link.linked);
+        assert_eq!(run(&mut jit), (32, Value::Int(5), Value::Int(10)));
+
+        // Evicting the second trace unlinks the first one.
+        assert!(jit.evict(second.start));
+        assert_eq!(run(&mut jit), (16, Value::Int(5), Value::Int(0)));
+
+        // The first trace is stitched when compiled after the second one.
+        let mut jit = JitCache::new();
+        jit.compile(&second);
+        jit.compile(&first);
+        assert_eq!(run(&mut jit), (32, Value::Int(5), Value::Int(10)));
+    }
+
+    // `ishl` isn't compiled, the loop runs in the interpreter.
+    run_jit_test_case!(
+        unsupported_instructions,
+        "support/tests/ShiftLoop.class",
+        Some(Value::Int(999000))
+    );
+
+    // Traces calling a method aren't compiled.
+    run_jit_test_case!(
+        method_calls,
+        "support/tests/CallLoop.class",
+        Some(Value::Int(999000))
+    );
+
+    #[test]
+    fn long_loop_counters_are_compiled() {
+        let env_var = env::var("CARGO_MANIFEST_DIR").unwrap();
+        let path = Path::new(&env_var).join("support/tests/LongCounter.class");
+        let class_file =
+            JVMParser::parse(&read_class_file(&path).unwrap()).unwrap();
+        let config = RuntimeConfig {
+            jit_threshold: 1,
+            ..RuntimeConfig::default()
+        };
+        let mut runtime = Runtime::new(Program::new(&class_file), config);
+        assert!(runtime.run(true).is_ok());
+        assert_eq!(runtime.top_return_value(), Some(Value::Long(4950)));
+        // The counter is compared with `lcmp` against a `ldc2_w` constant.
+        assert_eq!(runtime.native_traces().len(), 1);
+    }
+
+    #[test]
+    fn divisions_by_zero_exit_the_trace() {
+        let env_var = env::var("CARGO_MANIFEST_DIR").unwrap();
+        let path =
+            Path::new(&env_var).join("support/tests/DivideByZeroLoop.class");
+        let class_file =
+            JVMParser::parse(&read_class_file(&path).unwrap()).unwrap();
+        let config = RuntimeConfig {
+            jit_threshold: 1,
+            ..RuntimeConfig::default()
+        };
+        let mut runtime = Runtime::new(Program::new(&class_file), config);
+        // The divisor reaches zero in the native trace, which exits so the
+        // interpreter fails the division.
+        let err = runtime.run(true).unwrap_err();
+        assert_eq!(err.kind(), &RuntimeErrorKind::DivisionByZero);
+        assert_eq!(runtime.native_traces().len(), 1);
+    }
+
+    #[test]
+    fn min_value_divisions_by_minus_one_exit_the_trace() {
+        let env_var = env::var("CARGO_MANIFEST_DIR").unwrap();
+        let path =
+            Path::new(&env_var).join("support/tests/MinDivideLoop.class");
+        let class_file =
+            JVMParser::parse(&read_class_file(&path).unwrap()).unwrap();
+        let config = RuntimeConfig {
+            jit_threshold: 1,
+            ..RuntimeConfig::default()
+        };
+        let mut runtime = Runtime::new(Program::new(&class_file), config);
+        assert!(runtime.run(true).is_ok());
+        assert_eq!(runtime.native_traces().len(), 1);
+        let mut interpreted =
+            Runtime::new(Program::new(&class_file), RuntimeConfig::default());
+        assert!(interpreted.run(false).is_ok());
+        assert_eq!(runtime.top_return_value(), interpreted.top_return_value());
+    }
+
+    #[test]
+    fn traces_with_unsupported_instructions_are_not_compiled() {
+        let start = ProgramCounter::new(0, 0);
+        let record = |pc, opcode, operands: Option<Vec<Value>>| {
+            Record::new(
+                ProgramCounter::new(0, pc),
+                Instruction::new(opcode, operands),
+            )
+        };
+        let mut jit = JitCache::new();
+        for unsupported in [
+            record(2, OPCode::IShl, None),
+            record(2, OPCode::InvokeStatic, Some(vec![Value::Int(3)])),
+            record(2, OPCode::Ldc, Some(vec![Value::Float(1.5)])),
+        ] {
+            let trace = Trace {
+                start,
+                trace: vec![
+                    record(0, OPCode::ILoad, Some(vec![Value::Int(0)])),
+                    unsupported,
+                    record(5, OPCode::Goto, Some(vec![Value::Int(-5)])),
+                ],
+            };
+            assert!(jit.compile(&trace).is_empty());
+            assert!(!jit.has_native_trace(start));
+        }
+        assert_eq!(jit.compiled_count(), 0);
+    }
+
+    #[test]
+    fn traces_popping_two_slots_are_not_compiled() {
+        let start = ProgramCounter::new(0, 0);
+        // A `long` takes two slots but a single operand, `pop2` would also
+        // pop the operand below it.
+        let trace = Trace {
+            start,
+            trace: vec![
+                Record::new(
+                    start,
+                    Instruction::new(OPCode::LLoad, Some(vec![Value::Int(0)])),
+                ),
+                Record::new(
+                    ProgramCounter::new(0, 1),
+                    Instruction::new(OPCode::Pop2, None),
+                ),
+                Record::new(
+                    ProgramCounter::new(0, 2),
+                    Instruction::new(OPCode::Goto, Some(vec![Value::Int(-2)])),
+                ),
+            ],
+        };
+        let mut jit = JitCache::new();
+        assert!(jit.compile(&trace).is_empty());
+        assert!(!jit.has_native_trace(start));
+    }
+
+    #[test]
+    fn traces_branching_with_operands_on_the_stack_are_not_compiled() {
+        let start = ProgramCounter::new(0, 0);
+        let record = |pc, opcode, operands: Option<Vec<Value>>| {
+            Record::new(
+                ProgramCounter::new(0, pc),
+                Instruction::new(opcode, operands),
+            )
+        };
+        // `x + (y == 0 ? 1 : 2)` keeps `x` on the stack across the `ifne`.
+        let trace = Trace {
+            start,
+            trace: vec![
+                record(0, OPCode::ILoad, Some(vec![Value::Int(0)])),
+                record(1, OPCode::ILoad, Some(vec![Value::Int(1)])),
+                record(2, OPCode::IfNe, Some(vec![Value::Int(7)])),
+                record(5, OPCode::BiPush, Some(vec![Value::Int(1)])),
+                record(7, OPCode::IAdd, None),
+                record(8, OPCode::IStore, Some(vec![Value::Int(0)])),
+                record(9, OPCode::Goto, Some(vec![Value::Int(-9)])),
+            ],
+        };
+        let mut jit = JitCache::new();
+        assert!(jit.compile(&trace).is_empty());
+        assert!(!jit.has_native_trace(start));
+    }
+
+    #[test]
+    fn gotos_to_unrecorded_instructions_exit_the_trace() {
+        let start = ProgramCounter::new(0, 20);
+        let trace = Trace {
+            start,
+            trace: vec![
+                Record::new(
+                    start,
+                    Instruction::new(
+                        OPCode::IInc,
+                        Some(vec![Value::Int(0), Value::Int(1)]),
+                    ),
+                ),
+                Record::new(
+                    ProgramCounter::new(0, 23),
+                    Instruction::new(OPCode::Goto, Some(vec![Value::Int(-10)])),
+                ),
+            ],
+        };
+        let mut jit = JitCache::new();
+        jit.compile(&trace);
+        let mut frame = Frame::default();
+        frame.locals = HashMap::from([(0, Value::Int(1))]);
+        frame.max_locals = 1;
+        // The jump target at 13 precedes the trace, it's left to the
+        // interpreter after a single iteration.
+        assert_eq!(
+            jit.execute(start, &mut frame.view())
+                .get_instruction_index(),
+            13
+        );
+        assert_eq!(frame.locals.get(&0), Some(&Value::Int(2)));
+    }
+
+    #[test]
+    fn flipped_branches_are_taken_exactly_when_the_original_is_not() {
+        let pairs = [
+            (OPCode::IfEq, OPCode::IfNe),
+            (OPCode::IfLt, OPCode::IfGe),
+            (OPCode::IfGt, OPCode::IfLe),
+            (OPCode::IfICmpEq, OPCode::IfICmpNe),
+            (OPCode::IfICmpLt, OPCode::IfICmpGe),
+            (OPCode::IfICmpGt, OPCode::IfICmpLe),
+        ];
+        for (cond, flipped) in pairs {
+            assert_eq!(flip_branch(cond), flipped);
+            assert_eq!(flip_branch(flipped), cond);
+        }
+        assert_eq!(flip_branch(OPCode::Goto), OPCode::Goto);
+    }
+
+    #[test]
+    fn zero_comparisons_exit_the_trace_off_the_recorded_path() {
+        let record = |pc, opcode, operands: Vec<i32>| {
+            Record::new(
+                ProgramCounter::new(0, pc),
+                Instruction::new(
+                    opcode,
+                    Some(operands.into_iter().map(Value::Int).collect()),
+                ),
+            )
+        };
+        let run = |trace: Vec<Record>| {
+            let start = ProgramCounter::new(0, 0);
+            let mut jit = JitCache::new();
+            jit.compile(&Trace { start, trace });
+            let mut frame = Frame::default();
+            frame.locals = HashMap::from([(0, Value::Int(-5))]);
+            frame.max_locals = 1;
+            let exit = jit.execute(start, &mut frame.view());
+            (exit.get_instruction_index(), frame.locals[&0])
+        };
+        // while (x < 0) x++; recorded with `ifge` falling through, the trace
+        // exits at the branch target.
+        assert_eq!(
+            run(vec![
+                record(0, OPCode::ILoad, vec![0]),
+                record(2, OPCode::IfGe, vec![9]),
+                record(5, OPCode::IInc, vec![0, 1]),
+                record(8, OPCode::Goto, vec![-8]),
+            ]),
+            (11, Value::Int(0))
+        );
+        // Recorded with `iflt` taken, the trace exits at the instruction
+        // following the branch.
+        assert_eq!(
+            run(vec![
+                record(0, OPCode::ILoad, vec![0]),
+                record(2, OPCode::IfLt, vec![6]),
+                record(8, OPCode::IInc, vec![0, 1]),
+                record(11, OPCode::Goto, vec![-11]),
+            ]),
+            (5, Value::Int(0))
+        );
+        // x is incremented until it's no longer negative or zero.
+        assert_eq!(
+            run(vec![
+                record(0, OPCode::ILoad, vec![0]),
+                record(2, OPCode::IfGt, vec![9]),
+                record(5, OPCode::IInc, vec![0, 1]),
+                record(8, OPCode::Goto, vec![-8]),
+            ]),
+            (11, Value::Int(1))
+        );
+        assert_eq!(
+            run(vec![
+                record(0, OPCode::ILoad, vec![0]),
+                record(2, OPCode::IfLe, vec![6]),
+                record(8, OPCode::IInc, vec![0, 1]),
+                record(11, OPCode::Goto, vec![-11]),
+            ]),
+            (5, Value::Int(1))
+        );
+    }
+
+    #[test]
+    fn labels_are_not_shared_between_traces() {
+        let iinc = |pc| {
+            Record::new(
+                ProgramCounter::new(0, pc),
+                Instruction::new(
+                    OPCode::IInc,
+                    Some(vec![Value::Int(0), Value::Int(1)]),
+                ),
+            )
+        };
+        let goto = |pc, offset| {
+            Record::new(
+                ProgramCounter::new(0, pc),
+                Instruction::new(OPCode::Goto, Some(vec![Value::Int(offset)])),
+            )
+        };
+        let mut jit = JitCache::new();
+        // A trace looping at 10, then a trace at 20 jumping back to 10.
+        let first = Trace {
+            start: ProgramCounter::new(0, 10),
+            trace: vec![iinc(10), goto(13, -3)],
+        };
+        let second = Trace {
+            start: ProgramCounter::new(0, 20),
+            trace: vec![iinc(20), goto(23, -13)],
+        };
+        jit.compile(&first);
+        jit.compile(&second);
+        // The second trace exits to 10 instead of jumping to the label of the
+        // first trace.
+        let mut frame = Frame::default();
+        frame.locals = HashMap::from([(0, Value::Int(0))]);
+        frame.max_locals = 1;
+        assert_eq!(
+            jit.execute(second.start, &mut frame.view())
+                .get_instruction_index(),
+            10
+        );
+        assert_eq!(frame.locals.get(&0), Some(&Value::Int(1)));
+    }
+
+    #[test]
+    fn stack_manipulations_are_compiled() {
+        let start = ProgramCounter::new(0, 0);
+        let run = |insts: Vec<Instruction>| {
+            let trace = Trace {
+                start,
+                trace: insts
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, inst)| {
+                        Record::new(ProgramCounter::new(0, index), inst)
+                    })
+                    .collect(),
+            };
+            let mut jit = JitCache::new();
+            jit.compile(&trace);
+            let mut frame = Frame::default();
+            frame.locals =
+                HashMap::from([(0, Value::Int(5)), (1, Value::Int(7))]);
+            frame.max_locals = 3;
+            jit.execute(start, &mut frame.view());
+            frame.locals.get(&2).copied()
+        };
+        let iload = |index| {
+            Instruction::new(OPCode::ILoad, Some(vec![Value::Int(index)]))
+        };
+        let istore =
+            Instruction::new(OPCode::IStore, Some(vec![Value::Int(2)]));
+        let inst = |opcode| Instruction::new(opcode, None);
+
+        // x + x
+        assert_eq!(
+            run(vec![
+                iload(0),
+                inst(OPCode::Dup),
+                inst(OPCode::IAdd),
+                istore.clone()
+            ]),
+            Some(Value::Int(10))
+        );
+        // x + x * 3, the copy is mutated by `imul`.
+        assert_eq!(
+            run(vec![
+                iload(0),
+                inst(OPCode::Dup),
+                Instruction::new(OPCode::BiPush, Some(vec![Value::Int(3)])),
+                inst(OPCode::IMul),
+                inst(OPCode::IAdd),
+                istore.clone(),
+            ]),
+            Some(Value::Int(20))
+        );
+        // y - x
+        assert_eq!(
+            run(vec![
+                iload(0),
+                iload(1),
+                inst(OPCode::Swap),
+                inst(OPCode::ISub),
+                istore.clone()
+            ]),
+            Some(Value::Int(2))
+        );
+        // x
+        assert_eq!(
+            run(vec![iload(0), iload(1), inst(OPCode::Pop), istore]),
+            Some(Value::Int(5))
+        );
+    }
 }
@@ -2,13 +2,32 @@
 use byteorder::{BigEndian, ReadBytesExt};
 
 use std::collections::HashMap;
+use std::env;
+use std::fmt;
 use std::io;
 use std::io::{Cursor, Read, Seek};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 /// Values of magic bytes of a JVM class file.
 const JVM_CLASS_FILE_MAGIC: u32 = 0xCAFE_BABE;
 
+/// Class file major versions accepted by the parser, from JDK 1.1 to 21.
+const SUPPORTED_MAJOR_VERSIONS: std::ops::RangeInclusive<u16> = 45..=65;
+
+// Field and method access flags, see Table 4.5-A and Table 4.6-A of the JVM
+// specification.
+const ACC_PUBLIC: u16 = 0x0001;
+const ACC_PRIVATE: u16 = 0x0002;
+const ACC_PROTECTED: u16 = 0x0004;
+const ACC_STATIC: u16 = 0x0008;
+const ACC_FINAL: u16 = 0x0010;
+const ACC_SYNCHRONIZED: u16 = 0x0020;
+const ACC_VOLATILE: u16 = 0x0040;
+const ACC_NATIVE: u16 = 0x0100;
+const ACC_ABSTRACT: u16 = 0x0400;
+const ACC_STRICT: u16 = 0x0800;
+
 /// `CPInfo` represents constant pool entries,
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum CPInfo {
@@ -58,10 +77,21 @@ pub enum CPInfo {
     ConstantMethodType {
         descriptor_index: u16,
     },
+    ConstantDynamic {
+        bootstrap_method_attr_index: u16,
+        name_and_type_index: u16,
+    },
     ConstantInvokeDynamic {
         bootstrap_method_attr_index: u16,
         name_and_type_index: u16,
     },
+    // Modules and packages only appear in `module-info` class files.
+    ConstantModule {
+        name_index: u16,
+    },
+    ConstantPackage {
+        name_index: u16,
+    },
     // Proxy value used mostly to populate the gaps in the constant pool.
     Unspecified,
 }
@@ -85,9 +115,9 @@ enum ConstantKind {
     MethodType = 16,
     Dynamic = 17,
     InvokeDynamic = 18,
+    Module = 19,
+    Package = 20,
     // Unspecified or unsupported constant kinds.
-    // Module = 19,
-    // Package = 20,
     Unspecified,
 }
 
@@ -109,6 +139,8 @@ impl From<u8> for ConstantKind {
             16 => Self::MethodType,
             17 => Self::Dynamic,
             18 => Self::InvokeDynamic,
+            19 => Self::Module,
+            20 => Self::Package,
             _ => Self::Unspecified,
         }
     }
@@ -182,6 +214,60 @@ pub struct BootstrapMethod {
     arguments: Vec<u16>,
 }
 
+impl BootstrapMethod {
+    /// Build a bootstrap method from its method handle index and the indexes
+    /// of its static arguments.
+    #[must_use]
+    pub const fn new(method_ref: u16, arguments: Vec<u16>) -> Self {
+        Self {
+            method_ref,
+            arguments,
+        }
+    }
+
+    /// Returns the constant pool index of the bootstrap method handle.
+    #[must_use]
+    pub const fn method_ref(&self) -> u16 {
+        self.method_ref
+    }
+
+    /// Returns the constant pool indexes of the bootstrap method static
+    /// arguments.
+    #[must_use]
+    pub fn arguments(&self) -> &[u16] {
+        &self.arguments
+    }
+}
+
+/// Line number table entry, instructions starting at `start_pc` come from
+/// the source line `line_number`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineNumberEntry {
+    pub start_pc: u16,
+    pub line_number: u16,
+}
+
+/// Local variable table entry, the local at `index` is named by the
+/// constant `name_index` for the instructions in
+/// `start_pc..start_pc + length`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LocalVariableEntry {
+    pub start_pc: u16,
+    pub length: u16,
+    pub name_index: u16,
+    pub descriptor_index: u16,
+    pub index: u16,
+}
+
+impl LocalVariableEntry {
+    /// Returns true if the entry covers the instruction at `pc`.
+    #[must_use]
+    pub fn covers(&self, pc: usize) -> bool {
+        let start = usize::from(self.start_pc);
+        (start..start + usize::from(self.length)).contains(&pc)
+    }
+}
+
 /// Exception table.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ExceptionEntry {
@@ -191,6 +277,28 @@ pub struct ExceptionEntry {
     catch_type: u16,
 }
 
+impl ExceptionEntry {
+    /// Returns the range of bytecode offsets `[start_pc, end_pc)` covered by
+    /// the handler.
+    #[must_use]
+    pub const fn range(&self) -> (u16, u16) {
+        (self.start_pc, self.end_pc)
+    }
+
+    /// Returns the bytecode offset of the handler.
+    #[must_use]
+    pub const fn handler_pc(&self) -> u16 {
+        self.handler_pc
+    }
+
+    /// Returns the constant pool index of the class of the exceptions caught
+    /// by the handler, zero if it catches all exceptions (i.e `finally`).
+    #[must_use]
+    pub const fn catch_type(&self) -> u16 {
+        self.catch_type
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum AttributeInfo {
     ConstantValueAttribute {
@@ -225,6 +333,18 @@ pub enum AttributeInfo {
         classes: Vec<u16>,
         attribute_name: String,
     },
+    LineNumberTableAttribute {
+        entries: Vec<LineNumberEntry>,
+        attribute_name: String,
+    },
+    LocalVariableTableAttribute {
+        entries: Vec<LocalVariableEntry>,
+        attribute_name: String,
+    },
+    ExceptionsAttribute {
+        exception_index_table: Vec<u16>,
+        attribute_name: String,
+    },
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -270,7 +390,7 @@ pub struct JVMClassFile {
     _minor_version: u16,
     _major_version: u16,
     _constant_pool_count: u16,
-    constant_pool: Vec<CPInfo>,
+    constant_pool: Arc<[CPInfo]>,
     _access_flags: u16,
     _this_class: u16,
     _super_class: u16,
@@ -281,14 +401,43 @@ pub struct JVMClassFile {
     _methods_count: u16,
     methods: Vec<MethodInfo>,
     _attributes_count: u16,
-    _attributes: HashMap<String, AttributeInfo>,
+    attributes: HashMap<String, AttributeInfo>,
 }
 
 impl JVMClassFile {
-    /// Returns a copy of the underlying constant pool.
+    /// Returns a shared handle to the underlying constant pool.
+    #[must_use]
+    pub fn constant_pool(&self) -> Arc<[CPInfo]> {
+        Arc::clone(&self.constant_pool)
+    }
+
+    /// Returns the binary name of the class i.e `com/example/Main`.
     #[must_use]
-    pub fn constant_pool(&self) -> Vec<CPInfo> {
-        self.constant_pool.clone()
+    pub fn class_name(&self) -> Option<&str> {
+        let CPInfo::ConstantClass { name_index } =
+            self.constant_pool.get(self._this_class as usize)?
+        else {
+            return None;
+        };
+        match self.constant_pool.get(*name_index as usize)? {
+            CPInfo::ConstantUtf8 { bytes } => Some(bytes),
+            _ => None,
+        }
+    }
+
+    /// Returns the binary name of the superclass, `None` for
+    /// `java/lang/Object`.
+    #[must_use]
+    pub fn super_class_name(&self) -> Option<&str> {
+        let CPInfo::ConstantClass { name_index } =
+            self.constant_pool.get(self._super_class as usize)?
+        else {
+            return None;
+        };
+        match self.constant_pool.get(*name_index as usize)? {
+            CPInfo::ConstantUtf8 { bytes } => Some(bytes),
+            _ => None,
+        }
     }
 
     /// Returns a copy of the underlying methods vector.
@@ -296,31 +445,150 @@ impl JVMClassFile {
     pub fn methods(&self) -> Vec<MethodInfo> {
         self.methods.clone()
     }
+
+    /// Returns the class bootstrap methods used by `invokedynamic` call
+    /// sites, empty if the class has no `BootstrapMethods` attribute.
+    #[must_use]
+    pub fn bootstrap_methods(&self) -> &[BootstrapMethod] {
+        match self.attributes.get("BootstrapMethods") {
+            Some(AttributeInfo::BootstrapMethodsAttribute {
+                bootstrap_methods,
+                ..
+            }) => bootstrap_methods,
+            _ => &[],
+        }
+    }
 }
 
-/// `JVMParser` namespaces functions that handle parsing of Java class files.
+/// `ParseError` represents the possible failures when parsing a class file.
 #[derive(Debug)]
-pub struct JVMParser;
+pub enum ParseError {
+    /// The class file doesn't start with `0xCAFEBABE`.
+    InvalidMagic { found: u32 },
+    /// The class file major version isn't supported.
+    UnsupportedVersion { major: u16 },
+    /// A constant pool entry at `index` has an unknown tag.
+    UnknownConstantKind { tag: u8, index: usize },
+    /// A `CONSTANT_Utf8` entry isn't valid UTF-8.
+    InvalidUtf8,
+    /// An attribute name doesn't point to a `CONSTANT_Utf8` entry.
+    InvalidAttributeName { index: u16 },
+    /// A `StackMapTable` frame has a reserved tag.
+    InvalidStackMapFrame { tag: u8 },
+    /// Access flags violate the specification (strict mode only).
+    InvalidAccessFlags { flags: u16, reason: &'static str },
+    /// An attribute appears more than once (strict mode only).
+    DuplicateAttribute { name: String },
+    /// Reading the class file failed, usually because it's truncated.
+    IoError(io::Error),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidMagic { found } => {
+                write!(f, "Invalid magic bytes {found:#010x}")
+            }
+            Self::UnsupportedVersion { major } => {
+                write!(f, "Unsupported class file major version {major}")
+            }
+            Self::UnknownConstantKind { tag, index } => {
+                write!(f, "Unknown constant kind with tag {tag} at {index}")
+            }
+            Self::InvalidUtf8 => write!(f, "Invalid UTF-8 constant"),
+            Self::InvalidAttributeName { index } => {
+                write!(f, "Attribute name at {index} isn't a UTF-8 constant")
+            }
+            Self::InvalidStackMapFrame { tag } => {
+                write!(f, "Invalid stack map frame tag {tag}")
+            }
+            Self::InvalidAccessFlags { flags, reason } => {
+                write!(f, "Invalid access flags {flags:#06x} : {reason}")
+            }
+            Self::DuplicateAttribute { name } => {
+                write!(f, "Duplicate attribute {name}")
+            }
+            Self::IoError(err) => {
+                write!(f, "Failed to read class file : {err}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::IoError(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ParseError {
+    fn from(err: io::Error) -> Self {
+        Self::IoError(err)
+    }
+}
+
+/// `JVMParser` namespaces functions that handle parsing of Java class files.
+///
+/// By default duplicate attributes are reported as warnings and only the
+/// first occurrence is kept, in strict mode they are rejected.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct JVMParser {
+    strict: bool,
+}
 
 impl JVMParser {
+    /// Create a new parser.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Enable or disable strict mode, in strict mode malformed class files
+    /// (i.e duplicate attributes or invalid access flags) are rejected
+    /// instead of accepted with a warning.
+    #[must_use]
+    pub const fn strict_mode(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Parse a Java class file with the default parser configuration.
+    /// # Errors
+    /// Returns `ParseError` if the class file is truncated or malformed.
+    pub fn parse(class_file_bytes: &[u8]) -> Result<JVMClassFile, ParseError> {
+        Self::new().parse_class_file(class_file_bytes)
+    }
+
     /// Parse a Java class file.
     /// # Errors
-    /// Returns `io::Error` in case a `std::io::Read` fails.
-    /// # Panics
-    /// Can panic if file isn't valid, since we don't handle some
-    /// `std::io::Read` failures.
-    pub fn parse(class_file_bytes: &[u8]) -> io::Result<JVMClassFile> {
+    /// Returns `ParseError` if the class file is truncated or malformed, in
+    /// strict mode duplicate attributes and invalid access flags are errors.
+    pub fn parse_class_file(
+        &self,
+        class_file_bytes: &[u8],
+    ) -> Result<JVMClassFile, ParseError> {
         // Create a new cursor on the class file bytes.
         let mut buffer = Cursor::new(class_file_bytes);
         // Read magic header..
         let magic = buffer.read_u32::<BigEndian>()?;
+        if magic != JVM_CLASS_FILE_MAGIC {
+            return Err(ParseError::InvalidMagic { found: magic });
+        }
         // Read the class file version numbers.
         let minor_version = buffer.read_u16::<BigEndian>()?;
         let major_version = buffer.read_u16::<BigEndian>()?;
+        if !SUPPORTED_MAJOR_VERSIONS.contains(&major_version) {
+            return Err(ParseError::UnsupportedVersion {
+                major: major_version,
+            });
+        }
         // Read the number of constants in the pool.
         let cp_size = buffer.read_u16::<BigEndian>()?;
         // Parse the constant pool.
-        let constant_pool = parse_constant_pool(&mut buffer, cp_size as usize);
+        let constant_pool = parse_constant_pool(&mut buffer, cp_size as usize)?;
         // Extra class file metdata.
         let access_flags = buffer.read_u16::<BigEndian>()?;
         let this_class = buffer.read_u16::<BigEndian>()?;
@@ -335,20 +603,20 @@ impl JVMParser {
         }
         // Field information.
         let (fields_count, fields) =
-            parse_field_information(&mut buffer, &constant_pool);
+            parse_field_information(&mut buffer, &constant_pool, self.strict)?;
         // Methods.
         let (methods_count, methods) =
-            parse_method_information(&mut buffer, &constant_pool);
+            parse_method_information(&mut buffer, &constant_pool, self.strict)?;
         // Attributes.
         let (attributes_count, attributes) =
-            parse_attribute_info(&mut buffer, &constant_pool);
+            parse_attribute_info(&mut buffer, &constant_pool, self.strict)?;
 
         Ok(JVMClassFile {
             _magic: magic,
             _minor_version: minor_version,
             _major_version: major_version,
             _constant_pool_count: cp_size,
-            constant_pool,
+            constant_pool: constant_pool.into(),
             _access_flags: access_flags,
             _this_class: this_class,
             _super_class: super_class,
@@ -359,7 +627,7 @@ impl JVMParser {
             _methods_count: methods_count,
             methods,
             _attributes_count: attributes_count,
-            _attributes: attributes,
+            attributes,
         })
     }
 }
@@ -368,134 +636,149 @@ impl JVMParser {
 fn parse_constant_pool(
     reader: &mut (impl Read + Seek),
     pool_size: usize,
-) -> Vec<CPInfo> {
+) -> Result<Vec<CPInfo>, ParseError> {
     // We preallocate because indexing is shifted and we know the pool size.
     let mut constant_pool = vec![CPInfo::Unspecified; pool_size];
     // The first entry in the pool is at index 1 according to JVM
     // spec.
-    #[allow(unused_assignments)]
-    (1..pool_size).for_each(|mut ii| {
-        let tag = reader.read_u8().unwrap();
+    let mut ii = 1;
+    while ii < pool_size {
+        let tag = reader.read_u8()?;
         match ConstantKind::from(tag) {
             ConstantKind::Class => {
                 constant_pool[ii] = CPInfo::ConstantClass {
-                    name_index: reader.read_u16::<BigEndian>().unwrap(),
+                    name_index: reader.read_u16::<BigEndian>()?,
                 };
             }
             ConstantKind::FieldRef => {
                 constant_pool[ii] = CPInfo::ConstantFieldRef {
-                    class_index: reader.read_u16::<BigEndian>().unwrap(),
-                    name_and_type_index: reader
-                        .read_u16::<BigEndian>()
-                        .unwrap(),
+                    class_index: reader.read_u16::<BigEndian>()?,
+                    name_and_type_index: reader.read_u16::<BigEndian>()?,
                 };
             }
             ConstantKind::MethodRef => {
                 constant_pool[ii] = CPInfo::ConstantMethodRef {
-                    class_index: reader.read_u16::<BigEndian>().unwrap(),
-                    name_and_type_index: reader
-                        .read_u16::<BigEndian>()
-                        .unwrap(),
+                    class_index: reader.read_u16::<BigEndian>()?,
+                    name_and_type_index: reader.read_u16::<BigEndian>()?,
                 };
             }
             ConstantKind::InterfaceMethodRef => {
                 constant_pool[ii] = CPInfo::ConstantInterfaceMethodRef {
-                    class_index: reader.read_u16::<BigEndian>().unwrap(),
-                    name_and_type_index: reader
-                        .read_u16::<BigEndian>()
-                        .unwrap(),
+                    class_index: reader.read_u16::<BigEndian>()?,
+                    name_and_type_index: reader.read_u16::<BigEndian>()?,
                 };
             }
             ConstantKind::String => {
                 constant_pool[ii] = CPInfo::ConstantString {
-                    string_index: reader.read_u16::<BigEndian>().unwrap(),
+                    string_index: reader.read_u16::<BigEndian>()?,
                 };
             }
             ConstantKind::Integer => {
                 constant_pool[ii] = CPInfo::ConstantInteger {
-                    bytes: reader.read_u32::<BigEndian>().unwrap(),
+                    bytes: reader.read_u32::<BigEndian>()?,
                 };
             }
             ConstantKind::Float => {
                 constant_pool[ii] = CPInfo::ConstantFloat {
-                    bytes: reader.read_u32::<BigEndian>().unwrap(),
+                    bytes: reader.read_u32::<BigEndian>()?,
                 };
             }
             ConstantKind::Long => {
                 constant_pool[ii] = CPInfo::ConstantLong {
-                    hi_bytes: reader.read_u32::<BigEndian>().unwrap(),
-                    lo_bytes: reader.read_u32::<BigEndian>().unwrap(),
+                    hi_bytes: reader.read_u32::<BigEndian>()?,
+                    lo_bytes: reader.read_u32::<BigEndian>()?,
                 };
                 ii += 1;
             }
             ConstantKind::Double => {
                 constant_pool[ii] = CPInfo::ConstantDouble {
-                    hi_bytes: reader.read_u32::<BigEndian>().unwrap(),
-                    lo_bytes: reader.read_u32::<BigEndian>().unwrap(),
+                    hi_bytes: reader.read_u32::<BigEndian>()?,
+                    lo_bytes: reader.read_u32::<BigEndian>()?,
                 };
                 ii += 1;
             }
             ConstantKind::NameAndType => {
                 constant_pool[ii] = CPInfo::ConstantNameAndType {
-                    name_index: reader.read_u16::<BigEndian>().unwrap(),
-                    descriptor_index: reader.read_u16::<BigEndian>().unwrap(),
+                    name_index: reader.read_u16::<BigEndian>()?,
+                    descriptor_index: reader.read_u16::<BigEndian>()?,
                 };
             }
             ConstantKind::Utf8 => {
-                let length = reader.read_u16::<BigEndian>().unwrap();
+                let length = reader.read_u16::<BigEndian>()?;
                 let mut buf = vec![0u8; length as usize];
-                reader.read_exact(&mut buf).unwrap();
+                reader.read_exact(&mut buf)?;
                 constant_pool[ii] = CPInfo::ConstantUtf8 {
-                    bytes: String::from_utf8(buf).unwrap(),
+                    bytes: String::from_utf8(buf)
+                        .map_err(|_| ParseError::InvalidUtf8)?,
                 };
             }
             ConstantKind::MethodHandle => {
-                let ref_kind = reader.read_u8().unwrap();
-                let ref_index = reader.read_u16::<BigEndian>().unwrap();
+                let ref_kind = reader.read_u8()?;
+                let ref_index = reader.read_u16::<BigEndian>()?;
                 constant_pool[ii] = CPInfo::ConstantMethodHandle {
                     reference_kind: ref_kind,
                     reference_index: ref_index,
                 };
             }
             ConstantKind::MethodType => {
-                let desc_index = reader.read_u16::<BigEndian>().unwrap();
+                let desc_index = reader.read_u16::<BigEndian>()?;
                 constant_pool[ii] = CPInfo::ConstantMethodType {
                     descriptor_index: desc_index,
                 };
             }
+            ConstantKind::Dynamic => {
+                let bootstrap_method_attr_index =
+                    reader.read_u16::<BigEndian>()?;
+                let name_and_type_index = reader.read_u16::<BigEndian>()?;
+                constant_pool[ii] = CPInfo::ConstantDynamic {
+                    bootstrap_method_attr_index,
+                    name_and_type_index,
+                };
+            }
             ConstantKind::InvokeDynamic => {
                 let bootstrap_method_attr_index =
-                    reader.read_u16::<BigEndian>().unwrap();
-                let name_and_type_index =
-                    reader.read_u16::<BigEndian>().unwrap();
+                    reader.read_u16::<BigEndian>()?;
+                let name_and_type_index = reader.read_u16::<BigEndian>()?;
                 constant_pool[ii] = CPInfo::ConstantInvokeDynamic {
                     bootstrap_method_attr_index,
                     name_and_type_index,
                 };
             }
-            _ => panic!(
-                "Unexpected constant kind {:?} with tag {}",
-                ConstantKind::from(tag),
-                tag
-            ),
+            ConstantKind::Module => {
+                let name_index = reader.read_u16::<BigEndian>()?;
+                constant_pool[ii] = CPInfo::ConstantModule { name_index };
+            }
+            ConstantKind::Package => {
+                let name_index = reader.read_u16::<BigEndian>()?;
+                constant_pool[ii] = CPInfo::ConstantPackage { name_index };
+            }
+            _ => {
+                return Err(ParseError::UnknownConstantKind { tag, index: ii })
+            }
         }
-    });
-    constant_pool
+        ii += 1;
+    }
+    Ok(constant_pool)
 }
 
 /// Parse field information.
 fn parse_field_information(
     reader: &mut (impl Read + Seek),
     constant_pool: &[CPInfo],
-) -> (u16, Vec<FieldInfo>) {
-    let fields_count = reader.read_u16::<BigEndian>().unwrap();
+    strict: bool,
+) -> Result<(u16, Vec<FieldInfo>), ParseError> {
+    let fields_count = reader.read_u16::<BigEndian>()?;
     let mut fields: Vec<FieldInfo> = Vec::new();
 
     for _ in 0..fields_count {
-        let access_flag = reader.read_u16::<BigEndian>().unwrap();
-        let name_index = reader.read_u16::<BigEndian>().unwrap();
-        let descriptor_index = reader.read_u16::<BigEndian>().unwrap();
-        let (_, attributes) = parse_attribute_info(reader, constant_pool);
+        let access_flag = reader.read_u16::<BigEndian>()?;
+        if strict {
+            validate_field_access_flags(access_flag)?;
+        }
+        let name_index = reader.read_u16::<BigEndian>()?;
+        let descriptor_index = reader.read_u16::<BigEndian>()?;
+        let (_, attributes) =
+            parse_attribute_info(reader, constant_pool, strict)?;
         fields.push(FieldInfo {
             access_flag,
             name_index,
@@ -504,22 +787,27 @@ fn parse_field_information(
         });
     }
 
-    (fields_count, fields)
+    Ok((fields_count, fields))
 }
 
 /// Parse method infromation.
 fn parse_method_information(
     reader: &mut (impl Read + Seek),
     constant_pool: &[CPInfo],
-) -> (u16, Vec<MethodInfo>) {
-    let methods_count = reader.read_u16::<BigEndian>().unwrap();
+    strict: bool,
+) -> Result<(u16, Vec<MethodInfo>), ParseError> {
+    let methods_count = reader.read_u16::<BigEndian>()?;
     let mut methods: Vec<MethodInfo> = Vec::new();
 
     for _ in 0..methods_count {
-        let access_flag = reader.read_u16::<BigEndian>().unwrap();
-        let name_index = reader.read_u16::<BigEndian>().unwrap();
-        let descriptor_index = reader.read_u16::<BigEndian>().unwrap();
-        let (_, attributes) = parse_attribute_info(reader, constant_pool);
+        let access_flag = reader.read_u16::<BigEndian>()?;
+        if strict {
+            validate_method_access_flags(access_flag)?;
+        }
+        let name_index = reader.read_u16::<BigEndian>()?;
+        let descriptor_index = reader.read_u16::<BigEndian>()?;
+        let (_, attributes) =
+            parse_attribute_info(reader, constant_pool, strict)?;
         methods.push(MethodInfo {
             access_flag,
             name_index,
@@ -528,26 +816,85 @@ fn parse_method_information(
         });
     }
 
-    (methods_count, methods)
+    Ok((methods_count, methods))
+}
+
+/// Returns an error describing invalid access flags.
+const fn invalid_access_flags(flags: u16, reason: &'static str) -> ParseError {
+    ParseError::InvalidAccessFlags { flags, reason }
+}
+
+/// Validate field access flags against the constraints of the JVM
+/// specification (Table 4.5-A).
+/// # Errors
+/// Returns `ParseError::InvalidAccessFlags` if more than one of `ACC_PUBLIC`,
+/// `ACC_PRIVATE` and `ACC_PROTECTED` is set or if the field is both
+/// `ACC_FINAL` and `ACC_VOLATILE`.
+pub fn validate_field_access_flags(flags: u16) -> Result<(), ParseError> {
+    if (flags & (ACC_PUBLIC | ACC_PRIVATE | ACC_PROTECTED)).count_ones() > 1 {
+        return Err(invalid_access_flags(
+            flags,
+            "field has more than one visibility",
+        ));
+    }
+    if flags & ACC_FINAL != 0 && flags & ACC_VOLATILE != 0 {
+        return Err(invalid_access_flags(
+            flags,
+            "field can't be both final and volatile",
+        ));
+    }
+    Ok(())
+}
+
+/// Validate method access flags against the constraints of the JVM
+/// specification (Table 4.6-A).
+///
+/// Constraints that depend on the enclosing class (i.e interface methods)
+/// aren't checked.
+/// # Errors
+/// Returns `ParseError::InvalidAccessFlags` if more than one of `ACC_PUBLIC`,
+/// `ACC_PRIVATE` and `ACC_PROTECTED` is set or if an `ACC_ABSTRACT` method
+/// is also private, static, final, synchronized, native or strict.
+pub fn validate_method_access_flags(flags: u16) -> Result<(), ParseError> {
+    if (flags & (ACC_PUBLIC | ACC_PRIVATE | ACC_PROTECTED)).count_ones() > 1 {
+        return Err(invalid_access_flags(
+            flags,
+            "method has more than one visibility",
+        ));
+    }
+    let not_abstract = ACC_PRIVATE
+        | ACC_STATIC
+        | ACC_FINAL
+        | ACC_SYNCHRONIZED
+        | ACC_NATIVE
+        | ACC_STRICT;
+    if flags & ACC_ABSTRACT != 0 && flags & not_abstract != 0 {
+        return Err(invalid_access_flags(
+            flags,
+            "abstract method has incompatible modifiers",
+        ));
+    }
+    Ok(())
 }
 
 /// Parse code attribute
 fn parse_code_attribute(
     reader: &mut (impl Read + Seek),
     constant_pool: &[CPInfo],
-) -> AttributeInfo {
-    let max_stack = reader.read_u16::<BigEndian>().unwrap();
-    let max_locals = reader.read_u16::<BigEndian>().unwrap();
-    let code_length = reader.read_u32::<BigEndian>().unwrap();
+    strict: bool,
+) -> Result<AttributeInfo, ParseError> {
+    let max_stack = reader.read_u16::<BigEndian>()?;
+    let max_locals = reader.read_u16::<BigEndian>()?;
+    let code_length = reader.read_u32::<BigEndian>()?;
     let mut buf = vec![0u8; code_length as usize];
-    reader.read_exact(&mut buf).unwrap();
-    let exception_table_length = reader.read_u16::<BigEndian>().unwrap();
+    reader.read_exact(&mut buf)?;
+    let exception_table_length = reader.read_u16::<BigEndian>()?;
     let mut exception_table_entries: Vec<ExceptionEntry> = Vec::new();
     for _ in 0..exception_table_length {
-        let start_pc = reader.read_u16::<BigEndian>().unwrap();
-        let end_pc = reader.read_u16::<BigEndian>().unwrap();
-        let handler_pc = reader.read_u16::<BigEndian>().unwrap();
-        let catch_type = reader.read_u16::<BigEndian>().unwrap();
+        let start_pc = reader.read_u16::<BigEndian>()?;
+        let end_pc = reader.read_u16::<BigEndian>()?;
+        let handler_pc = reader.read_u16::<BigEndian>()?;
+        let catch_type = reader.read_u16::<BigEndian>()?;
 
         exception_table_entries.push(ExceptionEntry {
             start_pc,
@@ -556,46 +903,51 @@ fn parse_code_attribute(
             catch_type,
         });
     }
-    let (_, attributes) = parse_attribute_info(reader, constant_pool);
-    AttributeInfo::CodeAttribute {
+    let (_, attributes) = parse_attribute_info(reader, constant_pool, strict)?;
+    Ok(AttributeInfo::CodeAttribute {
         max_stack,
         max_locals,
         code: buf,
         exception_table: exception_table_entries,
         attributes,
         attribute_name: "Code".to_string(),
-    }
+    })
 }
 
 /// Parse attributes.
 fn parse_attribute_info(
     reader: &mut (impl Read + Seek),
     constant_pool: &[CPInfo],
-) -> (u16, HashMap<String, AttributeInfo>) {
-    let attribute_count = reader.read_u16::<BigEndian>().unwrap();
+    strict: bool,
+) -> Result<(u16, HashMap<String, AttributeInfo>), ParseError> {
+    let attribute_count = reader.read_u16::<BigEndian>()?;
     let mut attributes: HashMap<String, AttributeInfo> = HashMap::new();
     for _ in 0..attribute_count {
-        let attribute_name_index = reader.read_u16::<BigEndian>().unwrap();
-        let attr_name = &constant_pool[attribute_name_index as usize];
+        let attribute_name_index = reader.read_u16::<BigEndian>()?;
+        let attr_name = constant_pool.get(attribute_name_index as usize);
         let attribute_name = match attr_name {
-            CPInfo::ConstantUtf8 { bytes } => bytes.clone(),
-            _ => panic!(
-                "Expected attribute name to be CPInfo::ConstantUtf8 got {attr_name:?}",
-            ),
+            Some(CPInfo::ConstantUtf8 { bytes }) => bytes.clone(),
+            _ => {
+                return Err(ParseError::InvalidAttributeName {
+                    index: attribute_name_index,
+                })
+            }
         };
-        let attribute_length = reader.read_u32::<BigEndian>().unwrap();
+        let attribute_length = reader.read_u32::<BigEndian>()?;
         let attribute_info = match attribute_name.as_str() {
             "ConstantValue" => Some(AttributeInfo::ConstantValueAttribute {
-                constant_value_index: reader.read_u16::<BigEndian>().unwrap(),
+                constant_value_index: reader.read_u16::<BigEndian>()?,
                 attribute_name: attribute_name.clone(),
             }),
-            "Code" => Some(parse_code_attribute(reader, constant_pool)),
+            "Code" => {
+                Some(parse_code_attribute(reader, constant_pool, strict)?)
+            }
             "StackMapTable" => {
-                let number_of_entries = reader.read_u16::<BigEndian>().unwrap();
+                let number_of_entries = reader.read_u16::<BigEndian>()?;
                 let mut stack_map_entries: Vec<StackMapFrame> = Vec::new();
                 for _ in 0..number_of_entries {
-                    let tag = reader.read_u8().unwrap();
-                    let frame = parse_stack_frame_entry(reader, tag);
+                    let tag = reader.read_u8()?;
+                    let frame = parse_stack_frame_entry(reader, tag)?;
                     stack_map_entries.push(frame);
                 }
                 Some(AttributeInfo::StackMapTableAttribute {
@@ -604,22 +956,20 @@ fn parse_attribute_info(
                 })
             }
             "SourceFile" => Some(AttributeInfo::SourceFileAttribute {
-                source_file_index: reader.read_u16::<BigEndian>().unwrap(),
+                source_file_index: reader.read_u16::<BigEndian>()?,
                 attribute_name: "SourceFile".to_string(),
             }),
             "BootstrapMethods" => {
-                let num_bootstrap_methods =
-                    reader.read_u16::<BigEndian>().unwrap();
+                let num_bootstrap_methods = reader.read_u16::<BigEndian>()?;
                 let mut bootstrap_method_table: Vec<BootstrapMethod> =
                     Vec::new();
 
                 for _ in 0..num_bootstrap_methods {
-                    let method_ref = reader.read_u16::<BigEndian>().unwrap();
-                    let argument_count =
-                        reader.read_u16::<BigEndian>().unwrap();
+                    let method_ref = reader.read_u16::<BigEndian>()?;
+                    let argument_count = reader.read_u16::<BigEndian>()?;
                     let mut arguments = Vec::new();
                     for _ in 0..argument_count {
-                        let arg = reader.read_u16::<BigEndian>().unwrap();
+                        let arg = reader.read_u16::<BigEndian>()?;
                         arguments.push(arg);
                     }
                     bootstrap_method_table.push(BootstrapMethod {
@@ -634,14 +984,14 @@ fn parse_attribute_info(
                 })
             }
             "NestHost" => Some(AttributeInfo::NestHostAttribute {
-                host_class_index: reader.read_u16::<BigEndian>().unwrap(),
+                host_class_index: reader.read_u16::<BigEndian>()?,
                 attribute_name: "NestHost".to_string(),
             }),
             "NestMembers" => {
-                let num_classes = reader.read_u16::<BigEndian>().unwrap();
+                let num_classes = reader.read_u16::<BigEndian>()?;
                 let mut classes = Vec::new();
                 for _ in 0..num_classes {
-                    let class_index = reader.read_u16::<BigEndian>().unwrap();
+                    let class_index = reader.read_u16::<BigEndian>()?;
                     classes.push(class_index);
                 }
                 Some(AttributeInfo::NestMembersAttribute {
@@ -649,25 +999,81 @@ fn parse_attribute_info(
                     attribute_name: "NestMembers".to_string(),
                 })
             }
+            "LineNumberTable" => {
+                let table_length = reader.read_u16::<BigEndian>()?;
+                let mut entries = Vec::new();
+                for _ in 0..table_length {
+                    entries.push(LineNumberEntry {
+                        start_pc: reader.read_u16::<BigEndian>()?,
+                        line_number: reader.read_u16::<BigEndian>()?,
+                    });
+                }
+                Some(AttributeInfo::LineNumberTableAttribute {
+                    entries,
+                    attribute_name: "LineNumberTable".to_string(),
+                })
+            }
+            "LocalVariableTable" => {
+                let table_length = reader.read_u16::<BigEndian>()?;
+                let mut entries = Vec::new();
+                for _ in 0..table_length {
+                    entries.push(LocalVariableEntry {
+                        start_pc: reader.read_u16::<BigEndian>()?,
+                        length: reader.read_u16::<BigEndian>()?,
+                        name_index: reader.read_u16::<BigEndian>()?,
+                        descriptor_index: reader.read_u16::<BigEndian>()?,
+                        index: reader.read_u16::<BigEndian>()?,
+                    });
+                }
+                Some(AttributeInfo::LocalVariableTableAttribute {
+                    entries,
+                    attribute_name: "LocalVariableTable".to_string(),
+                })
+            }
+            "Exceptions" => {
+                let number_of_exceptions = reader.read_u16::<BigEndian>()?;
+                let mut exception_index_table = Vec::new();
+                for _ in 0..number_of_exceptions {
+                    exception_index_table.push(reader.read_u16::<BigEndian>()?);
+                }
+                Some(AttributeInfo::ExceptionsAttribute {
+                    exception_index_table,
+                    attribute_name: "Exceptions".to_string(),
+                })
+            }
             _ => {
-                reader
-                    .seek(std::io::SeekFrom::Current(i64::from(
-                        attribute_length,
-                    )))
-                    .unwrap();
+                reader.seek(std::io::SeekFrom::Current(i64::from(
+                    attribute_length,
+                )))?;
                 None
             }
         };
-        let _ = attribute_info.map_or((), |attr| {
-            attributes.insert(attribute_name.clone(), attr);
-        });
+        if let Some(attr) = attribute_info {
+            if attributes.contains_key(&attribute_name) {
+                if strict {
+                    return Err(ParseError::DuplicateAttribute {
+                        name: attribute_name,
+                    });
+                }
+                #[cfg(feature = "tracing")]
+                tracing::warn!(
+                    attribute_name,
+                    "duplicate attribute, keeping the first one"
+                );
+                continue;
+            }
+            attributes.insert(attribute_name, attr);
+        }
     }
-    (attribute_count, attributes)
+    Ok((attribute_count, attributes))
 }
 
 /// Helper function to parse the `StackMapFrameTable` entry give a tag.
-fn parse_stack_frame_entry(reader: &mut impl Read, tag: u8) -> StackMapFrame {
-    match tag {
+fn parse_stack_frame_entry(
+    reader: &mut impl Read,
+    tag: u8,
+) -> Result<StackMapFrame, ParseError> {
+    let frame = match tag {
         0..=63 => StackMapFrame {
             t: StackMapFrameType::Same,
             offset_delta: 0,
@@ -678,38 +1084,38 @@ fn parse_stack_frame_entry(reader: &mut impl Read, tag: u8) -> StackMapFrame {
             t: StackMapFrameType::SameLocals,
             offset_delta: 0,
             locals: vec![],
-            stack: parse_verification_info(reader, 1),
+            stack: parse_verification_info(reader, 1)?,
         },
         247 => StackMapFrame {
             t: StackMapFrameType::SameLocalsExtended,
             offset_delta: 0,
             locals: vec![],
-            stack: parse_verification_info(reader, 1),
+            stack: parse_verification_info(reader, 1)?,
         },
         248..=250 => StackMapFrame {
             t: StackMapFrameType::Chop,
-            offset_delta: reader.read_u16::<BigEndian>().unwrap(),
+            offset_delta: reader.read_u16::<BigEndian>()?,
             locals: vec![],
             stack: vec![],
         },
         251 => StackMapFrame {
             t: StackMapFrameType::SameExtended,
-            offset_delta: reader.read_u16::<BigEndian>().unwrap(),
+            offset_delta: reader.read_u16::<BigEndian>()?,
             locals: vec![],
             stack: vec![],
         },
         252..=254 => StackMapFrame {
             t: StackMapFrameType::Append,
-            offset_delta: reader.read_u16::<BigEndian>().unwrap(),
-            locals: parse_verification_info(reader, (tag - 251).into()),
+            offset_delta: reader.read_u16::<BigEndian>()?,
+            locals: parse_verification_info(reader, (tag - 251).into())?,
             stack: vec![],
         },
         255 => {
-            let offset_delta = reader.read_u16::<BigEndian>().unwrap();
-            let n_locals_entries = reader.read_u16::<BigEndian>().unwrap();
-            let locals = parse_verification_info(reader, n_locals_entries);
-            let n_stack_entries = reader.read_u16::<BigEndian>().unwrap();
-            let stack = parse_verification_info(reader, n_stack_entries);
+            let offset_delta = reader.read_u16::<BigEndian>()?;
+            let n_locals_entries = reader.read_u16::<BigEndian>()?;
+            let locals = parse_verification_info(reader, n_locals_entries)?;
+            let n_stack_entries = reader.read_u16::<BigEndian>()?;
+            let stack = parse_verification_info(reader, n_stack_entries)?;
             StackMapFrame {
                 t: StackMapFrameType::Full,
                 offset_delta,
@@ -717,23 +1123,24 @@ fn parse_stack_frame_entry(reader: &mut impl Read, tag: u8) -> StackMapFrame {
                 stack,
             }
         }
-        _ => unreachable!("Unexpected tag entry {tag}"),
-    }
+        _ => return Err(ParseError::InvalidStackMapFrame { tag }),
+    };
+    Ok(frame)
 }
 
 /// Helper function parse verification info.
 fn parse_verification_info(
     reader: &mut impl Read,
     num_entries: u16,
-) -> Vec<VerificationInfo> {
+) -> Result<Vec<VerificationInfo>, ParseError> {
     let mut verifications: Vec<VerificationInfo> = Vec::new();
     for _ in 0..num_entries {
-        let tag = VerificationType::from(reader.read_u8().unwrap());
+        let tag = VerificationType::from(reader.read_u8()?);
         let cpool_index_or_offset = if tag
             == VerificationType::ObjectVerification
             || tag == VerificationType::UninitializedVerification
         {
-            reader.read_u16::<BigEndian>().unwrap()
+            reader.read_u16::<BigEndian>()?
         } else {
             0
         };
@@ -742,31 +1149,173 @@ fn parse_verification_info(
             cpool_index_or_offset,
         });
     }
-    verifications
+    Ok(verifications)
 }
 
-/// Helper function to read file into a buffer.
-/// # Panics
-/// Function panics on any `File::open` error.
+/// `ClassPath` is the list of directories and JAR archives searched when
+/// looking up classes that aren't part of the program.
+#[derive(Debug, Default, Clone)]
+pub struct ClassPath {
+    entries: Vec<PathBuf>,
+}
+
+impl ClassPath {
+    /// Create a class path from a list of directories and JAR archives.
+    #[must_use]
+    pub fn new(entries: Vec<PathBuf>) -> Self {
+        Self { entries }
+    }
+
+    /// Parse a class path given as a list of entries separated by the
+    /// platform path separator (`:` on Unix).
+    #[must_use]
+    pub fn parse(class_path: &str) -> Self {
+        Self::new(env::split_paths(class_path).collect())
+    }
+
+    /// Returns the class path entries.
+    #[must_use]
+    pub fn entries(&self) -> &[PathBuf] {
+        &self.entries
+    }
+
+    /// Search the class path entries in order for the class with the given
+    /// binary name and returns its bytes, or `None` if no entry has it.
+    /// # Errors
+    /// Returns `io::Error` if an entry containing the class can't be read.
+    pub fn find_class(&self, class_name: &str) -> io::Result<Option<Vec<u8>>> {
+        for entry in &self.entries {
+            if entry.is_dir() {
+                let path = entry.join(format!("{class_name}.class"));
+                if path.is_file() {
+                    return read_class_file(&path).map(Some);
+                }
+            } else if entry.is_file()
+                && read_jar_class_names(entry)?
+                    .iter()
+                    .any(|name| name == class_name)
+            {
+                return read_from_jar(entry, class_name).map(Some);
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Helper function to read file into a buffer, the contents are validated
+/// when the class file is parsed.
+/// # Errors
+/// Returns `io::Error` if the file can't be opened or read.
 pub fn read_class_file(fp: &Path) -> io::Result<Vec<u8>> {
+    std::fs::read(fp)
+}
+
+/// Helper function to read a class file from a JAR archive into a buffer,
+/// the class name can be given either in binary form `com/example/Main`
+/// or in its dotted form `com.example.Main`.
+/// # Errors
+/// Returns `io::Error` if the archive can't be opened or doesn't contain
+/// the requested class.
+pub fn read_from_jar(jar_path: &Path, class_name: &str) -> io::Result<Vec<u8>> {
     use std::fs::File;
-    use std::io::prelude::*;
 
-    let mut f = File::open(fp).unwrap();
+    let mut archive = zip::ZipArchive::new(File::open(jar_path)?)?;
+    let entry_name = format!("{}.class", class_name.replace('.', "/"));
+    let mut entry = archive.by_name(&entry_name)?;
     let mut buffer = Vec::new();
+    entry.read_to_end(&mut buffer)?;
+
+    if buffer.len() < 4
+        || u32::from_be_bytes(
+            buffer[0..4].try_into().expect("slice with wrong length"),
+        ) != JVM_CLASS_FILE_MAGIC
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{entry_name} is not a valid class file"),
+        ));
+    }
+    Ok(buffer)
+}
+
+/// Parse all the class files stored in a JAR archive, in archive order.
+/// # Errors
+/// Returns `io::Error` if the archive can't be read or one of its class files
+/// can't be parsed.
+pub fn read_jar(jar_path: &Path) -> io::Result<Vec<JVMClassFile>> {
+    read_jar_archive(std::fs::File::open(jar_path)?)
+}
 
-    match f.read_to_end(&mut buffer) {
-        Ok(bytes_read) => {
-            assert!(bytes_read == f.metadata()?.len() as usize);
-            assert!(
-                u32::from_be_bytes(
-                    buffer[0..4].try_into().expect("slice with wrong length")
-                ) == JVM_CLASS_FILE_MAGIC
-            );
-            Ok(buffer)
+/// Parse all the class files stored in the JAR archive read from `reader`.
+fn read_jar_archive<R: Read + Seek>(
+    reader: R,
+) -> io::Result<Vec<JVMClassFile>> {
+    let mut archive = zip::ZipArchive::new(reader)?;
+    let mut class_files = Vec::new();
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index)?;
+        if !entry.name().ends_with(".class") {
+            continue;
         }
-        Err(err) => Err(err),
+        let mut buffer = Vec::new();
+        entry.read_to_end(&mut buffer)?;
+        let class_file = JVMParser::parse(&buffer).map_err(|err| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} : {err}", entry.name()),
+            )
+        })?;
+        class_files.push(class_file);
     }
+    Ok(class_files)
+}
+
+/// Returns the binary names of all the classes stored in a JAR archive.
+/// # Errors
+/// Returns `io::Error` if the archive can't be opened.
+pub fn read_jar_class_names(jar_path: &Path) -> io::Result<Vec<String>> {
+    use std::fs::File;
+
+    let archive = zip::ZipArchive::new(File::open(jar_path)?)?;
+    Ok(archive
+        .file_names()
+        .filter_map(|name| name.strip_suffix(".class"))
+        .map(str::to_string)
+        .collect())
+}
+
+/// Returns the `Main-Class` attribute of the manifest of a JAR archive.
+/// # Errors
+/// Returns `io::Error` if the archive has no manifest or the manifest doesn't
+/// declare a main class.
+pub fn read_jar_main_class(jar_path: &Path) -> io::Result<String> {
+    use std::fs::File;
+
+    let mut archive = zip::ZipArchive::new(File::open(jar_path)?)?;
+    let mut manifest = String::new();
+    archive
+        .by_name("META-INF/MANIFEST.MF")?
+        .read_to_string(&mut manifest)?;
+
+    // Manifest lines are wrapped at 72 bytes, continuation lines start with
+    // a single space.
+    let mut attributes: Vec<String> = Vec::new();
+    for line in manifest.lines() {
+        match (line.strip_prefix(' '), attributes.last_mut()) {
+            (Some(continuation), Some(last)) => last.push_str(continuation),
+            _ => attributes.push(line.to_string()),
+        }
+    }
+    attributes
+        .iter()
+        .find_map(|attr| attr.strip_prefix("Main-Class:"))
+        .map(|main_class| main_class.trim().to_string())
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                "JAR manifest doesn't declare a Main-Class",
+            )
+        })
 }
 
 #[cfg(test)]
@@ -794,6 +1343,348 @@ mod tests {
         assert!(class_file._major_version > 61);
     }
 
+    #[test]
+    fn reading_missing_class_files_fails() {
+        let result = read_class_file(Path::new("support/tests/Missing.class"));
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn can_read_class_file_from_jar() {
+        let env_var = env::var("CARGO_MANIFEST_DIR").unwrap();
+        let path = Path::new(&env_var).join("support/jar/Hello.jar");
+        let main_class = read_jar_main_class(&path).unwrap_or_else(|_| {
+            panic!("Failed to read manifest : {:?}", path.as_os_str())
+        });
+        assert_eq!(main_class, "Hello");
+        let class_file_bytes = read_from_jar(&path, &main_class)
+            .unwrap_or_else(|_| {
+                panic!("Failed to read class from : {:?}", path.as_os_str())
+            });
+        let result = JVMParser::parse(&class_file_bytes);
+        assert!(result.is_ok());
+        assert!(read_from_jar(&path, "Missing").is_err());
+    }
+
+    #[test]
+    fn can_read_all_class_files_from_jar() {
+        use std::io::Write;
+        use zip::write::{FileOptions, ZipWriter};
+
+        let env_var = env::var("CARGO_MANIFEST_DIR").unwrap();
+        let root = Path::new(&env_var).join("support/tests");
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer
+            .start_file("META-INF/MANIFEST.MF", FileOptions::default())
+            .unwrap();
+        writer.write_all(b"Main-Class: Dispatch\n").unwrap();
+        for class_name in ["Dispatch", "DispatchHelper"] {
+            let class_file_bytes =
+                read_class_file(&root.join(format!("{class_name}.class")))
+                    .unwrap();
+            writer
+                .start_file(
+                    format!("{class_name}.class"),
+                    FileOptions::default(),
+                )
+                .unwrap();
+            writer.write_all(&class_file_bytes).unwrap();
+        }
+        let archive = writer.finish().unwrap().into_inner();
+
+        let class_files = read_jar_archive(Cursor::new(&archive)).unwrap();
+        let class_names: Vec<_> = class_files
+            .iter()
+            .map(|class_file| class_file.class_name().unwrap())
+            .collect();
+        assert_eq!(class_names, ["Dispatch", "DispatchHelper"]);
+
+        // Class files that fail to parse fail the whole archive.
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer
+            .start_file("Broken.class", FileOptions::default())
+            .unwrap();
+        writer.write_all(&[0xCA, 0xFE]).unwrap();
+        let archive = writer.finish().unwrap().into_inner();
+        assert_eq!(
+            read_jar_archive(Cursor::new(&archive)).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+
+    #[test]
+    fn duplicate_attributes_are_rejected_in_strict_mode() {
+        let env_var = env::var("CARGO_MANIFEST_DIR").unwrap();
+        let path = Path::new(&env_var).join("support/tests/Loop.class");
+        let mut class_file_bytes = read_class_file(&path).unwrap();
+        // The class file ends with a single `SourceFile` attribute, duplicate
+        // it and bump the attributes count.
+        let len = class_file_bytes.len();
+        let source_file = class_file_bytes[len - 8..].to_vec();
+        class_file_bytes[len - 10..len - 8]
+            .copy_from_slice(&2u16.to_be_bytes());
+        class_file_bytes.extend(source_file);
+
+        let class_file = JVMParser::parse(&class_file_bytes);
+        assert!(class_file.is_ok());
+        assert_eq!(class_file.unwrap().attributes.len(), 1);
+        let err = JVMParser::new()
+            .strict_mode(true)
+            .parse_class_file(&class_file_bytes)
+            .unwrap_err();
+        assert!(matches!(err, ParseError::DuplicateAttribute { .. }));
+    }
+
+    #[test]
+    fn can_validate_access_flags() {
+        // `public static final` field and `public static` method.
+        assert!(validate_field_access_flags(0x0019).is_ok());
+        assert!(validate_method_access_flags(0x0009).is_ok());
+        // `public abstract` method.
+        assert!(validate_method_access_flags(0x0401).is_ok());
+        // `public private` field and method.
+        assert!(validate_field_access_flags(0x0003).is_err());
+        assert!(validate_method_access_flags(0x0003).is_err());
+        // `final volatile` field.
+        assert!(validate_field_access_flags(0x0050).is_err());
+        // `abstract synchronized` and `abstract static` methods.
+        assert!(validate_method_access_flags(0x0420).is_err());
+        assert!(validate_method_access_flags(0x0408).is_err());
+    }
+
+    #[test]
+    fn invalid_access_flags_are_rejected_in_strict_mode() {
+        let env_var = env::var("CARGO_MANIFEST_DIR").unwrap();
+        let path = Path::new(&env_var).join("support/tests/Loop.class");
+        let class_file_bytes = read_class_file(&path).unwrap();
+        let class_file = JVMParser::parse(&class_file_bytes).unwrap();
+        // Mark the first method as both `public` and `private`.
+        let method = class_file.methods()[0].clone();
+        let mut offset = None;
+        for index in 0..class_file_bytes.len() - 6 {
+            let flags = u16::from_be_bytes([
+                class_file_bytes[index],
+                class_file_bytes[index + 1],
+            ]);
+            let name = &class_file_bytes[index + 2..index + 4];
+            let descriptor = &class_file_bytes[index + 4..index + 6];
+            if flags == method.access_flag
+                && name == method.name_index.to_be_bytes()
+                && descriptor == method.descriptor_index.to_be_bytes()
+            {
+                offset = Some(index);
+                break;
+            }
+        }
+        let offset = offset.unwrap();
+        let mut patched = class_file_bytes.clone();
+        patched[offset..offset + 2].copy_from_slice(
+            &(method.access_flag | ACC_PUBLIC | ACC_PRIVATE).to_be_bytes(),
+        );
+
+        assert!(JVMParser::parse(&patched).is_ok());
+        let err = JVMParser::new()
+            .strict_mode(true)
+            .parse_class_file(&patched)
+            .unwrap_err();
+        assert!(matches!(err, ParseError::InvalidAccessFlags { .. }));
+        assert!(JVMParser::new()
+            .strict_mode(true)
+            .parse_class_file(&class_file_bytes)
+            .is_ok());
+    }
+
+    #[test]
+    fn malformed_class_files_return_parse_errors() {
+        let env_var = env::var("CARGO_MANIFEST_DIR").unwrap();
+        let path =
+            Path::new(&env_var).join("support/tests/SingleFuncCall.class");
+        let class_file_bytes = read_class_file(&path).unwrap();
+
+        let mut patched = class_file_bytes.clone();
+        patched[0] = 0xCB;
+        assert!(matches!(
+            JVMParser::parse(&patched),
+            Err(ParseError::InvalidMagic { found: 0xCBFE_BABE })
+        ));
+        let mut patched = class_file_bytes.clone();
+        patched[6..8].copy_from_slice(&99u16.to_be_bytes());
+        assert!(matches!(
+            JVMParser::parse(&patched),
+            Err(ParseError::UnsupportedVersion { major: 99 })
+        ));
+        // The first constant pool entry starts after the pool size.
+        let mut patched = class_file_bytes.clone();
+        patched[10] = 2;
+        assert!(matches!(
+            JVMParser::parse(&patched),
+            Err(ParseError::UnknownConstantKind { tag: 2, index: 1 })
+        ));
+        assert!(matches!(
+            JVMParser::parse(&class_file_bytes[..class_file_bytes.len() / 2]),
+            Err(ParseError::IoError(_))
+        ));
+    }
+
+    #[test]
+    fn wide_constants_take_two_entries() {
+        // Pool with a `CONSTANT_Long` followed by a `CONSTANT_Integer`.
+        let bytes = [5, 0, 0, 0, 0, 0, 0, 0, 2, 3, 0, 0, 0, 7];
+        let constant_pool =
+            parse_constant_pool(&mut Cursor::new(bytes), 4).unwrap();
+        assert_eq!(
+            constant_pool,
+            vec![
+                CPInfo::Unspecified,
+                CPInfo::ConstantLong {
+                    hi_bytes: 0,
+                    lo_bytes: 2
+                },
+                CPInfo::Unspecified,
+                CPInfo::ConstantInteger { bytes: 7 },
+            ]
+        );
+    }
+
+    #[test]
+    fn can_parse_dynamic_constants() {
+        // Pool with a `CONSTANT_Dynamic` and a `CONSTANT_InvokeDynamic`.
+        let bytes = [17, 0, 1, 0, 2, 18, 0, 3, 0, 4];
+        let constant_pool =
+            parse_constant_pool(&mut Cursor::new(bytes), 3).unwrap();
+        assert_eq!(
+            constant_pool,
+            vec![
+                CPInfo::Unspecified,
+                CPInfo::ConstantDynamic {
+                    bootstrap_method_attr_index: 1,
+                    name_and_type_index: 2,
+                },
+                CPInfo::ConstantInvokeDynamic {
+                    bootstrap_method_attr_index: 3,
+                    name_and_type_index: 4,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn can_parse_module_info() {
+        let env_var = env::var("CARGO_MANIFEST_DIR").unwrap();
+        let path =
+            Path::new(&env_var).join("support/modules/module-info.class");
+        let class_file_bytes = read_class_file(&path).unwrap();
+        let class_file = JVMParser::parse(&class_file_bytes).unwrap();
+        let constant_pool = class_file.constant_pool();
+        let utf8 = |index: u16| match &constant_pool[index as usize] {
+            CPInfo::ConstantUtf8 { bytes } => bytes.as_str(),
+            entry => panic!("Expected a Utf8 entry, found {entry:?}"),
+        };
+        let modules: Vec<&str> = constant_pool
+            .iter()
+            .filter_map(|entry| match entry {
+                CPInfo::ConstantModule { name_index } => {
+                    Some(utf8(*name_index))
+                }
+                _ => None,
+            })
+            .collect();
+        let packages: Vec<&str> = constant_pool
+            .iter()
+            .filter_map(|entry| match entry {
+                CPInfo::ConstantPackage { name_index } => {
+                    Some(utf8(*name_index))
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(modules, vec!["com.example.greeter", "java.base"]);
+        assert_eq!(packages, vec!["com/example/greeter"]);
+        assert_eq!(class_file.class_name(), Some("module-info"));
+        assert!(class_file.methods().is_empty());
+    }
+
+    #[test]
+    fn can_parse_local_variable_tables() {
+        let env_var = env::var("CARGO_MANIFEST_DIR").unwrap();
+        let path = Path::new(&env_var).join("support/tests/Locals.class");
+        let class_file_bytes = read_class_file(&path).unwrap();
+        let class_file = JVMParser::parse(&class_file_bytes).unwrap();
+        let utf8 =
+            |index: u16| match &class_file.constant_pool()[index as usize] {
+                CPInfo::ConstantUtf8 { bytes } => bytes.clone(),
+                _ => panic!("expected an utf8 constant at {index}"),
+            };
+        let sum = class_file
+            .methods()
+            .into_iter()
+            .find(|method| utf8(method.name_index()) == "sum")
+            .unwrap();
+        let Some(AttributeInfo::CodeAttribute { attributes, .. }) =
+            sum.attributes().get("Code").cloned()
+        else {
+            panic!("expected a Code attribute");
+        };
+        let Some(AttributeInfo::LocalVariableTableAttribute {
+            entries, ..
+        }) = attributes.get("LocalVariableTable").cloned()
+        else {
+            panic!("expected a LocalVariableTable attribute");
+        };
+        let locals: Vec<(u16, u16, u16, String, String)> = entries
+            .iter()
+            .map(|entry| {
+                (
+                    entry.start_pc,
+                    entry.length,
+                    entry.index,
+                    utf8(entry.name_index),
+                    utf8(entry.descriptor_index),
+                )
+            })
+            .collect();
+        assert_eq!(
+            locals,
+            vec![
+                (4, 15, 2, "i".to_string(), "I".to_string()),
+                (0, 21, 0, "n".to_string(), "I".to_string()),
+                (2, 19, 1, "total".to_string(), "I".to_string()),
+            ]
+        );
+        assert!(entries[0].covers(4) && entries[0].covers(18));
+        assert!(!entries[0].covers(3) && !entries[0].covers(19));
+    }
+
+    #[test]
+    fn can_parse_declared_exceptions() {
+        let env_var = env::var("CARGO_MANIFEST_DIR").unwrap();
+        let path = Path::new(&env_var).join("support/tests/Throws.class");
+        let class_file_bytes = read_class_file(&path).unwrap();
+        let class_file = JVMParser::parse(&class_file_bytes).unwrap();
+        let constant_pool = class_file.constant_pool();
+        let utf8 = |index: u16| match &constant_pool[index as usize] {
+            CPInfo::ConstantUtf8 { bytes } => bytes.as_str(),
+            _ => panic!("expected an utf8 constant at {index}"),
+        };
+        let read = class_file
+            .methods()
+            .into_iter()
+            .find(|method| utf8(method.name_index()) == "read")
+            .unwrap();
+        let Some(AttributeInfo::ExceptionsAttribute {
+            exception_index_table,
+            ..
+        }) = read.attributes().get("Exceptions").cloned()
+        else {
+            panic!("expected an Exceptions attribute");
+        };
+        assert_eq!(exception_index_table, vec![16]);
+        let CPInfo::ConstantClass { name_index } = constant_pool[16] else {
+            panic!("expected a class constant");
+        };
+        assert_eq!(utf8(name_index), "java/io/IOException");
+    }
+
     #[test]
     fn can_parse_class_file_header() {
         let env_var = env::var("CARGO_MANIFEST_DIR").unwrap();
@@ -810,7 +1701,7 @@ mod tests {
             _minor_version: 0,
             _major_version: 64,
             _constant_pool_count: 31,
-            constant_pool: vec![
+            constant_pool: Arc::from(vec![
                 CPInfo::Unspecified,
                 CPInfo::ConstantMethodRef {
                     class_index: 2,
@@ -902,7 +1793,7 @@ mod tests {
                 CPInfo::ConstantUtf8 {
                     bytes: "SingleFuncCall.java".to_string(),
                 },
-            ],
+            ]),
             _access_flags: 33,
             _this_class: 8,
             _super_class: 2,
@@ -923,7 +1814,17 @@ mod tests {
                             max_locals: 1,
                             code: vec![42, 183, 0, 1, 177],
                             exception_table: vec![],
-                            attributes: HashMap::new(),
+                            attributes: HashMap::from([(
+                                "LineNumberTable".to_string(),
+                                AttributeInfo::LineNumberTableAttribute {
+                                    entries: vec![LineNumberEntry {
+                                        start_pc: 0,
+                                        line_number: 1,
+                                    }],
+                                    attribute_name: "LineNumberTable"
+                                        .to_string(),
+                                },
+                            )]),
                             attribute_name: "Code".to_string(),
                         },
                     )]),
@@ -942,7 +1843,27 @@ mod tests {
                                 19, 177,
                             ],
                             exception_table: vec![],
-                            attributes: HashMap::new(),
+                            attributes: HashMap::from([(
+                                "LineNumberTable".to_string(),
+                                AttributeInfo::LineNumberTableAttribute {
+                                    entries: vec![
+                                        LineNumberEntry {
+                                            start_pc: 0,
+                                            line_number: 3,
+                                        },
+                                        LineNumberEntry {
+                                            start_pc: 6,
+                                            line_number: 4,
+                                        },
+                                        LineNumberEntry {
+                                            start_pc: 13,
+                                            line_number: 5,
+                                        },
+                                    ],
+                                    attribute_name: "LineNumberTable"
+                                        .to_string(),
+                                },
+                            )]),
                             attribute_name: "Code".to_string(),
                         },
                     )]),
@@ -958,14 +1879,24 @@ mod tests {
                             max_locals: 2,
                             code: vec![26, 27, 96, 172],
                             exception_table: vec![],
-                            attributes: HashMap::new(),
+                            attributes: HashMap::from([(
+                                "LineNumberTable".to_string(),
+                                AttributeInfo::LineNumberTableAttribute {
+                                    entries: vec![LineNumberEntry {
+                                        start_pc: 0,
+                                        line_number: 8,
+                                    }],
+                                    attribute_name: "LineNumberTable"
+                                        .to_string(),
+                                },
+                            )]),
                             attribute_name: "Code".to_string(),
                         },
                     )]),
                 },
             ],
             _attributes_count: 1,
-            _attributes: HashMap::from([(
+            attributes: HashMap::from([(
                 "SourceFile".to_string(),
                 AttributeInfo::SourceFileAttribute {
                     source_file_index: 30,
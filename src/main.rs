@@ -1,9 +1,11 @@
 use std::env;
 use std::process::exit;
 
-use coldbrew::jvm::{read_class_file, JVMParser};
+use coldbrew::jvm::{
+    read_class_file, read_jar_main_class, ClassPath, JVMParser,
+};
 use coldbrew::program::Program;
-use coldbrew::runtime::Runtime;
+use coldbrew::runtime::{Runtime, RuntimeConfig};
 
 const USAGE_CMD: &str = "
     Coldbrew Tracing JIT usage guide :
@@ -11,17 +13,72 @@ const USAGE_CMD: &str = "
     Run `coldbrew unit` to run small test programs (interpreter only).
     Run `coldbrew integration` to run end to end CPU intensive test programs (interpreter only).
     Run `coldbrew jit` to run small test programs with hot loops (interpreter + tracing jit).
+    Run `coldbrew jar <path>` to run the main class of a JAR archive (interpreter only).
     Run `coldbrew help` to see this message.
+
+    Pass `--opcode-stats` to print the most executed opcodes of each program.
+    Pass `--stats` to print the hottest loops of each program.
+    Pass `--strict` to reject malformed class files instead of warning.
+    Pass `--class-path <paths>` (or `-cp <paths>`) to search the given `:`
+    separated directories and JAR archives for the classes a program calls
+    into, each class is loaded the first time one of its methods is called.
+    Pass `--threshold <n>` to compile loops entered more than `n` times.
+    Pass `--dump-trace` to print the machine code of each native trace, needs
+    a build with the `jit-disasm` feature.
 ";
 
+// Number of opcodes printed by `--opcode-stats`.
+const OPCODE_STATS_COUNT: usize = 20;
+
+// Number of loop headers printed by `--stats`.
+const HOT_LOOPS_COUNT: usize = 10;
+
 fn main() {
     // Decide which test files to run.
     let args: Vec<String> = env::args().collect();
-    let jit_mode = args[1].as_str() == "jit";
     assert!(
         (args.len() >= 2),
         "Unexpected argument use `coldbrew help` to see usage guide."
     );
+    let jit_mode = args[1].as_str() == "jit";
+    let reports = Reports {
+        opcode_stats: args.iter().any(|arg| arg == "--opcode-stats"),
+        profile_stats: args.iter().any(|arg| arg == "--stats"),
+        dump_trace: args.iter().any(|arg| arg == "--dump-trace"),
+    };
+    let parser =
+        JVMParser::new().strict_mode(args.iter().any(|arg| arg == "--strict"));
+    let class_path = match args
+        .iter()
+        .position(|arg| arg == "--class-path" || arg == "-cp")
+    {
+        Some(index) => {
+            let Some(class_path) = args.get(index + 1) else {
+                println!("Expected a class path `--class-path <paths>`.");
+                exit(64);
+            };
+            ClassPath::parse(class_path)
+        }
+        None => ClassPath::default(),
+    };
+    let mut config = RuntimeConfig::default();
+    if let Some(index) = args.iter().position(|arg| arg == "--threshold") {
+        let Some(threshold) =
+            args.get(index + 1).and_then(|arg| arg.parse().ok())
+        else {
+            println!("Expected a number of loop entries `--threshold <n>`.");
+            exit(64);
+        };
+        config.jit_threshold = threshold;
+    }
+    if args[1].as_str() == "jar" {
+        let Some(jar_path) = args.get(2).map(std::path::Path::new) else {
+            println!("Expected a path to a JAR file `coldbrew jar <path>`.");
+            exit(64);
+        };
+        run_jar(jar_path, reports, &class_path, config);
+        exit(0);
+    }
     let folder = match args[1].as_str() {
         "unit" => "./support/tests/",
         "integration" => "./support/integration/",
@@ -46,6 +103,20 @@ fn main() {
         "MEDouble.class",
         "FloatFibonacci.class",
         "LongFibonacci.class",
+        "CrossClass.class",
+        "MathUtils.class",
+        "StringArg.class",
+        "NativeMethod.class",
+        "DivisionByZero.class",
+        "DivideByZeroLoop.class",
+        "Recursion.class",
+        "Dispatch.class",
+        "DispatchHelper.class",
+        "Base.class",
+        "Derived.class",
+        "StaticInit.class",
+        "StaticConfig.class",
+        "Uncaught.class",
     ];
     for path in std::path::Path::new(folder).read_dir().unwrap() {
         let path = match path {
@@ -65,24 +136,143 @@ fn main() {
         }
     }
     for path in &paths {
-        let class_file_bytes = read_class_file(path).unwrap_or_else(|_| {
-            panic!("Failed to read class file : {:?}", path.as_os_str())
-        });
-        let class_file =
-            JVMParser::parse(&class_file_bytes).unwrap_or_else(|_| {
-                panic!("Failed to parse class file {:?}", path.as_os_str())
+        run_class(path, &parser, jit_mode, reports, &class_path, config);
+    }
+}
+
+/// Run a single class file parsed with `parser`, classes it uses are
+/// searched in `class_path` and the JIT is tuned by `config`.
+fn run_class(
+    path: &std::path::Path,
+    parser: &JVMParser,
+    jit_mode: bool,
+    reports: Reports,
+    class_path: &ClassPath,
+    config: RuntimeConfig,
+) {
+    let class_file_bytes = read_class_file(path).unwrap_or_else(|_| {
+        panic!("Failed to read class file : {:?}", path.as_os_str())
+    });
+    let class_file =
+        parser
+            .parse_class_file(&class_file_bytes)
+            .unwrap_or_else(|err| {
+                panic!(
+                    "Failed to parse class file {:?} : {err}",
+                    path.as_os_str()
+                )
             });
 
-        let program = Program::new(&class_file);
-        let mut runtime = Runtime::new(program);
-        match runtime.run(jit_mode) {
-            Ok(()) => {
-                println!(
-                    "[+] Program {:?} finished running successfully !",
-                    path.file_name().unwrap()
-                );
-            }
-            Err(err) => println!("Error : {err}"),
+    let program = Program::new(&class_file);
+    let mut runtime =
+        Runtime::new(program, config).with_class_path(class_path.clone());
+    match runtime.run(jit_mode) {
+        Ok(()) => {
+            println!(
+                "[+] Program {:?} finished running successfully !",
+                path.file_name().unwrap()
+            );
+            print_reports(&runtime, reports);
+        }
+        Err(err) => println!("Error : {err}"),
+    }
+}
+
+/// Run the class declared as `Main-Class` in the manifest of a JAR archive.
+fn run_jar(
+    jar_path: &std::path::Path,
+    reports: Reports,
+    class_path: &ClassPath,
+    config: RuntimeConfig,
+) {
+    let main_class = read_jar_main_class(jar_path).unwrap_or_else(|err| {
+        panic!(
+            "Failed to read JAR manifest {:?} : {err}",
+            jar_path.as_os_str()
+        )
+    });
+    let program = Program::from_jar(jar_path).unwrap_or_else(|err| {
+        panic!("Failed to load JAR {:?} : {err}", jar_path.as_os_str())
+    });
+    let mut runtime =
+        Runtime::new(program, config).with_class_path(class_path.clone());
+    match runtime.run(false) {
+        Ok(()) => {
+            println!(
+                "[+] Program {main_class} finished running successfully !"
+            );
+            print_reports(&runtime, reports);
         }
+        Err(err) => println!("Error : {err}"),
+    }
+}
+
+/// Reports printed after a program finished running, selected by flags.
+#[derive(Debug, Clone, Copy)]
+struct Reports {
+    // `--opcode-stats`, see `print_opcode_stats`.
+    opcode_stats: bool,
+    // `--stats`, see `print_profile_stats`.
+    profile_stats: bool,
+    // `--dump-trace`, see `print_native_traces`.
+    dump_trace: bool,
+}
+
+/// Print the reports selected by `reports` for the program run by `runtime`.
+fn print_reports(runtime: &Runtime, reports: Reports) {
+    if reports.opcode_stats {
+        print_opcode_stats(runtime);
+    }
+    if reports.profile_stats {
+        print_profile_stats(runtime);
+    }
+    if reports.dump_trace {
+        print_native_traces(runtime);
     }
 }
+
+/// Print the most executed opcodes of the program run by `runtime`.
+fn print_opcode_stats(runtime: &Runtime) {
+    let mut histogram: Vec<_> = runtime.opcode_histogram().iter().collect();
+    histogram.sort_by(|(lhs_opcode, lhs), (rhs_opcode, rhs)| {
+        rhs.cmp(lhs)
+            .then_with(|| lhs_opcode.mnemonic().cmp(rhs_opcode.mnemonic()))
+    });
+    for (opcode, count) in histogram.iter().take(OPCODE_STATS_COUNT) {
+        println!("{:>16} {count}", opcode.mnemonic());
+    }
+}
+
+/// Print the number of interpreted instructions and the hottest loops of the
+/// program run by `runtime`.
+fn print_profile_stats(runtime: &Runtime) {
+    let report = runtime.profiling_report();
+    println!("{:>16} {}", "instructions", report.total_instructions);
+    for (pc, count) in report.hot_pcs.iter().take(HOT_LOOPS_COUNT) {
+        println!(
+            "{:>16} {count}",
+            format!("{}@{}", pc.get_method_index(), pc.get_instruction_index())
+        );
+    }
+}
+
+/// Print the machine code of the native traces compiled for the program run
+/// by `runtime`.
+#[cfg(all(feature = "disasm", target_arch = "x86_64"))]
+fn print_native_traces(runtime: &Runtime) {
+    for (pc, trace) in runtime.native_traces() {
+        println!(
+            "native trace @ {}@{} :",
+            pc.get_method_index(),
+            pc.get_instruction_index()
+        );
+        print!("{}", trace.disassemble());
+    }
+}
+
+/// Print the machine code of the native traces compiled for the program run
+/// by `runtime`.
+#[cfg(not(all(feature = "disasm", target_arch = "x86_64")))]
+fn print_native_traces(_runtime: &Runtime) {
+    println!("Rebuild with `--features jit-disasm` to dump native traces.");
+}
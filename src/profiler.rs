@@ -1,26 +1,87 @@
 //! Code profiler for the interpreter works by keeping track of loop
 //! entries and exits. When a given loop entry has exceeded the threshold
 //! it's considered hot and a trace will be compiled for it.
-use std::collections::HashMap;
+#[cfg(feature = "accurate_profiling")]
+use std::collections::HashSet;
+
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
 
 use crate::runtime::ProgramCounter;
 
+// Maximum number of snapshots kept by the profiler, older snapshots are
+// dropped first.
+const MAX_SNAPSHOTS: usize = 100;
+
+/// Default number of entries to a loop header before it's considered hot.
+pub const DEFAULT_THRESHOLD: usize = 10;
+
+/// Default number of calls to a method before it's considered hot.
+pub const DEFAULT_METHOD_THRESHOLD: usize = 50;
+
+/// Point-in-time copy of the profiler state, used to track how hotness
+/// evolves during execution.
+#[derive(Debug, Clone)]
+pub struct ProfilerSnapshot {
+    // Time elapsed since the profiler was created.
+    pub timestamp: Duration,
+    // Record of loop entries and their access counts.
+    pub records: HashMap<ProgramCounter, usize>,
+    // Number of instructions counted so far.
+    pub instruction_count: usize,
+}
+
+/// Profiling data exported by the profiler, see `Profiler::report`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProfileReport {
+    // Loop headers and their entry counts ordered from the hottest.
+    pub hot_pcs: Vec<(ProgramCounter, usize)>,
+    // Number of instructions counted.
+    pub total_instructions: usize,
+}
+
 #[derive(Debug)]
 pub struct Profiler {
     // Threshold before a loop entry is considered hot.
     threshold: usize,
     // Last accessed program counter.
     last_pc: ProgramCounter,
+    // Set when a method is invoked, since the next counted entry is the
+    // first instruction of the callee it can't be a loop header.
+    method_boundary: bool,
     // Record of loop entries and their access counts.
     records: HashMap<ProgramCounter, usize>,
+    // Threshold before a method is considered hot.
+    method_threshold: usize,
+    // Record of method invocations and their call counts.
+    method_counts: HashMap<usize, usize>,
+    // Program counters executed so far, any re-entry is counted.
+    #[cfg(feature = "accurate_profiling")]
+    visited: HashSet<ProgramCounter>,
+    // Total number of instructions counted.
+    instruction_count: usize,
+    // Creation time of the profiler, snapshot timestamps are relative to it.
+    start: Instant,
+    // Last `MAX_SNAPSHOTS` snapshots ordered from oldest to newest.
+    snapshots: VecDeque<ProfilerSnapshot>,
 }
 
 impl Profiler {
-    pub fn new() -> Profiler {
+    /// Create a new profiler, loop headers entered more than `threshold`
+    /// times are hot.
+    pub fn new(threshold: usize) -> Profiler {
         Profiler {
-            threshold: 1,
+            threshold,
             last_pc: ProgramCounter::default(),
+            method_boundary: false,
             records: HashMap::new(),
+            method_threshold: DEFAULT_METHOD_THRESHOLD,
+            method_counts: HashMap::new(),
+            #[cfg(feature = "accurate_profiling")]
+            visited: HashSet::new(),
+            instruction_count: 0,
+            start: Instant::now(),
+            snapshots: VecDeque::with_capacity(MAX_SNAPSHOTS),
         }
     }
 
@@ -36,10 +97,16 @@ impl Profiler {
     // method index.
     // - The instruction index within the method is before the last accessed
     // program counter's instruction index.
+    //
+    // Entries following a method invocation are never considered loop
+    // headers, see `enter_method`.
+    //
+    // With the `accurate_profiling` feature any entry to an already executed
+    // `pc` is counted instead, which catches recursion and loops whose header
+    // follows the back edge at the cost of tracking every executed `pc`.
     pub fn count_entry(&mut self, pc: &ProgramCounter) {
-        if pc.get_method_index() == self.last_pc.get_method_index()
-            && pc.get_instruction_index() < self.last_pc.get_instruction_index()
-        {
+        self.instruction_count += 1;
+        if self.is_reentry(pc) {
             match self.records.get_mut(pc) {
                 Some(record) => *record += 1,
                 None => {
@@ -50,6 +117,46 @@ impl Profiler {
         self.last_pc = *pc;
     }
 
+    // Returns whether entering `pc` closes a loop, i.e it's the target of a
+    // backward jump within the same method.
+    #[cfg(not(feature = "accurate_profiling"))]
+    fn is_reentry(&mut self, pc: &ProgramCounter) -> bool {
+        if self.method_boundary {
+            self.method_boundary = false;
+            return false;
+        }
+        pc.get_method_index() == self.last_pc.get_method_index()
+            && pc.get_instruction_index() < self.last_pc.get_instruction_index()
+    }
+
+    // Returns whether `pc` was already executed.
+    #[cfg(feature = "accurate_profiling")]
+    fn is_reentry(&mut self, pc: &ProgramCounter) -> bool {
+        self.method_boundary = false;
+        !self.visited.insert(*pc)
+    }
+
+    // Signal that a method was invoked, for (recursive) calls the callee
+    // entry point has the same method index as the call site and a lower
+    // instruction index which would otherwise be counted as a backward jump.
+    pub fn enter_method(&mut self) {
+        self.method_boundary = true;
+    }
+
+    // Count a call to the method at `method_index`, frequently called
+    // methods are candidates for inlining.
+    pub fn count_method_call(&mut self, method_index: usize) {
+        *self.method_counts.entry(method_index).or_insert(0) += 1;
+    }
+
+    // Returns whether the method at `method_index` was called more than the
+    // method threshold.
+    pub fn is_hot_method(&self, method_index: usize) -> bool {
+        self.method_counts
+            .get(&method_index)
+            .is_some_and(|count| *count > self.method_threshold)
+    }
+
     // Count an exit from the JIT back to the interpreter, these "side-exits"
     // mark the non presence of a native trace which causes the exit back
     // to interpretation. Since we ideally want to spend as much time executing
@@ -73,10 +180,157 @@ impl Profiler {
         }
         false
     }
+
+    // Take a snapshot of the current profiler state, the snapshot is stored
+    // in the profiler and a copy is returned to the caller.
+    pub fn take_snapshot(&mut self) -> ProfilerSnapshot {
+        let snapshot = ProfilerSnapshot {
+            timestamp: self.start.elapsed(),
+            records: self.records.clone(),
+            instruction_count: self.instruction_count,
+        };
+        if self.snapshots.len() == MAX_SNAPSHOTS {
+            self.snapshots.pop_front();
+        }
+        self.snapshots.push_back(snapshot.clone());
+        snapshot
+    }
+
+    // Returns the stored snapshots ordered from oldest to newest.
+    pub const fn snapshots(&self) -> &VecDeque<ProfilerSnapshot> {
+        &self.snapshots
+    }
+
+    // Returns the number of instructions counted so far.
+    pub const fn total_instructions_executed(&self) -> usize {
+        self.instruction_count
+    }
+
+    // Returns the loop headers counted so far, ordered from the hottest, and
+    // the number of instructions counted.
+    pub fn report(&self) -> ProfileReport {
+        let mut hot_pcs: Vec<(ProgramCounter, usize)> = self
+            .records
+            .iter()
+            .map(|(pc, count)| (*pc, *count))
+            .collect();
+        hot_pcs.sort_by_key(|(pc, count)| {
+            (
+                std::cmp::Reverse(*count),
+                pc.get_method_index(),
+                pc.get_instruction_index(),
+            )
+        });
+        ProfileReport {
+            hot_pcs,
+            total_instructions: self.instruction_count,
+        }
+    }
 }
 
 impl Default for Profiler {
     fn default() -> Self {
-        Self::new()
+        Self::new(DEFAULT_THRESHOLD)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(not(feature = "accurate_profiling"))]
+    fn can_take_profiler_snapshots() {
+        let mut profiler = Profiler::default();
+        profiler.count_entry(&ProgramCounter::new(0, 4));
+        profiler.count_entry(&ProgramCounter::new(0, 2));
+        let snapshot = profiler.take_snapshot();
+        assert_eq!(snapshot.instruction_count, 2);
+        assert_eq!(snapshot.records.get(&ProgramCounter::new(0, 2)), Some(&1));
+
+        profiler.count_entry(&ProgramCounter::new(0, 4));
+        profiler.count_entry(&ProgramCounter::new(0, 2));
+        let snapshot = profiler.take_snapshot();
+        assert_eq!(snapshot.records.get(&ProgramCounter::new(0, 2)), Some(&2));
+        assert_eq!(profiler.snapshots().len(), 2);
+        assert_eq!(profiler.snapshots()[0].instruction_count, 2);
+        assert_eq!(profiler.snapshots()[1].instruction_count, 4);
+
+        for _ in 0..MAX_SNAPSHOTS {
+            profiler.take_snapshot();
+        }
+        assert_eq!(profiler.snapshots().len(), MAX_SNAPSHOTS);
+        assert_eq!(profiler.snapshots()[0].instruction_count, 4);
+    }
+
+    #[test]
+    #[cfg(not(feature = "accurate_profiling"))]
+    fn reports_are_ordered_from_the_hottest() {
+        let mut profiler = Profiler::default();
+        for _ in 0..3 {
+            profiler.count_entry(&ProgramCounter::new(0, 8));
+            profiler.count_entry(&ProgramCounter::new(0, 2));
+        }
+        profiler.count_entry(&ProgramCounter::new(1, 6));
+        profiler.count_entry(&ProgramCounter::new(1, 4));
+        profiler.count_exit(&ProgramCounter::new(0, 4));
+        assert_eq!(profiler.total_instructions_executed(), 8);
+        assert_eq!(
+            profiler.report(),
+            ProfileReport {
+                hot_pcs: vec![
+                    (ProgramCounter::new(0, 2), 3),
+                    (ProgramCounter::new(0, 4), 1),
+                    (ProgramCounter::new(1, 4), 1),
+                ],
+                total_instructions: 8,
+            }
+        );
+    }
+
+    #[test]
+    fn method_calls_are_counted_separately() {
+        let mut profiler = Profiler::default();
+        for _ in 0..DEFAULT_METHOD_THRESHOLD {
+            profiler.count_method_call(7);
+        }
+        assert!(!profiler.is_hot_method(7));
+        profiler.count_method_call(7);
+        assert!(profiler.is_hot_method(7));
+        assert!(!profiler.is_hot_method(8));
+        assert!(profiler.records.is_empty());
+    }
+
+    #[test]
+    #[cfg(not(feature = "accurate_profiling"))]
+    fn method_entries_are_not_loop_headers() {
+        let mut profiler = Profiler::default();
+        // Recursive call from instruction 10 back to the method entry.
+        profiler.count_entry(&ProgramCounter::new(3, 10));
+        profiler.enter_method();
+        profiler.count_entry(&ProgramCounter::new(3, 0));
+        assert!(profiler.records.is_empty());
+        // Backward jumps are still counted after the boundary.
+        profiler.count_entry(&ProgramCounter::new(3, 4));
+        profiler.count_entry(&ProgramCounter::new(3, 2));
+        assert_eq!(profiler.records.get(&ProgramCounter::new(3, 2)), Some(&1));
+    }
+
+    #[test]
+    #[cfg(feature = "accurate_profiling")]
+    fn reentries_are_counted_in_any_direction() {
+        let mut profiler = Profiler::default();
+        // Recursive call from instruction 10 back to the method entry.
+        profiler.count_entry(&ProgramCounter::new(3, 0));
+        profiler.count_entry(&ProgramCounter::new(3, 10));
+        profiler.enter_method();
+        profiler.count_entry(&ProgramCounter::new(3, 0));
+        assert_eq!(profiler.records.get(&ProgramCounter::new(3, 0)), Some(&1));
+        // Forward jump back into the loop header at instruction 12.
+        profiler.count_entry(&ProgramCounter::new(3, 12));
+        profiler.count_entry(&ProgramCounter::new(3, 4));
+        profiler.count_entry(&ProgramCounter::new(3, 12));
+        assert_eq!(profiler.records.get(&ProgramCounter::new(3, 12)), Some(&1));
+        assert!(!profiler.records.contains_key(&ProgramCounter::new(3, 4)));
     }
 }
@@ -1,8 +1,69 @@
 //! Abstract representation of a Java program.
-use crate::jvm::{AttributeInfo, CPInfo, JVMClassFile, StackMapFrame};
+use crate::jvm::{
+    read_class_file, read_jar, read_jar_main_class, AttributeInfo,
+    BootstrapMethod, CPInfo, ClassPath, JVMClassFile, JVMParser,
+    LineNumberEntry, LocalVariableEntry, ParseError, StackMapFrame,
+};
+
+use std::collections::{HashMap, HashSet};
+
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
 
 use regex::Regex;
 
+/// `ProgramError` represents the possible failures when loading classes
+/// into a program.
+#[derive(Debug)]
+pub enum ProgramError {
+    /// Reading a class file failed.
+    Io(io::Error),
+    /// Parsing a class file failed.
+    Parse(ParseError),
+    /// The merged constant pool doesn't fit in the `u16` index space.
+    ConstantPoolOverflow,
+    /// A method has no Code attribute (i.e native or abstract methods).
+    MissingCode {
+        class_name: String,
+        name: String,
+        descriptor: String,
+    },
+}
+
+impl fmt::Display for ProgramError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "Failed to load class file : {err}"),
+            Self::Parse(err) => write!(f, "Failed to parse class file : {err}"),
+            Self::ConstantPoolOverflow => {
+                write!(f, "Constant pool exceeds {} entries", u16::MAX)
+            }
+            Self::MissingCode {
+                class_name,
+                name,
+                descriptor,
+            } => write!(
+                f,
+                "Method '{class_name}.{name}' (descriptor '{descriptor}') has no Code attribute; native and abstract methods are not yet supported"
+            ),
+        }
+    }
+}
+
+impl From<io::Error> for ProgramError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<ParseError> for ProgramError {
+    fn from(err: ParseError) -> Self {
+        Self::Parse(err)
+    }
+}
+
 /// Primitive types supported by the JVM.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum BaseTypeKind {
@@ -13,15 +74,38 @@ pub enum BaseTypeKind {
     Void,
     String,
     List,
+    Reference,
 }
 
 /// JVM value type.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Type {
     t: BaseTypeKind,
     sub_t: Option<Box<Type>>,
 }
 
+impl Default for Type {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BaseTypeKind {
+    /// Returns the size in WORD (4 bytes) of a given type, references
+    /// (strings, arrays and objects) take a single word.
+    pub const fn size(&self) -> usize {
+        match self {
+            Self::Int
+            | Self::Float
+            | Self::String
+            | Self::List
+            | Self::Reference => 1,
+            Self::Long | Self::Double => 2,
+            Self::Void => 0,
+        }
+    }
+}
+
 impl Type {
     /// Empty constructor, we could use `Default` but hey.
     pub fn new() -> Self {
@@ -31,62 +115,404 @@ impl Type {
         }
     }
     /// Returns the size in WORD (4 bytes) of a given type.
-    pub fn size(&self) -> usize {
-        match self.t {
-            BaseTypeKind::Int | BaseTypeKind::Float => 1,
-            BaseTypeKind::Long | BaseTypeKind::Double => 2,
-            _ => 0,
-        }
+    pub const fn size(&self) -> usize {
+        self.t.size()
     }
 }
 
 /// Representation of Java programs that we want to run.
+///
+/// A program starts from a single (main) class, additional classes can be
+/// loaded later in which case their constant pool is appended to the program
+/// constant pool and all their indexes are shifted accordingly.
 #[derive(Debug, Clone)]
 pub struct Program {
-    // Constant pool.
-    pub constant_pool: Vec<CPInfo>,
-    // Methods.
-    // pub methods: HashMap<usize, Method>,
+    // Constant pool, merged across all loaded classes. The pool is shared
+    // between clones of the program since it's immutable once loaded.
+    pub constant_pool: Arc<[CPInfo]>,
+    // Methods of the loaded classes in the order they are loaded.
     pub methods: Vec<Method>,
+    // Binary names of the loaded classes.
+    classes: Vec<String>,
+    // Index in `methods` of the methods of the loaded classes keyed by the
+    // binary name of their class, their name and their descriptor.
+    method_indexes: HashMap<(String, String, String), usize>,
+    // Binary name of the superclass of each loaded class.
+    superclasses: HashMap<String, String>,
+    // Bootstrap methods of `invokedynamic` call sites, merged across all
+    // loaded classes.
+    bootstrap_methods: Vec<BootstrapMethod>,
 }
 
+/// Call site of an `invokedynamic` instruction, resolved from the bootstrap
+/// method that would link it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CallSite {
+    /// Non capturing lambda created by `LambdaMetafactory`, invoking the
+    /// functional interface calls the method at `method_index`.
+    Lambda { method_index: usize },
+    /// String concatenation created by `StringConcatFactory` consuming
+    /// `arg_count` operands.
+    StringConcat { arg_count: usize },
+}
+
+/// Exception handler of a method, resolved from its exception table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExceptionHandler {
+    // Range of instruction indexes `[start_pc, end_pc)` covered by the
+    // handler.
+    start_pc: usize,
+    end_pc: usize,
+    // Instruction index of the handler.
+    handler_pc: usize,
+    // Binary name of the class of the exceptions caught by the handler,
+    // `None` if it catches all exceptions (i.e `finally`).
+    catch_type: Option<String>,
+}
+
+/// Superclasses of the common `java.lang` exceptions, the JDK classes are
+/// never loaded.
+const JDK_EXCEPTIONS: &[(&str, &str)] = &[
+    ("java/lang/Throwable", "java/lang/Object"),
+    ("java/lang/Exception", "java/lang/Throwable"),
+    ("java/lang/Error", "java/lang/Throwable"),
+    ("java/lang/RuntimeException", "java/lang/Exception"),
+    (
+        "java/lang/ArithmeticException",
+        "java/lang/RuntimeException",
+    ),
+    (
+        "java/lang/IllegalArgumentException",
+        "java/lang/RuntimeException",
+    ),
+    (
+        "java/lang/IllegalStateException",
+        "java/lang/RuntimeException",
+    ),
+    (
+        "java/lang/IndexOutOfBoundsException",
+        "java/lang/RuntimeException",
+    ),
+    (
+        "java/lang/ArrayIndexOutOfBoundsException",
+        "java/lang/IndexOutOfBoundsException",
+    ),
+    (
+        "java/lang/NullPointerException",
+        "java/lang/RuntimeException",
+    ),
+    (
+        "java/lang/UnsupportedOperationException",
+        "java/lang/RuntimeException",
+    ),
+];
+
 /// Java class method representation for the interpreter.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct Method {
     _name_index: u16,
-    _return_type: Type,
+    return_type: Type,
     pub arg_types: Vec<Type>,
-    _max_stack: u16,
+    max_stack: u16,
     pub max_locals: u16,
     pub code: Vec<u8>,
     _constant: Option<u16>,
-    _stack_map_table: Option<Vec<StackMapFrame>>,
+    stack_map_table: Option<Vec<StackMapFrame>>,
+    // Source line numbers of the method bytecode if the class file has them.
+    line_number_table: Option<Vec<LineNumberEntry>>,
+    // Names of the method local variables if the class file has them.
+    local_variable_table: Option<Vec<LocalVariableEntry>>,
+    // Constant pool indexes of the checked exception classes the method
+    // declares in its `throws` clause.
+    declared_exceptions: Vec<u16>,
+    // Offset of the method's class constant pool in the program constant
+    // pool.
+    constant_pool_offset: usize,
+    // Binary name of the class declaring the method.
+    class_name: String,
+    // Exception handlers in the order they are searched.
+    exception_handlers: Vec<ExceptionHandler>,
 }
 
-impl Default for Method {
-    fn default() -> Self {
-        Self {
-            _name_index: 0,
-            _return_type: Type::new(),
-            arg_types: Vec::new(),
-            _max_stack: 0,
-            max_locals: 0,
-            code: Vec::new(),
-            _constant: None,
-            _stack_map_table: None,
-        }
+impl Method {
+    /// Returns the method bytecode.
+    pub fn code(&self) -> &[u8] {
+        &self.code
+    }
+
+    /// Returns the maximum depth of the method operand stack.
+    pub const fn max_stack(&self) -> u16 {
+        self.max_stack
+    }
+
+    /// Returns the number of local variables used by the method.
+    pub const fn max_locals(&self) -> u16 {
+        self.max_locals
+    }
+
+    /// Returns the types of the method arguments.
+    pub fn arg_types(&self) -> &[Type] {
+        &self.arg_types
+    }
+
+    /// Returns the method return type.
+    pub const fn return_type(&self) -> &Type {
+        &self.return_type
+    }
+
+    /// Returns the method stack map table if it has one.
+    pub fn stack_map_table(&self) -> Option<&[StackMapFrame]> {
+        self.stack_map_table.as_deref()
+    }
+
+    /// Returns the source line of the instruction at `pc` if the method has
+    /// a line number table.
+    pub fn line_number(&self, pc: usize) -> Option<u16> {
+        self.line_number_table
+            .as_ref()?
+            .iter()
+            .filter(|entry| usize::from(entry.start_pc) <= pc)
+            .max_by_key(|entry| entry.start_pc)
+            .map(|entry| entry.line_number)
+    }
+
+    /// Returns the constant pool indexes of the classes of the checked
+    /// exceptions the method declares.
+    pub fn declared_exceptions(&self) -> &[u16] {
+        &self.declared_exceptions
+    }
+
+    /// Returns the local variable table entry naming the local at `index`
+    /// for the instruction at `pc` if the method has a local variable table.
+    pub fn local_variable(
+        &self,
+        index: usize,
+        pc: usize,
+    ) -> Option<&LocalVariableEntry> {
+        self.local_variable_table
+            .as_ref()?
+            .iter()
+            .find(|entry| usize::from(entry.index) == index && entry.covers(pc))
     }
 }
 
 impl Program {
     /// Build a new program from a parsed class file.
     /// # Panics
-    /// Can panic if class file is missing Code attribute.
+    /// Can panic if a method is missing its Code attribute (i.e native or
+    /// abstract methods).
     #[must_use]
     pub fn new(class_file: &JVMClassFile) -> Self {
-        let constants = class_file.constant_pool();
-        // let mut methods: HashMap<usize, Method> = HashMap::new();
-        let mut methods: Vec<Method> = vec![Method::default(); 256];
+        let mut program = Self {
+            constant_pool: Arc::from([]),
+            methods: Vec::new(),
+            classes: Vec::new(),
+            method_indexes: HashMap::new(),
+            superclasses: HashMap::new(),
+            bootstrap_methods: Vec::new(),
+        };
+        program
+            .add_class(class_file)
+            .unwrap_or_else(|err| panic!("{err}"));
+        program
+    }
+
+    /// Build a new program from all the classes in a JAR archive, the class
+    /// declared as `Main-Class` in the archive manifest is used as the main
+    /// class.
+    /// # Errors
+    /// Returns `ProgramError` if the archive or one of its classes can't be
+    /// read.
+    pub fn from_jar(jar_path: &Path) -> Result<Self, ProgramError> {
+        let main_class = read_jar_main_class(jar_path)?.replace('.', "/");
+        let mut class_files = read_jar(jar_path)?;
+        let main = class_files
+            .iter()
+            .position(|class_file| {
+                class_file.class_name() == Some(main_class.as_str())
+            })
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("Main class {main_class} isn't in the archive"),
+                )
+            })?;
+        let mut program = Self::new(&class_files.remove(main));
+        for class_file in &class_files {
+            program.merge_class(class_file)?;
+        }
+        Ok(program)
+    }
+
+    /// Load an additional class file into the program, its methods become
+    /// callable from the already loaded classes.
+    /// # Errors
+    /// Returns `ProgramError` if the class file can't be read or parsed or if
+    /// one of its methods has no Code attribute.
+    pub fn load_class(&mut self, path: &Path) -> Result<(), ProgramError> {
+        let class_file_bytes = read_class_file(path)?;
+        let class_file = JVMParser::parse(&class_file_bytes)?;
+        self.merge_class(&class_file)
+    }
+
+    /// Load the classes whose methods are referenced by the program but
+    /// aren't loaded yet from the class path, classes loaded this way are
+    /// resolved as well. Classes missing from the class path are ignored.
+    /// # Errors
+    /// Returns `ProgramError` if a class file can't be read or parsed.
+    pub fn resolve_classes(
+        &mut self,
+        class_path: &ClassPath,
+    ) -> Result<(), ProgramError> {
+        let mut missing: HashSet<String> = HashSet::new();
+        loop {
+            let unresolved: HashSet<String> = self
+                .referenced_classes()
+                .into_iter()
+                .filter(|name| {
+                    !self.classes.contains(name) && !missing.contains(name)
+                })
+                .collect();
+            if unresolved.is_empty() {
+                return Ok(());
+            }
+            for class_name in unresolved {
+                if !self.load_from_class_path(class_path, &class_name)? {
+                    missing.insert(class_name);
+                }
+            }
+        }
+    }
+
+    /// Load the class with the binary name `class_name` from the class path,
+    /// returns whether the class path has it.
+    /// # Errors
+    /// Returns `ProgramError` if the class file can't be read or parsed.
+    pub fn load_from_class_path(
+        &mut self,
+        class_path: &ClassPath,
+        class_name: &str,
+    ) -> Result<bool, ProgramError> {
+        match class_path.find_class(class_name)? {
+            Some(class_file_bytes) => {
+                self.merge_class(&JVMParser::parse(&class_file_bytes)?)?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Returns the binary names of the classes declaring the methods
+    /// referenced in the constant pool.
+    fn referenced_classes(&self) -> HashSet<String> {
+        self.constant_pool
+            .iter()
+            .filter_map(|entry| match entry {
+                CPInfo::ConstantMethodRef { class_index, .. } => {
+                    self.class_name(*class_index as usize)
+                }
+                _ => None,
+            })
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// Returns the name of the class at `class_index` in the constant pool.
+    #[must_use]
+    pub fn class_name(&self, class_index: usize) -> Option<&str> {
+        let CPInfo::ConstantClass { name_index } =
+            self.constant_pool.get(class_index)?
+        else {
+            return None;
+        };
+        match self.constant_pool.get(*name_index as usize)? {
+            CPInfo::ConstantUtf8 { bytes } => Some(bytes),
+            _ => None,
+        }
+    }
+
+    /// Merge a parsed class file into the program.
+    fn merge_class(
+        &mut self,
+        class_file: &JVMClassFile,
+    ) -> Result<(), ProgramError> {
+        if self.constant_pool.len() + class_file.constant_pool().len()
+            > u16::MAX as usize
+        {
+            return Err(ProgramError::ConstantPoolOverflow);
+        }
+        self.add_class(class_file)
+    }
+
+    /// Append the class constant pool to the program constant pool and
+    /// register the class methods.
+    ///
+    /// Classes with methods missing their Code attribute are rejected before
+    /// anything is merged.
+    fn add_class(
+        &mut self,
+        class_file: &JVMClassFile,
+    ) -> Result<(), ProgramError> {
+        if let Some(method_info) = class_file.methods().iter().find(|method| {
+            !matches!(
+                method.attributes().get("Code"),
+                Some(AttributeInfo::CodeAttribute { .. })
+            )
+        }) {
+            let constants = class_file.constant_pool();
+            let utf8 = |index: u16| match constants.get(index as usize) {
+                Some(CPInfo::ConstantUtf8 { bytes }) => bytes.clone(),
+                _ => "<unknown>".to_string(),
+            };
+            return Err(ProgramError::MissingCode {
+                class_name: class_file
+                    .class_name()
+                    .unwrap_or("<unknown>")
+                    .to_string(),
+                name: utf8(method_info.name_index()),
+                descriptor: utf8(method_info.descriptor_index()),
+            });
+        }
+        if let Some(class_name) = class_file.class_name() {
+            self.classes.push(class_name.to_string());
+            if let Some(super_class_name) = class_file.super_class_name() {
+                self.superclasses.insert(
+                    class_name.to_string(),
+                    super_class_name.to_string(),
+                );
+            }
+        }
+        let offset = self.constant_pool.len();
+        let bootstrap_offset = self.bootstrap_methods.len() as u16;
+        self.bootstrap_methods.extend(
+            class_file
+                .bootstrap_methods()
+                .iter()
+                .map(|bootstrap_method| {
+                    BootstrapMethod::new(
+                        bootstrap_method.method_ref() + offset as u16,
+                        bootstrap_method
+                            .arguments()
+                            .iter()
+                            .map(|argument| argument + offset as u16)
+                            .collect(),
+                    )
+                }),
+        );
+        self.constant_pool = if offset == 0 {
+            // The main class pool doesn't need relocation and can be shared
+            // with the class file.
+            class_file.constant_pool()
+        } else {
+            self.constant_pool
+                .iter()
+                .cloned()
+                .chain(class_file.constant_pool().iter().map(|entry| {
+                    Self::relocate(entry, offset as u16, bootstrap_offset)
+                }))
+                .collect()
+        };
+        let constants = &self.constant_pool;
         for method_info in &class_file.methods() {
             let mut arg_types: Vec<Type> = Vec::new();
             let mut return_type: Type = Type {
@@ -94,26 +520,75 @@ impl Program {
                 sub_t: None,
             };
             let descriptor =
-                &constants[method_info.descriptor_index() as usize];
-            let _method_name = &constants[method_info.name_index() as usize];
+                &constants[offset + method_info.descriptor_index() as usize];
 
             if let CPInfo::ConstantUtf8 { bytes } = descriptor {
                 (arg_types, return_type) = Self::parse_method_types(bytes);
             }
             let attr = method_info.attributes();
 
-            let (max_stack, max_locals, code) =
-                if let Some(AttributeInfo::CodeAttribute {
-                    max_stack,
-                    max_locals,
-                    code,
-                    ..
-                }) = attr.get("Code")
+            let (
+                max_stack,
+                max_locals,
+                code,
+                line_number_table,
+                local_variable_table,
+                exception_handlers,
+            ) = if let Some(AttributeInfo::CodeAttribute {
+                max_stack,
+                max_locals,
+                code,
+                exception_table,
+                attributes,
+                ..
+            }) = attr.get("Code")
+            {
+                let exception_handlers = exception_table
+                    .iter()
+                    .map(|entry| {
+                        let (start_pc, end_pc) = entry.range();
+                        ExceptionHandler {
+                            start_pc: start_pc.into(),
+                            end_pc: end_pc.into(),
+                            handler_pc: entry.handler_pc().into(),
+                            catch_type: (entry.catch_type() != 0)
+                                .then(|| {
+                                    self.class_name(
+                                        offset + entry.catch_type() as usize,
+                                    )
+                                })
+                                .flatten()
+                                .map(str::to_string),
+                        }
+                    })
+                    .collect();
+                let line_number_table = match attributes.get("LineNumberTable")
                 {
-                    (*max_stack, *max_locals, code.clone())
-                } else {
-                    panic!("Expected at least one code attribute")
+                    Some(AttributeInfo::LineNumberTableAttribute {
+                        entries,
+                        ..
+                    }) => Some(entries.clone()),
+                    _ => None,
                 };
+                let local_variable_table =
+                    match attributes.get("LocalVariableTable") {
+                        Some(AttributeInfo::LocalVariableTableAttribute {
+                            entries,
+                            ..
+                        }) => Some(entries.clone()),
+                        _ => None,
+                    };
+                (
+                    *max_stack,
+                    *max_locals,
+                    code.clone(),
+                    line_number_table,
+                    local_variable_table,
+                    exception_handlers,
+                )
+            } else {
+                unreachable!("Methods without code are rejected before merging")
+            };
 
             let constant =
                 if let Some(AttributeInfo::ConstantValueAttribute {
@@ -137,75 +612,479 @@ impl Program {
                     None
                 };
 
+            let declared_exceptions =
+                if let Some(AttributeInfo::ExceptionsAttribute {
+                    exception_index_table,
+                    ..
+                }) = attr.get("Exceptions")
+                {
+                    exception_index_table
+                        .iter()
+                        .map(|index| (offset + *index as usize) as u16)
+                        .collect()
+                } else {
+                    vec![]
+                };
+
+            let name_index = offset + method_info.name_index() as usize;
             let method = Method {
-                _name_index: method_info.name_index(),
-                _return_type: return_type,
+                _name_index: name_index as u16,
+                return_type,
                 arg_types,
-                _max_stack: max_stack,
+                max_stack,
                 max_locals,
                 code,
                 _constant: constant,
-                _stack_map_table: stack_map_table,
+                stack_map_table,
+                line_number_table,
+                local_variable_table,
+                declared_exceptions,
+                constant_pool_offset: offset,
+                class_name: class_file
+                    .class_name()
+                    .unwrap_or_default()
+                    .to_string(),
+                exception_handlers,
             };
-            // methods.insert(method_info.name_index() as usize, method);
-            methods[method_info.name_index() as usize] = method;
-        }
-
-        Self {
-            // Get a copy of the constant pool.
-            constant_pool: class_file.constant_pool(),
-            // Get a copy of the program methods.
-            methods,
+            if let (
+                Some(class_name),
+                CPInfo::ConstantUtf8 { bytes: name },
+                CPInfo::ConstantUtf8 { bytes: descriptor },
+            ) = (class_file.class_name(), &constants[name_index], descriptor)
+            {
+                self.method_indexes.insert(
+                    (class_name.to_string(), name.clone(), descriptor.clone()),
+                    self.methods.len(),
+                );
+            }
+            self.methods.push(method);
         }
+        Ok(())
     }
 
-    // Find method name index in the constant pool by reference.
-    pub fn find_method(&self, method_ref: usize) -> i32 {
-        match self.constant_pool[method_ref] {
+    /// Shift all the constant pool indexes in `entry` by `offset` and the
+    /// bootstrap method indexes by `bootstrap_offset`.
+    fn relocate(entry: &CPInfo, offset: u16, bootstrap_offset: u16) -> CPInfo {
+        match entry.clone() {
+            CPInfo::ConstantClass { name_index } => CPInfo::ConstantClass {
+                name_index: name_index + offset,
+            },
+            CPInfo::ConstantFieldRef {
+                class_index,
+                name_and_type_index,
+            } => CPInfo::ConstantFieldRef {
+                class_index: class_index + offset,
+                name_and_type_index: name_and_type_index + offset,
+            },
             CPInfo::ConstantMethodRef {
+                class_index,
                 name_and_type_index,
-                ..
-            } => {
-                if let CPInfo::ConstantNameAndType { name_index, .. } =
-                    self.constant_pool[name_and_type_index as usize]
-                {
-                    return name_index.into();
+            } => CPInfo::ConstantMethodRef {
+                class_index: class_index + offset,
+                name_and_type_index: name_and_type_index + offset,
+            },
+            CPInfo::ConstantInterfaceMethodRef {
+                class_index,
+                name_and_type_index,
+            } => CPInfo::ConstantInterfaceMethodRef {
+                class_index: class_index + offset,
+                name_and_type_index: name_and_type_index + offset,
+            },
+            CPInfo::ConstantString { string_index } => CPInfo::ConstantString {
+                string_index: string_index + offset,
+            },
+            CPInfo::ConstantNameAndType {
+                name_index,
+                descriptor_index,
+            } => CPInfo::ConstantNameAndType {
+                name_index: name_index + offset,
+                descriptor_index: descriptor_index + offset,
+            },
+            CPInfo::ConstantMethodHandle {
+                reference_kind,
+                reference_index,
+            } => CPInfo::ConstantMethodHandle {
+                reference_kind,
+                reference_index: reference_index + offset,
+            },
+            CPInfo::ConstantMethodType { descriptor_index } => {
+                CPInfo::ConstantMethodType {
+                    descriptor_index: descriptor_index + offset,
                 }
-                0
             }
-            _ => panic!("Expected ConstantMethodRef"),
+            CPInfo::ConstantDynamic {
+                bootstrap_method_attr_index,
+                name_and_type_index,
+            } => CPInfo::ConstantDynamic {
+                bootstrap_method_attr_index: bootstrap_method_attr_index
+                    + bootstrap_offset,
+                name_and_type_index: name_and_type_index + offset,
+            },
+            CPInfo::ConstantInvokeDynamic {
+                bootstrap_method_attr_index,
+                name_and_type_index,
+            } => CPInfo::ConstantInvokeDynamic {
+                bootstrap_method_attr_index: bootstrap_method_attr_index
+                    + bootstrap_offset,
+                name_and_type_index: name_and_type_index + offset,
+            },
+            CPInfo::ConstantModule { name_index } => CPInfo::ConstantModule {
+                name_index: name_index + offset,
+            },
+            CPInfo::ConstantPackage { name_index } => CPInfo::ConstantPackage {
+                name_index: name_index + offset,
+            },
+            // Entries that don't reference other entries are left as is.
+            entry => entry,
+        }
+    }
+
+    /// Find the index in `methods` of the method referenced at `method_ref`
+    /// in the constant pool. Methods are looked up by class, name and
+    /// descriptor in the referenced class then in its superclasses, `None` is
+    /// returned if none of the loaded classes declares the method.
+    #[must_use]
+    pub fn find_method(&self, method_ref: usize) -> Option<usize> {
+        let (class_name, name, descriptor) = self.method_ref(method_ref)?;
+        let mut class_name = Some(class_name);
+        while let Some(class) = class_name {
+            if let Some(method_index) =
+                self.method_index(class, name, descriptor)
+            {
+                #[cfg(feature = "tracing")]
+                tracing::trace!(
+                    method_ref,
+                    method_index,
+                    "resolved method reference"
+                );
+                return Some(method_index);
+            }
+            class_name = self.superclasses.get(class).map(String::as_str);
+        }
+        None
+    }
+
+    /// Returns the index in `methods` of the method `name` with the given
+    /// `descriptor` declared by the class `class_name`.
+    #[must_use]
+    pub fn method_index(
+        &self,
+        class_name: &str,
+        name: &str,
+        descriptor: &str,
+    ) -> Option<usize> {
+        self.method_indexes
+            .get(&(
+                class_name.to_string(),
+                name.to_string(),
+                descriptor.to_string(),
+            ))
+            .copied()
+    }
+
+    /// Resolve the `invokedynamic` call site at `call_site_index` in the
+    /// constant pool. Only lambdas and string concatenation are supported
+    /// since bootstrap methods aren't actually executed, `None` is returned
+    /// for other bootstrap methods.
+    #[must_use]
+    pub fn resolve_call_site(
+        &self,
+        call_site_index: usize,
+    ) -> Option<CallSite> {
+        let CPInfo::ConstantInvokeDynamic {
+            bootstrap_method_attr_index,
+            name_and_type_index,
+        } = self.constant_pool.get(call_site_index)?
+        else {
+            return None;
+        };
+        let bootstrap_method = self
+            .bootstrap_methods
+            .get(*bootstrap_method_attr_index as usize)?;
+        let (class_name, method_name) =
+            self.method_handle_target(bootstrap_method.method_ref() as usize)?;
+        let CPInfo::ConstantNameAndType {
+            descriptor_index, ..
+        } = self.constant_pool[*name_and_type_index as usize]
+        else {
+            return None;
+        };
+        let CPInfo::ConstantUtf8 { bytes: descriptor } =
+            &self.constant_pool[descriptor_index as usize]
+        else {
+            return None;
+        };
+        let (captured, _) = Self::parse_method_types(descriptor);
+
+        match (class_name, method_name) {
+            ("java/lang/invoke/LambdaMetafactory", "metafactory") => {
+                // Captured values would have to be bound to the lambda.
+                if !captured.is_empty() {
+                    return None;
+                }
+                // The second static argument is the method handle of the
+                // lambda implementation.
+                let implementation = *bootstrap_method.arguments().get(1)?;
+                let CPInfo::ConstantMethodHandle {
+                    reference_index, ..
+                } = self.constant_pool[implementation as usize]
+                else {
+                    return None;
+                };
+                let CPInfo::ConstantMethodRef { .. } =
+                    self.constant_pool[reference_index as usize]
+                else {
+                    return None;
+                };
+                Some(CallSite::Lambda {
+                    method_index: self.find_method(reference_index as usize)?,
+                })
+            }
+            (
+                "java/lang/invoke/StringConcatFactory",
+                "makeConcatWithConstants" | "makeConcat",
+            ) => Some(CallSite::StringConcat {
+                arg_count: captured.len(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Returns the class and method names targeted by the method handle at
+    /// `method_handle_index` in the constant pool.
+    fn method_handle_target(
+        &self,
+        method_handle_index: usize,
+    ) -> Option<(&str, &str)> {
+        let CPInfo::ConstantMethodHandle {
+            reference_index, ..
+        } = self.constant_pool.get(method_handle_index)?
+        else {
+            return None;
+        };
+        let (class_name, method_name, _) =
+            self.method_ref(*reference_index as usize)?;
+        Some((class_name, method_name))
+    }
+
+    /// Returns whether the class with the binary name `class_name` is loaded.
+    #[must_use]
+    pub fn has_class(&self, class_name: &str) -> bool {
+        self.classes.iter().any(|name| name == class_name)
+    }
+
+    /// Returns the class name, method name and descriptor of the method
+    /// referenced at `method_ref` in the constant pool.
+    #[must_use]
+    pub fn method_ref(&self, method_ref: usize) -> Option<(&str, &str, &str)> {
+        let CPInfo::ConstantMethodRef {
+            class_index,
+            name_and_type_index,
+        } = self.constant_pool.get(method_ref)?
+        else {
+            return None;
+        };
+        self.member(*class_index, *name_and_type_index)
+    }
+
+    /// Returns the class name, field name and descriptor of the field
+    /// referenced at `field_ref` in the constant pool.
+    #[must_use]
+    pub fn field_ref(&self, field_ref: usize) -> Option<(&str, &str, &str)> {
+        let CPInfo::ConstantFieldRef {
+            class_index,
+            name_and_type_index,
+        } = self.constant_pool.get(field_ref)?
+        else {
+            return None;
+        };
+        self.member(*class_index, *name_and_type_index)
+    }
+
+    /// Returns the class name, member name and descriptor of a field or
+    /// method reference.
+    fn member(
+        &self,
+        class_index: u16,
+        name_and_type_index: u16,
+    ) -> Option<(&str, &str, &str)> {
+        let CPInfo::ConstantNameAndType {
+            name_index,
+            descriptor_index,
+        } = self.constant_pool.get(name_and_type_index as usize)?
+        else {
+            return None;
+        };
+        let utf8 = |index: u16| match self.constant_pool.get(index as usize)? {
+            CPInfo::ConstantUtf8 { bytes } => Some(bytes.as_str()),
+            _ => None,
+        };
+        Some((
+            self.class_name(class_index as usize)?,
+            utf8(*name_index)?,
+            utf8(*descriptor_index)?,
+        ))
+    }
+
+    /// Returns the number of arguments of the method or interface method
+    /// referenced at `method_ref` in the constant pool.
+    /// # Panics
+    /// Panics if the entry isn't a method reference.
+    #[must_use]
+    pub fn arg_count(&self, method_ref: usize) -> usize {
+        let (CPInfo::ConstantMethodRef {
+            name_and_type_index,
+            ..
+        }
+        | CPInfo::ConstantInterfaceMethodRef {
+            name_and_type_index,
+            ..
+        }) = self.constant_pool[method_ref]
+        else {
+            panic!("Expected ConstantMethodRef or ConstantInterfaceMethodRef")
+        };
+        match &self.constant_pool[name_and_type_index as usize] {
+            CPInfo::ConstantNameAndType {
+                descriptor_index, ..
+            } => match &self.constant_pool[*descriptor_index as usize] {
+                CPInfo::ConstantUtf8 { bytes } => {
+                    Self::parse_method_types(bytes).0.len()
+                }
+                _ => 0,
+            },
+            _ => 0,
         }
     }
 
     // Returns program entry point, in this case the index of the method
     // main.
     pub fn entry_point(&self) -> usize {
-        for (index, _) in self.methods.iter().enumerate() {
-            match self.constant_pool.get(index) {
-                Some(constant) => {
-                    if let CPInfo::ConstantUtf8 { bytes } = constant {
-                        if bytes == "main" {
-                            return index;
-                        }
-                    }
-                }
-                None => panic!("method \"main\" was not found"),
+        // Methods of the main class are loaded first.
+        self.methods
+            .iter()
+            .position(|method| {
+                matches!(
+                    self.constant_pool.get(usize::from(method._name_index)),
+                    Some(CPInfo::ConstantUtf8 { bytes }) if bytes == "main"
+                )
+            })
+            // This might cause some issues but since the input to our
+            // runtime is a class file that already passed the Java compiler
+            // we should assume a main function already exists.
+            .unwrap_or(0)
+    }
+
+    /// Returns the binary name of the class declaring the method at
+    /// `method_index`, `None` if there is no such method.
+    #[must_use]
+    pub fn method_class(&self, method_index: usize) -> Option<&str> {
+        self.methods
+            .get(method_index)
+            .map(|method| method.class_name.as_str())
+            .filter(|class_name| !class_name.is_empty())
+    }
+
+    /// Returns the instruction index of the first handler of the method at
+    /// `method_index` that covers the instruction at `pc` and catches
+    /// exceptions of the class `exception_class`.
+    #[must_use]
+    pub fn exception_handler(
+        &self,
+        method_index: usize,
+        pc: usize,
+        exception_class: &str,
+    ) -> Option<usize> {
+        self.methods
+            .get(method_index)?
+            .exception_handlers
+            .iter()
+            .find(|handler| {
+                (handler.start_pc..handler.end_pc).contains(&pc)
+                    && handler.catch_type.as_deref().is_none_or(|catch_type| {
+                        self.is_subclass_of(exception_class, catch_type)
+                    })
+            })
+            .map(|handler| handler.handler_pc)
+    }
+
+    /// Returns whether the class `class_name` is `ancestor` or one of its
+    /// subclasses, the superclasses of the common `java.lang` exceptions
+    /// are known without loading them.
+    #[must_use]
+    pub fn is_subclass_of(&self, class_name: &str, ancestor: &str) -> bool {
+        let mut class_name = Some(class_name);
+        while let Some(name) = class_name {
+            if name == ancestor {
+                return true;
             }
+            class_name =
+                self.superclasses.get(name).map(String::as_str).or_else(|| {
+                    JDK_EXCEPTIONS
+                        .iter()
+                        .find(|(exception, _)| *exception == name)
+                        .map(|(_, superclass)| *superclass)
+                });
         }
-        // This might cause some issues but since the input to our runtime
-        // is a class file that already passed the Java compiler we should
-        // assume a main function already exists.
-        0
+        false
     }
 
-    // Returns a slice containing code of method pointed at by `method_index`.
-    pub fn code(&self, method_index: usize) -> &[u8] {
-        &self.methods[method_index].code
+    /// Returns the index of the static initializer (`<clinit>`) of the class
+    /// with the binary name `class_name`, `None` if the class isn't loaded or
+    /// has no static initializer.
+    #[must_use]
+    pub fn class_initializer(&self, class_name: &str) -> Option<usize> {
+        self.method_index(class_name, "<clinit>", "()V")
+    }
+
+    // Returns the offset of the constant pool of the class that declares the
+    // method pointed at by `method_index`, constant pool indexes in the method
+    // bytecode are relative to it. `None` if there is no such method.
+    pub fn constant_pool_offset(&self, method_index: usize) -> Option<usize> {
+        self.methods
+            .get(method_index)
+            .map(|method| method.constant_pool_offset)
     }
 
-    // Return the declared max locals for a method.
-    pub fn max_locals(&self, method_index: usize) -> u16 {
-        self.methods[method_index].max_locals
+    // Returns a slice containing code of method pointed at by `method_index`,
+    // `None` if there is no such method (i.e it was never loaded).
+    pub fn code(&self, method_index: usize) -> Option<&[u8]> {
+        self.methods
+            .get(method_index)
+            .map(|method| method.code.as_slice())
+            .filter(|code| !code.is_empty())
+    }
+
+    /// Returns the source line of the instruction at `pc` in a method if the
+    /// method has a line number table.
+    pub fn line_number(&self, method_index: usize, pc: usize) -> Option<u16> {
+        self.methods.get(method_index)?.line_number(pc)
+    }
+
+    /// Returns the source name of the local at `index` in a method for the
+    /// instruction at `pc` if the method has a local variable table.
+    pub fn local_name(
+        &self,
+        method_index: usize,
+        index: usize,
+        pc: usize,
+    ) -> Option<&str> {
+        let method = self.methods.get(method_index)?;
+        let entry = method.local_variable(index, pc)?;
+        match self
+            .constant_pool
+            .get(method.constant_pool_offset + usize::from(entry.name_index))?
+        {
+            CPInfo::ConstantUtf8 { bytes } => Some(bytes),
+            _ => None,
+        }
+    }
+
+    // Return the declared max locals for a method, `None` if there is no
+    // such method.
+    pub fn max_locals(&self, method_index: usize) -> Option<u16> {
+        self.methods
+            .get(method_index)
+            .map(|method| method.max_locals)
     }
 
     // Parse constant method types, returns a tuple of argument types and
@@ -213,8 +1092,8 @@ impl Program {
     fn parse_method_types(bytes: &str) -> (Vec<Type>, Type) {
         let re = Regex::new(r"\(([^\)]*)\)([^$]+)").unwrap();
         let caps = re.captures(bytes).unwrap();
-        let arg_string = caps.get(1).map_or("", |m| return m.as_str());
-        let return_type_string = caps.get(2).map_or("", |m| return m.as_str());
+        let arg_string = caps.get(1).map_or("", |m| m.as_str());
+        let return_type_string = caps.get(2).map_or("", |m| m.as_str());
         let mut types: Vec<Type> = Vec::new();
         let ret_type = Self::decode_type(return_type_string);
 
@@ -241,9 +1120,7 @@ impl Program {
         match t.t {
             BaseTypeKind::String => 18,
             BaseTypeKind::List => {
-                return 1 + Self::decode_type_string_length(
-                    t.sub_t.as_ref().unwrap(),
-                )
+                1 + Self::decode_type_string_length(t.sub_t.as_ref().unwrap())
             }
             _ => 1,
         }
@@ -306,6 +1183,43 @@ mod tests {
     use std::env;
     use std::path::Path;
 
+    #[test]
+    #[should_panic(
+        expected = "Method 'NativeMethod.answer' (descriptor '()I') has no Code attribute"
+    )]
+    fn methods_without_code_are_reported() {
+        let env_var = env::var("CARGO_MANIFEST_DIR").unwrap();
+        let path = Path::new(&env_var).join("support/tests/NativeMethod.class");
+        let class_file_bytes = read_class_file(&path).unwrap();
+        let class_file = JVMParser::parse(&class_file_bytes).unwrap();
+        let _ = Program::new(&class_file);
+    }
+
+    #[test]
+    fn loading_classes_with_methods_without_code_fails() {
+        let env_var = env::var("CARGO_MANIFEST_DIR").unwrap();
+        let root = Path::new(&env_var);
+        let class_file_bytes =
+            read_class_file(&root.join("support/tests/Loop.class")).unwrap();
+        let class_file = JVMParser::parse(&class_file_bytes).unwrap();
+        let mut program = Program::new(&class_file);
+        let constant_pool_len = program.constant_pool.len();
+        let methods_len = program.methods.len();
+        let err = program
+            .load_class(&root.join("support/tests/NativeMethod.class"))
+            .unwrap_err();
+        assert!(matches!(
+            &err,
+            ProgramError::MissingCode { class_name, name, descriptor }
+                if class_name == "NativeMethod"
+                    && name == "answer"
+                    && descriptor == "()I"
+        ));
+        // Nothing of the rejected class is merged.
+        assert_eq!(program.constant_pool.len(), constant_pool_len);
+        assert_eq!(program.methods.len(), methods_len);
+    }
+
     #[test]
     fn can_build_program() {
         let env_var = env::var("CARGO_MANIFEST_DIR").unwrap();
@@ -321,7 +1235,7 @@ mod tests {
         let methods = vec![
             Method {
                 _name_index: 27,
-                _return_type: Type {
+                return_type: Type {
                     t: BaseTypeKind::Void,
                     sub_t: None,
                 },
@@ -332,30 +1246,42 @@ mod tests {
                         sub_t: None,
                     })),
                 }],
-                _max_stack: 2,
+                max_stack: 2,
                 max_locals: 2,
                 code: vec![
                     16, 12, 184, 0, 7, 60, 178, 0, 13, 27, 182, 0, 19, 177,
                 ],
                 _constant: None,
-                _stack_map_table: None,
+                stack_map_table: None,
+                line_number_table: None,
+                local_variable_table: None,
+                declared_exceptions: vec![],
+                constant_pool_offset: 0,
+                class_name: "Factorial".to_string(),
+                exception_handlers: vec![],
             },
             Method {
                 _name_index: 5,
-                _return_type: Type {
+                return_type: Type {
                     t: BaseTypeKind::Void,
                     sub_t: None,
                 },
                 arg_types: vec![],
-                _max_stack: 1,
+                max_stack: 1,
                 max_locals: 1,
                 code: vec![42, 183, 0, 1, 177],
                 _constant: None,
-                _stack_map_table: None,
+                stack_map_table: None,
+                line_number_table: None,
+                local_variable_table: None,
+                declared_exceptions: vec![],
+                constant_pool_offset: 0,
+                class_name: "Factorial".to_string(),
+                exception_handlers: vec![],
             },
             Method {
                 _name_index: 11,
-                _return_type: Type {
+                return_type: Type {
                     t: BaseTypeKind::Int,
                     sub_t: None,
                 },
@@ -363,22 +1289,108 @@ mod tests {
                     t: BaseTypeKind::Int,
                     sub_t: None,
                 }],
-                _max_stack: 2,
+                max_stack: 2,
                 max_locals: 3,
                 code: vec![
                     4, 60, 5, 61, 28, 26, 163, 0, 13, 27, 28, 104, 60, 132, 2,
                     1, 167, 255, 244, 27, 172,
                 ],
                 _constant: None,
-                _stack_map_table: None,
+                stack_map_table: None,
+                line_number_table: None,
+                local_variable_table: None,
+                declared_exceptions: vec![],
+                constant_pool_offset: 0,
+                class_name: "Factorial".to_string(),
+                exception_handlers: vec![],
             },
         ];
 
         for method in methods {
-            let name_index = method._name_index;
-            let program_method = &program.methods[name_index as usize];
-            assert_eq!(method.code, program_method.code);
+            let method_index = program
+                .methods
+                .iter()
+                .position(|program_method| {
+                    program_method._name_index == method._name_index
+                })
+                .unwrap();
+            let program_method = &program.methods[method_index];
+            assert_eq!(method.code(), program_method.code());
+            assert_eq!(method.max_stack(), program_method.max_stack());
+            assert_eq!(method.max_locals(), program_method.max_locals());
+            assert_eq!(method.arg_types(), program_method.arg_types());
+            assert_eq!(method.return_type(), program_method.return_type());
+            assert_eq!(
+                program.method_class(method_index),
+                Some(method.class_name.as_str())
+            );
+        }
+        assert_eq!(program.methods[program.entry_point()]._name_index, 27);
+    }
+
+    #[test]
+    fn constant_pool_is_shared() {
+        let env_var = env::var("CARGO_MANIFEST_DIR").unwrap();
+        let path =
+            Path::new(&env_var).join("support/tests/SingleFuncCall.class");
+        let class_file_bytes = read_class_file(&path).unwrap();
+        let class_file = JVMParser::parse(&class_file_bytes).unwrap();
+        let program = Program::new(&class_file);
+        let clone = program.clone();
+        assert!(Arc::ptr_eq(&program.constant_pool, &clone.constant_pool));
+        assert!(Arc::ptr_eq(
+            &program.constant_pool,
+            &class_file.constant_pool()
+        ));
+    }
+
+    #[test]
+    fn unknown_methods_have_no_code_attributes() {
+        let env_var = env::var("CARGO_MANIFEST_DIR").unwrap();
+        let path =
+            Path::new(&env_var).join("support/tests/SingleFuncCall.class");
+        let class_file_bytes = read_class_file(&path).unwrap();
+        let class_file = JVMParser::parse(&class_file_bytes).unwrap();
+        let program = Program::new(&class_file);
+        let method_index = program.methods.len();
+        assert_eq!(program.constant_pool_offset(method_index), None);
+        assert_eq!(program.max_locals(method_index), None);
+        assert!(program.max_locals(program.entry_point()).is_some());
+    }
+
+    #[test]
+    fn declared_exceptions_index_the_program_constant_pool() {
+        let env_var = env::var("CARGO_MANIFEST_DIR").unwrap();
+        let path = Path::new(&env_var).join("support/tests/Throws.class");
+        let class_file_bytes = read_class_file(&path).unwrap();
+        let class_file = JVMParser::parse(&class_file_bytes).unwrap();
+        let program = Program::new(&class_file);
+        let throwing: Vec<&Method> = program
+            .methods
+            .iter()
+            .filter(|method| !method.declared_exceptions().is_empty())
+            .collect();
+        assert_eq!(throwing.len(), 2);
+        for method in throwing {
+            let classes: Vec<&str> = method
+                .declared_exceptions()
+                .iter()
+                .filter_map(|index| program.class_name(*index as usize))
+                .collect();
+            assert_eq!(classes, vec!["java/io/IOException"]);
         }
-        assert_eq!(program.entry_point(), 27);
+    }
+
+    #[test]
+    fn type_sizes_are_in_words() {
+        assert_eq!(BaseTypeKind::Int.size(), 1);
+        assert_eq!(BaseTypeKind::Float.size(), 1);
+        assert_eq!(BaseTypeKind::String.size(), 1);
+        assert_eq!(BaseTypeKind::Reference.size(), 1);
+        assert_eq!(BaseTypeKind::List.size(), 1);
+        assert_eq!(BaseTypeKind::Long.size(), 2);
+        assert_eq!(BaseTypeKind::Double.size(), 2);
+        assert_eq!(BaseTypeKind::Void.size(), 0);
+        assert_eq!(Program::decode_type("J").size(), BaseTypeKind::Long.size());
     }
 }
@@ -2,12 +2,12 @@
 //! environment and running programs.
 use crate::bytecode::OPCode;
 use crate::jit;
-use crate::jvm::CPInfo;
+use crate::jvm::{CPInfo, ClassPath};
 use crate::profiler;
-use crate::program::{BaseTypeKind, Program};
+use crate::program::{BaseTypeKind, CallSite, Program};
 use crate::trace;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 
 /// `RuntimeErrorKind` represents the possible errors that can occur
@@ -17,6 +17,16 @@ pub enum RuntimeErrorKind {
     InvalidValue,
     InvalidOperandType(OPCode),
     MissingOperands(OPCode),
+    MissingCode(ProgramCounter),
+    DivisionByZero,
+    UnsupportedInstruction(OPCode),
+    NullReference(OPCode),
+    ArrayIndexOutOfBounds(i32),
+    StackOverflow,
+    // Loading the class with this binary name from the class path failed.
+    ClassLoading(String),
+    // No handler caught the thrown exception of this class.
+    UncaughtException(String),
 }
 
 /// `RuntimeError` is a custom type used to handle and represents
@@ -24,6 +34,26 @@ pub enum RuntimeErrorKind {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct RuntimeError {
     kind: RuntimeErrorKind,
+    // Source line of the failing instruction if its method has a line
+    // number table.
+    line: Option<u16>,
+}
+
+impl RuntimeError {
+    /// Create a new error of the given kind without a source line.
+    pub const fn new(kind: RuntimeErrorKind) -> Self {
+        Self { kind, line: None }
+    }
+
+    /// Returns the kind of error.
+    pub const fn kind(&self) -> &RuntimeErrorKind {
+        &self.kind
+    }
+
+    /// Returns the source line of the failing instruction if known.
+    pub const fn line(&self) -> Option<u16> {
+        self.line
+    }
 }
 
 impl fmt::Display for RuntimeError {
@@ -33,15 +63,59 @@ impl fmt::Display for RuntimeError {
                 write!(f, "Expected value of type (int, float, long, double)")
             }
             RuntimeErrorKind::MissingOperands(opcode) => {
-                write!(f, "Instruction {opcode} expects at least on operand, got none.")
+                write!(f, "Instruction {opcode} expects at least one operand, got none")
             }
             RuntimeErrorKind::InvalidOperandType(opcode) => {
                 write!(f, "Invalid operand type for instruction {opcode}")
             }
+            RuntimeErrorKind::MissingCode(pc) => {
+                write!(f, "No bytecode to execute @ {pc}")
+            }
+            RuntimeErrorKind::DivisionByZero => {
+                write!(f, "Integer division by zero")
+            }
+            RuntimeErrorKind::UnsupportedInstruction(opcode) => {
+                write!(f, "Unsupported use of instruction {opcode}")
+            }
+            RuntimeErrorKind::NullReference(opcode) => {
+                write!(f, "Null reference used by instruction {opcode}")
+            }
+            RuntimeErrorKind::ArrayIndexOutOfBounds(index) => {
+                write!(f, "Array index {index} out of bounds")
+            }
+            RuntimeErrorKind::StackOverflow => {
+                write!(f, "Stack overflow, too many nested method calls")
+            }
+            RuntimeErrorKind::ClassLoading(ref class_name) => {
+                write!(f, "Failed to load class {class_name}")
+            }
+            RuntimeErrorKind::UncaughtException(ref class_name) => {
+                write!(f, "Uncaught exception {class_name}")
+            }
+        }?;
+        match self.line {
+            Some(line) => write!(f, " at line {line}"),
+            None => Ok(()),
         }
     }
 }
 
+impl std::error::Error for RuntimeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        // None of the runtime errors wrap an underlying error yet.
+        None
+    }
+}
+
+/// Object allocated by `new`.
+#[derive(Debug, Clone, Default)]
+struct Object {
+    // Binary name of the class of the object.
+    class_name: String,
+    // Values of the fields of the object keyed by their name.
+    fields: HashMap<String, Value>,
+}
+
 /// JVM value types.
 #[repr(C, u8)]
 #[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
@@ -50,8 +124,89 @@ pub enum Value {
     Long(i64),
     Float(f32),
     Double(f64),
+    // Reference to an object on the heap, `None` is the null reference.
+    Reference(Option<usize>),
+}
+
+/// Values are displayed the way Java prints them, floating point values
+/// always have a fractional part.
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Int(v) => write!(f, "{v}"),
+            Self::Long(v) => write!(f, "{v}"),
+            Self::Float(v) if v.is_infinite() => {
+                write!(f, "{}Infinity", if v < 0. { "-" } else { "" })
+            }
+            Self::Double(v) if v.is_infinite() => {
+                write!(f, "{}Infinity", if v < 0. { "-" } else { "" })
+            }
+            Self::Float(v) => write!(f, "{v:?}"),
+            Self::Double(v) => write!(f, "{v:?}"),
+            Self::Reference(None) => write!(f, "null"),
+            Self::Reference(Some(index)) => write!(f, "object@{index}"),
+        }
+    }
+}
+
+/// Generates the conversions between `Value` variants and the primitive type
+/// they wrap, extracting a primitive fails if the variant doesn't match.
+///
+/// Each entry also generates a named constructor `from_$t` and a named
+/// accessor `as_$t` on `Value`.
+macro_rules! impl_value_conversions {
+    ($($variant:ident => $t:ty, $from:ident, $as:ident);*) => {
+        impl Value {
+            $(
+                #[doc = concat!(
+                    "Creates a `Value::", stringify!($variant),
+                    "` from a `", stringify!($t), "`."
+                )]
+                pub const fn $from(v: $t) -> Self {
+                    Value::$variant(v)
+                }
+
+                #[doc = concat!(
+                    "Returns the wrapped `", stringify!($t),
+                    "` if the value is a `Value::", stringify!($variant), "`."
+                )]
+                pub const fn $as(&self) -> Option<$t> {
+                    match self {
+                        Value::$variant(v) => Some(*v),
+                        _ => None,
+                    }
+                }
+            )*
+        }
+
+        $(
+            impl TryFrom<Value> for $t {
+                type Error = RuntimeError;
+
+                fn try_from(value: Value) -> Result<Self, Self::Error> {
+                    match value {
+                        Value::$variant(v) => Ok(v),
+                        _ => Err(RuntimeError::new(RuntimeErrorKind::InvalidValue)),
+                    }
+                }
+            }
+
+            impl From<$t> for Value {
+                fn from(v: $t) -> Self {
+                    Value::$variant(v)
+                }
+            }
+        )*
+    };
 }
 
+impl_value_conversions!(
+    Int => i32, from_i32, as_i32;
+    Long => i64, from_i64, as_i64;
+    Float => f32, from_f32, as_f32;
+    Double => f64, from_f64, as_f64
+);
+
 /// Trait used to represent a JVM value.
 pub trait TypedValue {
     /// Returns the base type of the value.
@@ -70,6 +225,7 @@ impl Value {
             Self::Long(_) => BaseTypeKind::Long,
             Self::Float(_) => BaseTypeKind::Float,
             Self::Double(_) => BaseTypeKind::Double,
+            Self::Reference(_) => BaseTypeKind::Reference,
         }
     }
 
@@ -85,6 +241,7 @@ impl Value {
             Self::Long(val) => Value::Long(val),
             Self::Float(val) => Value::Long(val as i64),
             Self::Double(val) => Value::Long(val as i64),
+            Self::Reference(_) => panic!("Expected primitive value type"),
         }
     }
     /// Converts an existing value from it's base type to `BaseTypeKind::Int`.
@@ -94,6 +251,7 @@ impl Value {
             Self::Long(val) => Value::Int(val as i32),
             Self::Float(val) => Value::Int(val as i32),
             Self::Double(val) => Value::Int(val as i32),
+            Self::Reference(_) => panic!("Expected primitive value type"),
         }
     }
     /// Converts an existing value from it's base type to `BaseTypeKind::Double`.
@@ -103,6 +261,7 @@ impl Value {
             Self::Long(val) => Value::Double(val as f64),
             Self::Float(val) => Value::Double(val as f64),
             Self::Double(val) => Value::Double(val),
+            Self::Reference(_) => panic!("Expected primitive value type"),
         }
     }
     /// Converts an existing value from it's base type to `BaseTypeKind::Float`.
@@ -112,6 +271,31 @@ impl Value {
             Self::Long(val) => Value::Float(val as f32),
             Self::Float(val) => Value::Float(val),
             Self::Double(val) => Value::Float(val as f32),
+            Self::Reference(_) => panic!("Expected primitive value type"),
+        }
+    }
+    /// Narrows an `int` to a `byte`, the result is sign extended back to an
+    /// `int`.
+    pub fn to_byte(&self) -> Value {
+        match *self {
+            Self::Int(val) => Value::Int(i32::from(val as i8)),
+            _ => panic!("Expected int value type"),
+        }
+    }
+    /// Narrows an `int` to a `char`, the result is zero extended back to an
+    /// `int`.
+    pub fn to_char(&self) -> Value {
+        match *self {
+            Self::Int(val) => Value::Int(i32::from(val as u16)),
+            _ => panic!("Expected int value type"),
+        }
+    }
+    /// Narrows an `int` to a `short`, the result is sign extended back to an
+    /// `int`.
+    pub fn to_short(&self) -> Value {
+        match *self {
+            Self::Int(val) => Value::Int(i32::from(val as i16)),
+            _ => panic!("Expected int value type"),
         }
     }
 
@@ -121,7 +305,9 @@ impl Value {
             (Self::Int(lhs), Self::Int(rhs)) => {
                 Self::Int(lhs.wrapping_add(*rhs))
             }
-            (Self::Long(lhs), Self::Long(rhs)) => Self::Long(lhs + rhs),
+            (Self::Long(lhs), Self::Long(rhs)) => {
+                Self::Long(lhs.wrapping_add(*rhs))
+            }
             (Self::Float(lhs), Self::Float(rhs)) => Self::Float(lhs + rhs),
             (Self::Double(lhs), Self::Double(rhs)) => Self::Double(lhs + rhs),
             _ => panic!("Expected value type"),
@@ -131,8 +317,12 @@ impl Value {
     /// Computes the difference of two values of the same type.
     pub fn sub(lhs: &Self, rhs: &Self) -> Self {
         match (lhs, rhs) {
-            (Self::Int(lhs), Self::Int(rhs)) => Self::Int(lhs - rhs),
-            (Self::Long(lhs), Self::Long(rhs)) => Self::Long(lhs - rhs),
+            (Self::Int(lhs), Self::Int(rhs)) => {
+                Self::Int(lhs.wrapping_sub(*rhs))
+            }
+            (Self::Long(lhs), Self::Long(rhs)) => {
+                Self::Long(lhs.wrapping_sub(*rhs))
+            }
             (Self::Float(lhs), Self::Float(rhs)) => Self::Float(lhs - rhs),
             (Self::Double(lhs), Self::Double(rhs)) => Self::Double(lhs - rhs),
             _ => panic!("Expected value type"),
@@ -142,38 +332,169 @@ impl Value {
     /// Computes the product of two values of the same type.
     pub fn mul(lhs: &Self, rhs: &Self) -> Self {
         match (lhs, rhs) {
-            (Self::Int(lhs), Self::Int(rhs)) => Self::Int(lhs * rhs),
-            (Self::Long(lhs), Self::Long(rhs)) => Self::Long(lhs * rhs),
+            (Self::Int(lhs), Self::Int(rhs)) => {
+                Self::Int(lhs.wrapping_mul(*rhs))
+            }
+            (Self::Long(lhs), Self::Long(rhs)) => {
+                Self::Long(lhs.wrapping_mul(*rhs))
+            }
             (Self::Float(lhs), Self::Float(rhs)) => Self::Float(lhs * rhs),
             (Self::Double(lhs), Self::Double(rhs)) => Self::Double(lhs * rhs),
             _ => panic!("Expected value type"),
         }
     }
 
-    /// Computes the division of two values of the same type.
-    pub fn div(lhs: &Self, rhs: &Self) -> Self {
+    /// Computes the division of two values of the same type, integer
+    /// division by zero fails while floating point division by zero follows
+    /// IEEE 754 (infinity or `NaN`).
+    pub fn div(lhs: &Self, rhs: &Self) -> Result<Self, RuntimeError> {
         match (lhs, rhs) {
-            (Self::Int(lhs), Self::Int(rhs)) => Self::Int(lhs / rhs),
-            (Self::Long(lhs), Self::Long(rhs)) => Self::Long(lhs / rhs),
-            (Self::Float(lhs), Self::Float(rhs)) => Self::Float(lhs / rhs),
-            (Self::Double(lhs), Self::Double(rhs)) => Self::Double(lhs / rhs),
+            (Self::Int(_), Self::Int(0)) | (Self::Long(_), Self::Long(0)) => {
+                Err(RuntimeError::new(RuntimeErrorKind::DivisionByZero))
+            }
+            // Dividing the minimum value by -1 overflows back to it.
+            (Self::Int(lhs), Self::Int(rhs)) => {
+                Ok(Self::Int(lhs.wrapping_div(*rhs)))
+            }
+            (Self::Long(lhs), Self::Long(rhs)) => {
+                Ok(Self::Long(lhs.wrapping_div(*rhs)))
+            }
+            (Self::Float(lhs), Self::Float(rhs)) => Ok(Self::Float(lhs / rhs)),
+            (Self::Double(lhs), Self::Double(rhs)) => {
+                Ok(Self::Double(lhs / rhs))
+            }
             _ => panic!("Expected value type"),
         }
     }
 
-    /// Computes the remainder of the division of two values of the same type.
-    pub fn rem(lhs: &Self, rhs: &Self) -> Self {
+    /// Computes the remainder of the division of two values of the same type,
+    /// integer remainders by zero fail like integer division.
+    ///
+    /// Floating point remainders truncate the quotient like `frem` and `drem`
+    /// i.e the result has the sign of `lhs`, which is what Rust's `%` does
+    /// (`fmod`) unlike the IEEE 754 `remainder` operation that rounds it.
+    pub fn rem(lhs: &Self, rhs: &Self) -> Result<Self, RuntimeError> {
         match (lhs, rhs) {
-            (Self::Int(lhs), Self::Int(rhs)) => Self::Int(lhs % rhs),
-            (Self::Long(lhs), Self::Long(rhs)) => Self::Long(lhs % rhs),
-            (Self::Float(lhs), Self::Float(rhs)) => Self::Float(lhs % rhs),
-            (Self::Double(lhs), Self::Double(rhs)) => Self::Double(lhs % rhs),
+            (Self::Int(_), Self::Int(0)) | (Self::Long(_), Self::Long(0)) => {
+                Err(RuntimeError::new(RuntimeErrorKind::DivisionByZero))
+            }
+            // The remainder of the minimum value by -1 is 0.
+            (Self::Int(lhs), Self::Int(rhs)) => {
+                Ok(Self::Int(lhs.wrapping_rem(*rhs)))
+            }
+            (Self::Long(lhs), Self::Long(rhs)) => {
+                Ok(Self::Long(lhs.wrapping_rem(*rhs)))
+            }
+            (Self::Float(lhs), Self::Float(rhs)) => Ok(Self::Float(lhs % rhs)),
+            (Self::Double(lhs), Self::Double(rhs)) => {
+                Ok(Self::Double(lhs % rhs))
+            }
             _ => panic!("Expected value type"),
         }
     }
 
-    /// Compares two values of the same type, returns 1 if rhs is greater than lhs
-    /// -1 if rhs is less than lhs and 0 otherwise.
+    /// Computes the bitwise and of two integral values of the same type.
+    pub fn band(lhs: &Self, rhs: &Self) -> Self {
+        match (lhs, rhs) {
+            (Self::Int(lhs), Self::Int(rhs)) => Self::Int(lhs & rhs),
+            (Self::Long(lhs), Self::Long(rhs)) => Self::Long(lhs & rhs),
+            _ => panic!("Expected integral value type"),
+        }
+    }
+
+    /// Computes the bitwise or of two integral values of the same type.
+    pub fn bor(lhs: &Self, rhs: &Self) -> Self {
+        match (lhs, rhs) {
+            (Self::Int(lhs), Self::Int(rhs)) => Self::Int(lhs | rhs),
+            (Self::Long(lhs), Self::Long(rhs)) => Self::Long(lhs | rhs),
+            _ => panic!("Expected integral value type"),
+        }
+    }
+
+    /// Computes the bitwise exclusive or of two integral values of the same
+    /// type.
+    pub fn bxor(lhs: &Self, rhs: &Self) -> Self {
+        match (lhs, rhs) {
+            (Self::Int(lhs), Self::Int(rhs)) => Self::Int(lhs ^ rhs),
+            (Self::Long(lhs), Self::Long(rhs)) => Self::Long(lhs ^ rhs),
+            _ => panic!("Expected integral value type"),
+        }
+    }
+
+    /// Shifts an integral value left, the shift distance is always an `int`
+    /// of which only the low 5 (`int`) or 6 (`long`) bits are used.
+    pub fn shl(lhs: &Self, rhs: &Self) -> Self {
+        match (lhs, rhs) {
+            (Self::Int(lhs), Self::Int(rhs)) => Self::Int(lhs << (rhs & 0x1f)),
+            (Self::Long(lhs), Self::Int(rhs)) => {
+                Self::Long(lhs << (rhs & 0x3f))
+            }
+            _ => panic!("Expected integral value type"),
+        }
+    }
+
+    /// Shifts an integral value right extending its sign, see `shl` for the
+    /// shift distance.
+    pub fn shr(lhs: &Self, rhs: &Self) -> Self {
+        match (lhs, rhs) {
+            (Self::Int(lhs), Self::Int(rhs)) => Self::Int(lhs >> (rhs & 0x1f)),
+            (Self::Long(lhs), Self::Int(rhs)) => {
+                Self::Long(lhs >> (rhs & 0x3f))
+            }
+            _ => panic!("Expected integral value type"),
+        }
+    }
+
+    /// Shifts an integral value right filling with zeros, see `shl` for the
+    /// shift distance.
+    pub fn ushr(lhs: &Self, rhs: &Self) -> Self {
+        match (lhs, rhs) {
+            (Self::Int(lhs), Self::Int(rhs)) => {
+                Self::Int(((*lhs as u32) >> (rhs & 0x1f)) as i32)
+            }
+            (Self::Long(lhs), Self::Int(rhs)) => {
+                Self::Long(((*lhs as u64) >> (rhs & 0x3f)) as i64)
+            }
+            _ => panic!("Expected integral value type"),
+        }
+    }
+
+    /// Returns the arithmetic negation of a value, integral negation wraps
+    /// so negating the minimum value yields itself as the JVM specifies.
+    pub fn neg(value: &Self) -> Self {
+        match value {
+            Self::Int(v) => Self::Int(v.wrapping_neg()),
+            Self::Long(v) => Self::Long(v.wrapping_neg()),
+            Self::Float(v) => Self::Float(-v),
+            Self::Double(v) => Self::Double(-v),
+            Self::Reference(_) => panic!("Expected primitive value type"),
+        }
+    }
+
+    /// Returns the default value of a field with the given descriptor, zero
+    /// for primitive types and `null` for references.
+    pub fn default_for(descriptor: &str) -> Self {
+        match descriptor.as_bytes().first() {
+            Some(b'J') => Self::Long(0),
+            Some(b'F') => Self::Float(0.),
+            Some(b'D') => Self::Double(0.),
+            Some(b'L' | b'[') => Self::Reference(None),
+            _ => Self::Int(0),
+        }
+    }
+
+    /// Returns whether the value is a floating point `NaN`.
+    pub const fn is_nan(&self) -> bool {
+        match *self {
+            Self::Float(v) => v.is_nan(),
+            Self::Double(v) => v.is_nan(),
+            _ => false,
+        }
+    }
+
+    /// Compares two values of the same type, returns 1 if lhs is greater than
+    /// rhs, -1 if lhs is less than rhs and 0 otherwise (including when either
+    /// value is `NaN`).
     pub fn compare(lhs: &Self, rhs: &Self) -> i32 {
         match (lhs, rhs) {
             (Self::Int(lhs), Self::Int(rhs)) => Self::cmp(lhs, rhs),
@@ -184,6 +505,35 @@ impl Value {
         }
     }
 
+    /// Compares two floats for `fcmpg`, returns 1 if either value is `NaN`.
+    pub fn fcmpg(lhs: &Self, rhs: &Self) -> i32 {
+        Self::compare_or(lhs, rhs, 1)
+    }
+
+    /// Compares two floats for `fcmpl`, returns -1 if either value is `NaN`.
+    pub fn fcmpl(lhs: &Self, rhs: &Self) -> i32 {
+        Self::compare_or(lhs, rhs, -1)
+    }
+
+    /// Compares two doubles for `dcmpg`, returns 1 if either value is `NaN`.
+    pub fn dcmpg(lhs: &Self, rhs: &Self) -> i32 {
+        Self::compare_or(lhs, rhs, 1)
+    }
+
+    /// Compares two doubles for `dcmpl`, returns -1 if either value is `NaN`.
+    pub fn dcmpl(lhs: &Self, rhs: &Self) -> i32 {
+        Self::compare_or(lhs, rhs, -1)
+    }
+
+    /// Compares two values, returns `unordered` if either value is `NaN`.
+    fn compare_or(lhs: &Self, rhs: &Self, unordered: i32) -> i32 {
+        if lhs.is_nan() || rhs.is_nan() {
+            unordered
+        } else {
+            Self::compare(lhs, rhs)
+        }
+    }
+
     /// Comparison function for primitive types that implement `PartialOrd`.
     fn cmp<T: PartialOrd>(lhs: &T, rhs: &T) -> i32 {
         if lhs < rhs {
@@ -196,15 +546,96 @@ impl Value {
 
 /// Instructions are composed of an opcode and list of optional
 /// arguments or parameters.
+///
+/// Unlike `Value`, instructions are compared structurally : floating point
+/// operands are equal when they have the same bit pattern and all `NaN`s are
+/// equal to each other. This makes `Instruction` usable as a key in a
+/// `HashSet` or `HashMap`.
 #[derive(Debug, Clone)]
 pub struct Instruction {
     mnemonic: OPCode,
     operands: Option<Vec<Value>>,
 }
 
+/// `SwitchTable` is the jump table of a `tableswitch` or `lookupswitch`
+/// instruction, offsets are relative to the switch opcode.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SwitchTable {
+    // Offset taken when no key matches.
+    pub default: i32,
+    // Pairs of key and offset taken when the key matches.
+    pub targets: Vec<(i32, i32)>,
+}
+
+impl SwitchTable {
+    /// Returns the offset to jump to for `key`.
+    pub fn offset(&self, key: i32) -> i32 {
+        self.targets
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map_or(self.default, |(_, offset)| *offset)
+    }
+}
+
+/// Returns the bits used to compare and hash a floating point operand, all
+/// `NaN`s share the same canonical bits.
+fn canonical_bits(value: f64) -> u64 {
+    if value.is_nan() {
+        f64::NAN.to_bits()
+    } else {
+        value.to_bits()
+    }
+}
+
+/// Structural key of an operand used by `Instruction` equality and hashing.
+fn operand_key(value: &Value) -> (u8, u64) {
+    match *value {
+        Value::Int(v) => (0, v as u64),
+        Value::Long(v) => (1, v as u64),
+        Value::Float(v) => (2, canonical_bits(v as f64)),
+        Value::Double(v) => (3, canonical_bits(v)),
+        Value::Reference(v) => (4, v.map_or(u64::MAX, |v| v as u64)),
+    }
+}
+
+impl PartialEq for Instruction {
+    fn eq(&self, other: &Self) -> bool {
+        self.mnemonic == other.mnemonic
+            && match (&self.operands, &other.operands) {
+                (Some(lhs), Some(rhs)) => {
+                    lhs.len() == rhs.len()
+                        && lhs
+                            .iter()
+                            .zip(rhs)
+                            .all(|(l, r)| operand_key(l) == operand_key(r))
+                }
+                (None, None) => true,
+                _ => false,
+            }
+    }
+}
+
+impl Eq for Instruction {}
+
+impl std::hash::Hash for Instruction {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.mnemonic.hash(state);
+        if let Some(operands) = &self.operands {
+            operands.len().hash(state);
+            for operand in operands {
+                operand_key(operand).hash(state);
+            }
+        }
+    }
+}
+
 impl fmt::Display for Instruction {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "Inst({:}, {:?})", self.mnemonic, self.operands)
+        write!(f, "{}", self.mnemonic)?;
+        for operand in self.operands.iter().flatten() {
+            write!(f, " {operand}")?;
+        }
+        Ok(())
     }
 }
 
@@ -234,10 +665,60 @@ impl Instruction {
         self.mnemonic = mnemonic
     }
 
+    /// Returns the jump table of a `tableswitch` or `lookupswitch`
+    /// instruction, `None` for other instructions or missing operands.
+    pub fn switch_table(&self) -> Option<SwitchTable> {
+        let params: Vec<i32> = self
+            .operands
+            .as_ref()?
+            .iter()
+            .map(|param| match param {
+                Value::Int(v) => Some(*v),
+                _ => None,
+            })
+            .collect::<Option<_>>()?;
+        let (&default, rest) = params.split_first()?;
+        let targets = match self.mnemonic {
+            OPCode::TableSwitch => {
+                let (&low, offsets) = rest.split_first()?;
+                offsets
+                    .iter()
+                    .skip(1)
+                    .zip(low..)
+                    .map(|(&offset, key)| (key, offset))
+                    .collect()
+            }
+            OPCode::LookupSwitch => rest
+                .get(1..)?
+                .chunks_exact(2)
+                .map(|pair| (pair[0], pair[1]))
+                .collect(),
+            _ => return None,
+        };
+        Some(SwitchTable { default, targets })
+    }
+
     // Returns a copy of instruction parameters.
     pub fn get_params(&self) -> Option<Vec<Value>> {
         self.operands.clone()
     }
+
+    /// Set the nth parameter of an instruction in place, unlike mutating the
+    /// copy returned by `get_params`.
+    /// # Panics
+    /// Panics if the instruction doesn't have an nth parameter.
+    pub fn set_param(&mut self, index: usize, value: Value) {
+        match self
+            .operands
+            .as_mut()
+            .and_then(|params| params.get_mut(index))
+        {
+            Some(param) => *param = value,
+            None => {
+                panic!("{} has no parameter at index {index}", self.mnemonic)
+            }
+        }
+    }
 }
 
 /// Program counter for the runtime points to the current instruction
@@ -289,12 +770,15 @@ impl Default for ProgramCounter {
 
 /// Frames are used to store data and partial results within a method's scope.
 /// Each frame has an operand stack and array of local variables.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct Frame {
     pub pc: ProgramCounter,
     stack: Vec<Value>,
     pub locals: HashMap<usize, Value>,
     pub max_locals: u16,
+    // Instruction index of the opcode of the last decoded instruction,
+    // switch offsets are relative to it.
+    opcode_index: usize,
 }
 
 impl Frame {
@@ -311,6 +795,68 @@ impl Frame {
     fn inc_instruction_index(&mut self) {
         self.pc.instruction_index += 1;
     }
+
+    /// Returns a view of the frame data used by native traces.
+    pub(crate) fn view(&mut self) -> FrameView<'_> {
+        FrameView {
+            locals: &mut self.locals,
+            max_locals: self.max_locals,
+            pc: &mut self.pc,
+        }
+    }
+}
+
+/// `FrameView` exposes the parts of a `Frame` that native traces read and
+/// write, so the JIT doesn't depend on the frame layout.
+pub(crate) struct FrameView<'a> {
+    // Local variables of the frame.
+    pub locals: &'a mut HashMap<usize, Value>,
+    // Declared number of local variables of the frame.
+    pub max_locals: u16,
+    // Program counter of the frame, where execution continues after the
+    // native trace exits.
+    pub pc: &'a mut ProgramCounter,
+}
+
+/// Default maximum number of frames on the call stack.
+pub const DEFAULT_MAX_FRAMES: usize = 512;
+
+/// `RuntimeConfig` holds the tuning knobs of the tracing JIT and the
+/// limits of the interpreter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RuntimeConfig {
+    // Number of entries to a loop header before it's considered hot.
+    pub jit_threshold: usize,
+    // Recordings with more instructions than this are aborted.
+    pub max_trace_length: usize,
+    // When disabled loops are never recorded nor compiled.
+    pub jit_enabled: bool,
+    // Invocations that would push more frames than this fail with
+    // `RuntimeErrorKind::StackOverflow`.
+    pub max_frames: usize,
+}
+
+impl Default for RuntimeConfig {
+    fn default() -> Self {
+        Self {
+            jit_threshold: profiler::DEFAULT_THRESHOLD,
+            max_trace_length: trace::DEFAULT_MAX_TRACE_LENGTH,
+            jit_enabled: true,
+            max_frames: DEFAULT_MAX_FRAMES,
+        }
+    }
+}
+
+/// `StepResult` describes where execution stands after `Runtime::step`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepResult {
+    // An instruction was evaluated and execution can continue.
+    Continue,
+    // There are no more frames to execute.
+    Done,
+    // A native trace was executed and exited at `exit_pc` in the current
+    // method.
+    JitTransferred { exit_pc: usize },
 }
 
 /// `Runtime` represents an execution context for JVM programs
@@ -319,9 +865,11 @@ impl Frame {
 /// execution to the `Jit` when a block is considered hot.
 ///
 /// `Trace` structure :
-/// +-------------------------
+/// ```text
+/// +------------------------+
 /// + `Profile`   | `Record` +
 /// +------------------------+
+/// ```
 ///
 /// `Profile` has all the profiling information for a trace, such
 /// as how many times the trace was executed at this pc value and
@@ -347,12 +895,31 @@ pub struct Runtime {
     traces: HashMap<ProgramCounter, trace::Trace>,
     // Used to store return values of the VM.
     return_values: Vec<Value>,
+    // Number of times each opcode was evaluated by the interpreter.
+    opcode_counts: HashMap<OPCode, u64>,
+    // Values of the static fields keyed by class and field name, fields
+    // are added on their first `putstatic`.
+    static_fields: HashMap<(String, String), Value>,
+    // Objects allocated by `new`, references are indexes in the heap.
+    // Objects are never freed.
+    heap: Vec<Object>,
+    // Arrays allocated by `newarray`, references are indexes in this pool.
+    // Arrays are never freed.
+    arrays: Vec<Vec<Value>>,
+    // Tuning knobs of the tracing JIT.
+    config: RuntimeConfig,
+    // Searched for the classes of invoked methods that aren't loaded yet.
+    class_path: Option<ClassPath>,
+    // Classes the class path doesn't have, they aren't searched again.
+    missing_classes: HashSet<String>,
+    // Classes whose static initializer (`<clinit>`) ran or is running.
+    initialized_classes: HashSet<String>,
 }
 
 impl Runtime {
     // TODO: considering moving Program to JVM module instead
     // to avoid repetition here and keeps things tight.
-    pub fn new(program: Program) -> Self {
+    pub fn new(program: Program, config: RuntimeConfig) -> Self {
         let main = program.entry_point();
         let pc = ProgramCounter {
             instruction_index: 0,
@@ -363,84 +930,263 @@ impl Runtime {
             stack: Vec::new(),
             locals: HashMap::new(),
             max_locals: 0,
+            opcode_index: 0,
         };
+        let mut frames = vec![initial_frame];
+        // The main class is initialized before `main` runs.
+        let mut initialized_classes = HashSet::new();
+        if let Some(class_name) = program.method_class(main) {
+            initialized_classes.insert(class_name.to_string());
+            if let Some(clinit) = program.class_initializer(class_name) {
+                frames.push(Frame {
+                    pc: ProgramCounter {
+                        instruction_index: 0,
+                        method_index: clinit,
+                    },
+                    stack: Vec::new(),
+                    locals: HashMap::new(),
+                    max_locals: 0,
+                    opcode_index: 0,
+                });
+            }
+        }
         Self {
             program,
-            frames: vec![initial_frame],
-            recorder: trace::Recorder::new(),
-            profiler: profiler::Profiler::new(),
+            frames,
+            recorder: trace::Recorder::with_max_trace_length(
+                config.max_trace_length,
+            ),
+            profiler: profiler::Profiler::new(config.jit_threshold),
             jit_cache: jit::JitCache::new(),
             traces: HashMap::new(),
             return_values: vec![],
+            opcode_counts: HashMap::new(),
+            static_fields: HashMap::new(),
+            heap: Vec::new(),
+            arrays: Vec::new(),
+            config,
+            class_path: None,
+            missing_classes: HashSet::new(),
+            initialized_classes,
         }
     }
 
+    /// Load the classes of invoked methods from `class_path` the first time
+    /// they are called instead of requiring them to be loaded upfront.
+    #[must_use]
+    pub fn with_class_path(mut self, class_path: ClassPath) -> Self {
+        self.class_path = Some(class_path);
+        self
+    }
+
     pub fn run(&mut self, jit_mode: bool) -> Result<(), RuntimeError> {
-        loop {
-            // No more frames, exit.
-            if self.frames.is_empty() {
-                break;
-            }
-            // Fetch the next instruction.
-            let pc = self.frames.last().unwrap().pc;
-            if self.recorder.is_recording()
-                && self.recorder.is_done_recording(pc)
-                && !self.traces.contains_key(&pc)
-            {
-                // TODO: Clean up the naming on trace recoder implementation.
-                let recorded_trace = self.recorder.recording();
-                // TODO: Remove clone once we get rid of stdout trace dump
-                // Cache the trace.
-                self.traces.insert(pc, recorded_trace.clone());
-                // Dump trace to stdout.
-                #[cfg(debug_assertions)]
-                for entry in &recorded_trace.trace {
-                    println!("{entry}");
-                }
-                // Compile recorded trace.
-                if jit_mode {
-                    self.jit_cache.compile(&recorded_trace);
-                }
-            }
-            if self.jit_cache.has_native_trace(pc) && jit_mode {
-                #[cfg(debug_assertions)]
-                println!("Jit entry @ {pc}");
-                // If we have a native trace at this pc run it
-                // and capture the return value which is the next
-                // pc to execute and restore the stack frame.
-                let mut frame = self.frames.pop().unwrap();
-                let _cont_pc = self.jit_cache.execute(pc, &mut frame);
-                self.frames.push(frame);
-                #[cfg(debug_assertions)]
-                println!("Jit exit @ {_cont_pc}");
-                // Return execution to the interpreter.
-                continue;
-            } else {
-                let inst = self.fetch();
-                self.profiler.count_entry(&pc);
+        while self.execute_step(jit_mode)? != StepResult::Done {}
+        Ok(())
+    }
 
-                if self.profiler.is_hot(&pc) {
-                    // println!("Found a hot loop...");
-                    // println!("Recording...");
-                    self.recorder.init(pc, pc);
-                }
+    /// Execute the next instruction, or the native trace at the program
+    /// counter when the JIT is enabled, and return where execution stands.
+    pub fn step(&mut self) -> Result<StepResult, RuntimeError> {
+        self.execute_step(self.config.jit_enabled)
+    }
+
+    /// Returns the program counter of the current frame or `None` if there
+    /// are no frames.
+    pub fn current_pc(&self) -> Option<ProgramCounter> {
+        self.frames.last().map(|frame| frame.pc)
+    }
 
-                if self.recorder.is_recording() {
-                    self.recorder.record(pc, inst.clone());
+    /// Execute a single step, native traces are only compiled and executed
+    /// when `jit_mode` is set.
+    fn execute_step(
+        &mut self,
+        jit_mode: bool,
+    ) -> Result<StepResult, RuntimeError> {
+        // No more frames, exit.
+        if self.frames.is_empty() {
+            return Ok(StepResult::Done);
+        }
+        // Fetch the next instruction.
+        let pc = self.frames.last().unwrap().pc;
+        // Once the recorder closes the loop, finalize the recording,
+        // cache it and compile it to a native trace. Aborted recordings are
+        // dropped.
+        if self.recorder.is_done_recording(pc) {
+            match self.recorder.recording() {
+                Ok(recorded_trace) => {
+                    self.cache_trace(pc, recorded_trace, jit_mode)
+                }
+                Err(_reason) => {
+                    #[cfg(debug_assertions)]
+                    println!("Recording aborted @ {pc} : {_reason}");
                 }
-                #[cfg(debug_assertions)]
-                println!("eval {inst} @ {pc}");
-                // Evaluate the instruction.
-                self.eval(&inst)?
             }
         }
-        Ok(())
+        if self.jit_cache.has_native_trace(pc)
+            && jit_mode
+            && self.config.jit_enabled
+        {
+            // The instructions executed by the native trace aren't recorded,
+            // an ongoing recording would miss them.
+            self.recorder.abort(trace::AbortReason::NativeTraceEntered);
+            #[cfg(debug_assertions)]
+            println!("Jit entry @ {pc}");
+            // If we have a native trace at this pc run it
+            // and capture the return value which is the next
+            // pc to execute and restore the stack frame.
+            let frame = self.frames.last_mut().unwrap();
+            let exit_pc = self.jit_cache.execute(pc, &mut frame.view());
+            #[cfg(debug_assertions)]
+            println!("Jit exit @ {exit_pc}");
+            // Count the side-exit so frequently taken exits become hot
+            // and get their own traces.
+            self.profiler.count_exit(&exit_pc);
+            // Return execution to the interpreter.
+            return Ok(StepResult::JitTransferred {
+                exit_pc: exit_pc.get_instruction_index(),
+            });
+        } else {
+            let inst = self.fetch().map_err(|err| self.locate(err, pc))?;
+            self.profiler.count_entry(&pc);
+
+            // Hot loops are recorded unless they already have a valid
+            // trace, evicted traces are recorded again.
+            let has_trace = if jit_mode {
+                self.jit_cache.has_native_trace(pc)
+            } else {
+                self.traces.contains_key(&pc)
+            };
+            // With accurate profiling every instruction of a loop body
+            // becomes hot, the ongoing recording is kept instead of being
+            // restarted at each of them.
+            let keep_recording = cfg!(feature = "accurate_profiling")
+                && self.recorder.is_recording();
+            if self.config.jit_enabled
+                && self.profiler.is_hot(&pc)
+                && !(self.recorder.is_compiled(&pc) && has_trace)
+                && !keep_recording
+            {
+                self.recorder.init(pc, pc);
+            }
+
+            if self.recorder.is_recording() {
+                self.recorder.record(pc, inst.clone());
+            }
+            #[cfg(debug_assertions)]
+            println!("eval {inst} @ {pc}");
+            // Evaluate the instruction.
+            self.eval(&inst).map_err(|err| self.locate(err, pc))?;
+        }
+        if self.frames.is_empty() {
+            Ok(StepResult::Done)
+        } else {
+            Ok(StepResult::Continue)
+        }
+    }
+
+    /// Attach the source line of the instruction at `pc` to `err`.
+    fn locate(&self, err: RuntimeError, pc: ProgramCounter) -> RuntimeError {
+        RuntimeError {
+            line: self
+                .program
+                .line_number(pc.get_method_index(), pc.get_instruction_index()),
+            ..err
+        }
+    }
+
+    /// Cache the trace recorded at `pc` and compile it to a native trace in
+    /// `jit_mode`, traces already cached at `pc` are kept.
+    fn cache_trace(
+        &mut self,
+        pc: ProgramCounter,
+        recorded_trace: trace::Trace,
+        jit_mode: bool,
+    ) {
+        if self.traces.contains_key(&pc) {
+            return;
+        }
+        // Dump trace to stdout.
+        #[cfg(debug_assertions)]
+        print!("{}", recorded_trace.to_text());
+        // Compile recorded trace.
+        if jit_mode {
+            for evicted in self.jit_cache.compile(&recorded_trace) {
+                self.evict_trace(evicted);
+            }
+        }
+        // Cache the trace.
+        self.traces.insert(pc, recorded_trace);
+    }
+
+    /// Evict the trace recorded at `pc` and its native trace, the loop is
+    /// recorded again the next time it is hot.
+    pub fn evict_trace(&mut self, pc: ProgramCounter) {
+        self.jit_cache.evict(pc);
+        self.traces.remove(&pc);
+        self.recorder.invalidate(&pc);
+    }
+
+    /// Returns the native traces compiled so far keyed by the program counter
+    /// of their loop header, sorted by program counter.
+    pub fn native_traces(&self) -> Vec<(ProgramCounter, &jit::NativeTrace)> {
+        let mut traces: Vec<_> = self.jit_cache.native_traces().collect();
+        traces.sort_by_key(|(pc, _)| {
+            (pc.get_method_index(), pc.get_instruction_index())
+        });
+        traces
     }
 
     /// Returns the top value in the return values stack.
     /// Used for testing only
     pub fn top_return_value(&self) -> Option<Value> {
-        return self.return_values.last().copied();
+        self.return_values.last().copied()
+    }
+
+    /// Returns a snapshot of the operand stack of the current frame or an
+    /// empty stack if there are no frames.
+    pub fn inspect_stack(&self) -> Vec<Value> {
+        self.frames
+            .last()
+            .map(|frame| frame.stack.clone())
+            .unwrap_or_default()
+    }
+
+    /// Returns a snapshot of the local variables of the current frame or
+    /// an empty map if there are no frames.
+    pub fn inspect_locals(&self) -> HashMap<usize, Value> {
+        self.frames
+            .last()
+            .map(|frame| frame.locals.clone())
+            .unwrap_or_default()
+    }
+
+    /// Returns the source name of the local at `local_index` in the frame at
+    /// `frame_index` (the outermost frame is 0) for the instruction the
+    /// frame executes next, if its method has a local variable table.
+    pub fn local_name(
+        &self,
+        frame_index: usize,
+        local_index: usize,
+    ) -> Option<&str> {
+        let pc = self.frames.get(frame_index)?.pc;
+        self.program.local_name(
+            pc.get_method_index(),
+            local_index,
+            pc.get_instruction_index(),
+        )
+    }
+
+    /// Returns the loop headers entry counts and the number of instructions
+    /// interpreted so far, instructions executed in native traces aren't
+    /// counted.
+    pub fn profiling_report(&self) -> profiler::ProfileReport {
+        self.profiler.report()
+    }
+
+    /// Returns the number of times each opcode was evaluated by the
+    /// interpreter, opcodes executed in native traces aren't counted.
+    pub fn opcode_histogram(&self) -> &HashMap<OPCode, u64> {
+        &self.opcode_counts
     }
 
     /// Push a JVM value into the stack
@@ -458,6 +1204,143 @@ impl Runtime {
         }
     }
 
+    /// Pop an integer operand of `opcode` from the stack.
+    ///
+    /// Returns `RuntimeErrorKind::MissingOperands` if the stack is empty and
+    /// `RuntimeErrorKind::InvalidOperandType` if the value isn't an integer.
+    fn pop_int(&mut self, opcode: OPCode) -> Result<i32, RuntimeError> {
+        match self.pop() {
+            Some(Value::Int(value)) => Ok(value),
+            Some(_) => Err(RuntimeError::new(
+                RuntimeErrorKind::InvalidOperandType(opcode),
+            )),
+            None => Err(RuntimeError::new(RuntimeErrorKind::MissingOperands(
+                opcode,
+            ))),
+        }
+    }
+
+    /// Pop a long operand of `opcode` from the stack.
+    ///
+    /// Returns `RuntimeErrorKind::MissingOperands` if the stack is empty and
+    /// `RuntimeErrorKind::InvalidOperandType` if the value isn't a long.
+    fn pop_long(&mut self, opcode: OPCode) -> Result<i64, RuntimeError> {
+        match self.pop() {
+            Some(Value::Long(value)) => Ok(value),
+            Some(_) => Err(RuntimeError::new(
+                RuntimeErrorKind::InvalidOperandType(opcode),
+            )),
+            None => Err(RuntimeError::new(RuntimeErrorKind::MissingOperands(
+                opcode,
+            ))),
+        }
+    }
+
+    /// Pop a primitive operand of `opcode` from the stack.
+    ///
+    /// Returns `RuntimeErrorKind::MissingOperands` if the stack is empty and
+    /// `RuntimeErrorKind::InvalidOperandType` if the value is a reference.
+    fn pop_primitive(&mut self, opcode: OPCode) -> Result<Value, RuntimeError> {
+        match self.pop() {
+            Some(Value::Reference(_)) => Err(RuntimeError::new(
+                RuntimeErrorKind::InvalidOperandType(opcode),
+            )),
+            Some(value) => Ok(value),
+            None => Err(RuntimeError::new(RuntimeErrorKind::MissingOperands(
+                opcode,
+            ))),
+        }
+    }
+
+    /// Pop an object reference from the stack for `opcode` and return its
+    /// index in the heap.
+    fn pop_object(&mut self, opcode: OPCode) -> Result<usize, RuntimeError> {
+        match self.pop() {
+            Some(Value::Reference(Some(object)))
+                if object < self.heap.len() =>
+            {
+                Ok(object)
+            }
+            Some(Value::Reference(None)) => {
+                Err(RuntimeError::new(RuntimeErrorKind::NullReference(opcode)))
+            }
+            Some(_) => Err(RuntimeError::new(
+                RuntimeErrorKind::InvalidOperandType(opcode),
+            )),
+            None => Err(RuntimeError::new(RuntimeErrorKind::MissingOperands(
+                opcode,
+            ))),
+        }
+    }
+
+    /// Pop an array reference from the stack for `opcode` and return its
+    /// index in the array pool.
+    fn pop_array(&mut self, opcode: OPCode) -> Result<usize, RuntimeError> {
+        match self.pop() {
+            Some(Value::Reference(Some(array)))
+                if array < self.arrays.len() =>
+            {
+                Ok(array)
+            }
+            Some(Value::Reference(None)) => {
+                Err(RuntimeError::new(RuntimeErrorKind::NullReference(opcode)))
+            }
+            Some(_) => Err(RuntimeError::new(
+                RuntimeErrorKind::InvalidOperandType(opcode),
+            )),
+            None => Err(RuntimeError::new(RuntimeErrorKind::MissingOperands(
+                opcode,
+            ))),
+        }
+    }
+
+    /// Returns the slot of `index` in `array`, fails with
+    /// `RuntimeErrorKind::ArrayIndexOutOfBounds` if it is out of bounds.
+    fn array_slot(
+        &self,
+        array: usize,
+        index: i32,
+    ) -> Result<usize, RuntimeError> {
+        usize::try_from(index)
+            .ok()
+            .filter(|slot| *slot < self.arrays[array].len())
+            .ok_or(RuntimeError::new(RuntimeErrorKind::ArrayIndexOutOfBounds(
+                index,
+            )))
+    }
+
+    /// Pop values from the stack until they span `slots` slots, `long` and
+    /// `double` values take two slots. Values are returned in stack order.
+    ///
+    /// Returns `RuntimeErrorKind::MissingOperands` if the stack runs out of
+    /// values and `RuntimeErrorKind::InvalidOperandType` if a two slot value
+    /// would be split.
+    fn pop_slots(
+        &mut self,
+        slots: usize,
+        opcode: OPCode,
+    ) -> Result<Vec<Value>, RuntimeError> {
+        let mut values = Vec::new();
+        let mut count = 0;
+        while count < slots {
+            let value = self.pop().ok_or(RuntimeError::new(
+                RuntimeErrorKind::MissingOperands(opcode),
+            ))?;
+            count += match value {
+                Value::Long(_) | Value::Double(_) => 2,
+                _ => 1,
+            };
+            values.push(value);
+        }
+        if count > slots {
+            return Err(RuntimeError::new(
+                RuntimeErrorKind::InvalidOperandType(opcode),
+            ));
+        }
+        values.reverse();
+        Ok(values)
+    }
+
     /// Store the topmost value in the stack as local value.
     fn store(&mut self, index: usize) {
         if let Some(value) = self.pop() {
@@ -477,18 +1360,29 @@ impl Runtime {
     }
 
     /// Jump with a relative offset.
-    fn jump(&mut self, offset: i32) {
+    ///
+    /// Returns `RuntimeErrorKind::InvalidValue` if the target is before the
+    /// start of the method.
+    fn jump(&mut self, offset: i32) -> Result<(), RuntimeError> {
         if let Some(frame) = self.frames.last_mut() {
-            frame.pc.instruction_index = (frame.pc.instruction_index as isize
-                + offset as isize)
-                as usize;
+            let target = frame.pc.instruction_index as isize + offset as isize;
+            frame.pc.instruction_index =
+                usize::try_from(target).map_err(|_| {
+                    RuntimeError::new(RuntimeErrorKind::InvalidValue)
+                })?;
         }
+        Ok(())
     }
 
     /// Evaluate a given instruction.
     fn eval(&mut self, inst: &Instruction) -> Result<(), RuntimeError> {
+        *self.opcode_counts.entry(inst.mnemonic).or_insert(0) += 1;
         if let Some(_frame) = self.frames.last_mut() {
             match inst.mnemonic {
+                OPCode::AConstNull => {
+                    self.push(Value::Reference(None));
+                    Ok(())
+                }
                 OPCode::IconstM1 => {
                     self.push(Value::Int(-1));
                     Ok(())
@@ -553,59 +1447,60 @@ impl Runtime {
                         self.push(params[0]);
                         Ok(())
                     }
-                    None => Err(RuntimeError {
-                        kind: RuntimeErrorKind::MissingOperands(inst.mnemonic),
-                    }),
+                    None => Err(RuntimeError::new(
+                        RuntimeErrorKind::MissingOperands(inst.mnemonic),
+                    )),
                 },
                 // Load operations.
                 OPCode::ILoad
                 | OPCode::LLoad
                 | OPCode::FLoad
-                | OPCode::DLoad => inst.operands.as_ref().map_or_else(
+                | OPCode::DLoad
+                | OPCode::ALoad => inst.operands.as_ref().map_or_else(
                     || {
-                        Err(RuntimeError {
-                            kind: RuntimeErrorKind::MissingOperands(
-                                inst.mnemonic,
-                            ),
-                        })
+                        Err(RuntimeError::new(
+                            RuntimeErrorKind::MissingOperands(inst.mnemonic),
+                        ))
                     },
-                    |params| match params.get(0) {
+                    |params| match params.first() {
                         Some(Value::Int(v)) => {
                             self.load(*v as usize);
                             Ok(())
                         }
-                        _ => Err(RuntimeError {
-                            kind: RuntimeErrorKind::InvalidOperandType(
-                                inst.mnemonic,
-                            ),
-                        }),
+                        _ => Err(RuntimeError::new(
+                            RuntimeErrorKind::InvalidOperandType(inst.mnemonic),
+                        )),
                     },
                 ),
                 OPCode::ILoad0
                 | OPCode::LLoad0
                 | OPCode::FLoad0
-                | OPCode::DLoad0 => {
+                | OPCode::DLoad0
+                | OPCode::ALoad0 => {
                     self.load(0);
                     Ok(())
                 }
                 OPCode::ILoad1
                 | OPCode::LLoad1
                 | OPCode::FLoad1
-                | OPCode::DLoad1 => {
+                | OPCode::DLoad1
+                | OPCode::ALoad1 => {
                     self.load(1);
                     Ok(())
                 }
                 OPCode::ILoad2
                 | OPCode::LLoad2
                 | OPCode::FLoad2
-                | OPCode::DLoad2 => {
+                | OPCode::DLoad2
+                | OPCode::ALoad2 => {
                     self.load(2);
                     Ok(())
                 }
                 OPCode::ILoad3
                 | OPCode::LLoad3
                 | OPCode::FLoad3
-                | OPCode::DLoad3 => {
+                | OPCode::DLoad3
+                | OPCode::ALoad3 => {
                     self.load(3);
                     Ok(())
                 }
@@ -613,51 +1508,52 @@ impl Runtime {
                 OPCode::IStore
                 | OPCode::LStore
                 | OPCode::FStore
-                | OPCode::DStore => inst.operands.as_ref().map_or_else(
+                | OPCode::DStore
+                | OPCode::AStore => inst.operands.as_ref().map_or_else(
                     || {
-                        Err(RuntimeError {
-                            kind: RuntimeErrorKind::MissingOperands(
-                                inst.mnemonic,
-                            ),
-                        })
+                        Err(RuntimeError::new(
+                            RuntimeErrorKind::MissingOperands(inst.mnemonic),
+                        ))
                     },
-                    |params| match params.get(0) {
+                    |params| match params.first() {
                         Some(Value::Int(v)) => {
                             self.store(*v as usize);
                             Ok(())
                         }
-                        _ => Err(RuntimeError {
-                            kind: RuntimeErrorKind::InvalidOperandType(
-                                inst.mnemonic,
-                            ),
-                        }),
+                        _ => Err(RuntimeError::new(
+                            RuntimeErrorKind::InvalidOperandType(inst.mnemonic),
+                        )),
                     },
                 ),
                 OPCode::IStore0
                 | OPCode::LStore0
                 | OPCode::FStore0
-                | OPCode::DStore0 => {
+                | OPCode::DStore0
+                | OPCode::AStore0 => {
                     self.store(0);
                     Ok(())
                 }
                 OPCode::IStore1
                 | OPCode::LStore1
                 | OPCode::FStore1
-                | OPCode::DStore1 => {
+                | OPCode::DStore1
+                | OPCode::AStore1 => {
                     self.store(1);
                     Ok(())
                 }
                 OPCode::IStore2
                 | OPCode::LStore2
                 | OPCode::FStore2
-                | OPCode::DStore2 => {
+                | OPCode::DStore2
+                | OPCode::AStore2 => {
                     self.store(2);
                     Ok(())
                 }
                 OPCode::IStore3
                 | OPCode::LStore3
                 | OPCode::FStore3
-                | OPCode::DStore3 => {
+                | OPCode::DStore3
+                | OPCode::AStore3 => {
                     self.store(3);
                     Ok(())
                 }
@@ -670,9 +1566,7 @@ impl Runtime {
                         self.push(Value::add(&a, &b));
                         Ok(())
                     } else {
-                        Err(RuntimeError {
-                            kind: RuntimeErrorKind::InvalidValue,
-                        })
+                        Err(RuntimeError::new(RuntimeErrorKind::InvalidValue))
                     }
                 }
                 OPCode::ISub | OPCode::LSub | OPCode::FSub | OPCode::DSub => {
@@ -683,9 +1577,7 @@ impl Runtime {
                         self.push(Value::sub(&a, &b));
                         Ok(())
                     } else {
-                        Err(RuntimeError {
-                            kind: RuntimeErrorKind::InvalidValue,
-                        })
+                        Err(RuntimeError::new(RuntimeErrorKind::InvalidValue))
                     }
                 }
                 OPCode::IMul | OPCode::LMul | OPCode::FMul | OPCode::DMul => {
@@ -696,9 +1588,7 @@ impl Runtime {
                         self.push(Value::mul(&a, &b));
                         Ok(())
                     } else {
-                        Err(RuntimeError {
-                            kind: RuntimeErrorKind::InvalidValue,
-                        })
+                        Err(RuntimeError::new(RuntimeErrorKind::InvalidValue))
                     }
                 }
                 OPCode::IDiv | OPCode::LDiv | OPCode::FDiv | OPCode::DDiv => {
@@ -706,12 +1596,10 @@ impl Runtime {
                     let lhs = self.pop();
 
                     if let (Some(a), Some(b)) = (lhs, rhs) {
-                        self.push(Value::div(&a, &b));
+                        self.push(Value::div(&a, &b)?);
                         Ok(())
                     } else {
-                        Err(RuntimeError {
-                            kind: RuntimeErrorKind::InvalidValue,
-                        })
+                        Err(RuntimeError::new(RuntimeErrorKind::InvalidValue))
                     }
                 }
                 OPCode::IRem | OPCode::LRem | OPCode::FRem | OPCode::DRem => {
@@ -719,22 +1607,113 @@ impl Runtime {
                     let lhs = self.pop();
 
                     if let (Some(a), Some(b)) = (lhs, rhs) {
-                        self.push(Value::rem(&a, &b));
+                        self.push(Value::rem(&a, &b)?);
+                        Ok(())
+                    } else {
+                        Err(RuntimeError::new(RuntimeErrorKind::InvalidValue))
+                    }
+                }
+                // Bitwise operations.
+                OPCode::Iand
+                | OPCode::Land
+                | OPCode::IOr
+                | OPCode::LOr
+                | OPCode::IXor
+                | OPCode::LXor
+                | OPCode::IShl
+                | OPCode::LShl
+                | OPCode::IShr
+                | OPCode::LShr
+                | OPCode::IUShr
+                | OPCode::LUShr => {
+                    let rhs = self.pop();
+                    let lhs = self.pop();
+
+                    if let (Some(a), Some(b)) = (lhs, rhs) {
+                        let op = match inst.mnemonic {
+                            OPCode::Iand | OPCode::Land => Value::band,
+                            OPCode::IOr | OPCode::LOr => Value::bor,
+                            OPCode::IXor | OPCode::LXor => Value::bxor,
+                            OPCode::IShl | OPCode::LShl => Value::shl,
+                            OPCode::IShr | OPCode::LShr => Value::shr,
+                            _ => Value::ushr,
+                        };
+                        self.push(op(&a, &b));
+                        Ok(())
+                    } else {
+                        Err(RuntimeError::new(RuntimeErrorKind::InvalidValue))
+                    }
+                }
+                OPCode::Pop => match self.pop() {
+                    Some(_) => Ok(()),
+                    None => Err(RuntimeError::new(
+                        RuntimeErrorKind::MissingOperands(inst.mnemonic),
+                    )),
+                },
+                OPCode::Pop2 => match self.pop() {
+                    // A single `long` or `double` takes both slots.
+                    Some(Value::Long(_) | Value::Double(_)) => Ok(()),
+                    Some(_) => match self.pop() {
+                        Some(_) => Ok(()),
+                        None => Err(RuntimeError::new(
+                            RuntimeErrorKind::MissingOperands(inst.mnemonic),
+                        )),
+                    },
+                    None => Err(RuntimeError::new(
+                        RuntimeErrorKind::MissingOperands(inst.mnemonic),
+                    )),
+                },
+                OPCode::DupX1
+                | OPCode::DupX2
+                | OPCode::Dup2
+                | OPCode::Dup2X1
+                | OPCode::Dup2X2 => {
+                    // Number of slots to duplicate and number of slots below
+                    // them where the copy is inserted.
+                    let (size, depth) = match inst.mnemonic {
+                        OPCode::DupX1 => (1, 1),
+                        OPCode::DupX2 => (1, 2),
+                        OPCode::Dup2 => (2, 0),
+                        OPCode::Dup2X1 => (2, 1),
+                        _ => (2, 2),
+                    };
+                    let top = self.pop_slots(size, inst.mnemonic)?;
+                    let below = self.pop_slots(depth, inst.mnemonic)?;
+                    for value in top.iter().chain(&below).chain(&top) {
+                        self.push(*value);
+                    }
+                    Ok(())
+                }
+                OPCode::Swap => {
+                    let rhs = self.pop();
+                    let lhs = self.pop();
+
+                    if let (Some(a), Some(b)) = (lhs, rhs) {
+                        self.push(b);
+                        self.push(a);
+                        Ok(())
+                    } else {
+                        Err(RuntimeError::new(
+                            RuntimeErrorKind::MissingOperands(inst.mnemonic),
+                        ))
+                    }
+                }
+                OPCode::INeg | OPCode::LNeg | OPCode::FNeg | OPCode::DNeg => {
+                    if let Some(value) = self.pop() {
+                        self.push(Value::neg(&value));
                         Ok(())
                     } else {
-                        Err(RuntimeError {
-                            kind: RuntimeErrorKind::InvalidValue,
-                        })
+                        Err(RuntimeError::new(RuntimeErrorKind::InvalidValue))
                     }
                 }
                 OPCode::IInc => {
                     if let Some(params) = &inst.operands {
                         if params.len() < 2 {
-                            Err(RuntimeError {
-                                kind: RuntimeErrorKind::MissingOperands(
+                            Err(RuntimeError::new(
+                                RuntimeErrorKind::MissingOperands(
                                     inst.mnemonic,
                                 ),
-                            })
+                            ))
                         } else {
                             match (params[0], params[1]) {
                                 (Value::Int(index), Value::Int(constant)) => {
@@ -752,170 +1731,166 @@ impl Runtime {
                                         .or_insert(Value::Int(constant));
                                     Ok(())
                                 }
-                                _ => Err(RuntimeError {
-                                    kind: RuntimeErrorKind::InvalidOperandType(
+                                _ => Err(RuntimeError::new(
+                                    RuntimeErrorKind::InvalidOperandType(
                                         inst.mnemonic,
                                     ),
-                                }),
+                                )),
                             }
                         }
                     } else {
-                        Err(RuntimeError {
-                            kind: RuntimeErrorKind::MissingOperands(
-                                inst.mnemonic,
-                            ),
-                        })
+                        Err(RuntimeError::new(
+                            RuntimeErrorKind::MissingOperands(inst.mnemonic),
+                        ))
                     }
                 }
                 // Type conversion operations.
                 OPCode::L2I | OPCode::F2I | OPCode::D2I => {
-                    let val = self.pop();
-                    self.push(val.expect("expected value").to_int());
+                    let val = self.pop_primitive(inst.mnemonic)?;
+                    self.push(val.to_int());
                     Ok(())
                 }
                 OPCode::I2F | OPCode::L2F | OPCode::D2F => {
-                    let val = self.pop();
-                    self.push(val.expect("expected value").to_float());
+                    let val = self.pop_primitive(inst.mnemonic)?;
+                    self.push(val.to_float());
                     Ok(())
                 }
                 OPCode::I2D | OPCode::L2D | OPCode::F2D => {
-                    let val = self.pop();
-                    self.push(val.expect("expected value").to_double());
+                    let val = self.pop_primitive(inst.mnemonic)?;
+                    self.push(val.to_double());
                     Ok(())
                 }
                 OPCode::I2L | OPCode::F2L | OPCode::D2L => {
-                    let val = self.pop();
-                    self.push(val.expect("expected value").to_long());
+                    let val = self.pop_primitive(inst.mnemonic)?;
+                    self.push(val.to_long());
+                    Ok(())
+                }
+                OPCode::I2B | OPCode::I2C | OPCode::I2S => {
+                    let val = Value::Int(self.pop_int(inst.mnemonic)?);
+                    self.push(match inst.mnemonic {
+                        OPCode::I2B => val.to_byte(),
+                        OPCode::I2C => val.to_char(),
+                        _ => val.to_short(),
+                    });
                     Ok(())
                 }
                 // Comparison operations.
-                OPCode::LCmp
-                | OPCode::FCmpL
+                OPCode::LCmp => {
+                    // `value2` is on top of the stack and is compared to
+                    // `value1` right below it.
+                    let rhs = self.pop_long(inst.mnemonic)?;
+                    let lhs = self.pop_long(inst.mnemonic)?;
+                    self.push(Value::Int(Value::compare(
+                        &Value::Long(lhs),
+                        &Value::Long(rhs),
+                    )));
+                    Ok(())
+                }
+                OPCode::FCmpL
                 | OPCode::FCmpG
                 | OPCode::DCmpL
                 | OPCode::DCmpG => {
+                    // `value2` is on top of the stack and is compared to
+                    // `value1` right below it.
                     let rhs = self.pop();
                     let lhs = self.pop();
 
                     if let (Some(a), Some(b)) = (lhs, rhs) {
-                        self.push(Value::Int(Value::compare(&a, &b)));
+                        let compare = match inst.mnemonic {
+                            OPCode::FCmpG => Value::fcmpg,
+                            OPCode::FCmpL => Value::fcmpl,
+                            OPCode::DCmpG => Value::dcmpg,
+                            OPCode::DCmpL => Value::dcmpl,
+                            _ => Value::compare,
+                        };
+                        self.push(Value::Int(compare(&a, &b)));
                         Ok(())
                     } else {
-                        Err(RuntimeError {
-                            kind: RuntimeErrorKind::InvalidValue,
-                        })
+                        Err(RuntimeError::new(RuntimeErrorKind::InvalidValue))
                     }
                 }
                 // Control flow operations.
-                OPCode::IfEq => {
-                    let Some(Value::Int(value)) = self.pop() else {
-                        panic!("expected value to be integer")
+                OPCode::IfNull | OPCode::IfNonNull => {
+                    let reference = match self.pop() {
+                        Some(Value::Reference(reference)) => reference,
+                        Some(_) => {
+                            return Err(RuntimeError::new(
+                                RuntimeErrorKind::InvalidOperandType(
+                                    inst.mnemonic,
+                                ),
+                            ))
+                        }
+                        None => {
+                            return Err(RuntimeError::new(
+                                RuntimeErrorKind::MissingOperands(
+                                    inst.mnemonic,
+                                ),
+                            ))
+                        }
                     };
 
-                    let relative_offset = inst.operands.as_ref().map_or_else(
-                        || {
-                            panic!(
-                             "Expected instruction to have parameters got None"
-                         )
-                        },
-                        |params| Self::get_relative_offset(params),
-                    );
+                    let relative_offset = Self::get_relative_offset(inst)?;
+                    if reference.is_none() == (inst.mnemonic == OPCode::IfNull)
+                    {
+                        self.jump(relative_offset)?;
+                    }
+                    Ok(())
+                }
+                OPCode::IfEq => {
+                    let value = self.pop_int(inst.mnemonic)?;
+
+                    let relative_offset = Self::get_relative_offset(inst)?;
                     if value == 0 {
-                        self.jump(relative_offset);
+                        self.jump(relative_offset)?;
                     }
                     Ok(())
                 }
                 OPCode::IfNe => {
-                    let Some(Value::Int(value)) = self.pop() else {
-                        panic!("expected value to be integer")
-                    };
+                    let value = self.pop_int(inst.mnemonic)?;
 
-                    let relative_offset = inst.operands.as_ref().map_or_else(
-                        || {
-                            panic!(
-                             "Expected instruction to have parameters got None"
-                         )
-                        },
-                        |params| Self::get_relative_offset(params),
-                    );
+                    let relative_offset = Self::get_relative_offset(inst)?;
                     if value != 0 {
-                        self.jump(relative_offset)
+                        self.jump(relative_offset)?;
                     }
                     Ok(())
                 }
                 OPCode::IfLt => {
-                    let Some(Value::Int(value)) = self.pop() else {
-                        panic!("expected value to be integer")
-                    };
+                    let value = self.pop_int(inst.mnemonic)?;
 
-                    let relative_offset = inst.operands.as_ref().map_or_else(
-                        || {
-                            panic!(
-                             "Expected instruction to have parameters got None"
-                         )
-                        },
-                        |params| Self::get_relative_offset(params),
-                    );
+                    let relative_offset = Self::get_relative_offset(inst)?;
 
                     if value < 0 {
-                        self.jump(relative_offset)
+                        self.jump(relative_offset)?;
                     }
                     Ok(())
                 }
                 OPCode::IfGt => {
-                    let Some(Value::Int(value)) = self.pop() else {
-                        panic!("expected value to be integer")
-                    };
+                    let value = self.pop_int(inst.mnemonic)?;
 
-                    let relative_offset = inst.operands.as_ref().map_or_else(
-                        || {
-                            panic!(
-                             "Expected instruction to have parameters got None"
-                         )
-                        },
-                        |params| Self::get_relative_offset(params),
-                    );
+                    let relative_offset = Self::get_relative_offset(inst)?;
 
                     if value > 0 {
-                        self.jump(relative_offset)
+                        self.jump(relative_offset)?;
                     }
                     Ok(())
                 }
                 OPCode::IfLe => {
-                    let Some(Value::Int(value)) = self.pop() else {
-                        panic!("expected value to be integer");
-                    };
+                    let value = self.pop_int(inst.mnemonic)?;
 
-                    let relative_offset = inst.operands.as_ref().map_or_else(
-                        || {
-                            panic!(
-                             "Expected instruction to have parameters got None"
-                         )
-                        },
-                        |params| Self::get_relative_offset(params),
-                    );
+                    let relative_offset = Self::get_relative_offset(inst)?;
 
                     if value <= 0 {
-                        self.jump(relative_offset)
+                        self.jump(relative_offset)?;
                     }
                     Ok(())
                 }
                 OPCode::IfGe => {
-                    let Some(Value::Int(value)) = self.pop() else {
-                        panic!("expected value to be integer");
-                    };
+                    let value = self.pop_int(inst.mnemonic)?;
 
-                    let relative_offset = inst.operands.as_ref().map_or_else(
-                        || {
-                            panic!(
-                             "Expected instruction to have parameters got None"
-                         )
-                        },
-                        |params| Self::get_relative_offset(params),
-                    );
+                    let relative_offset = Self::get_relative_offset(inst)?;
 
                     if value >= 0 {
-                        self.jump(relative_offset)
+                        self.jump(relative_offset)?;
                     }
                     Ok(())
                 }
@@ -923,175 +1898,150 @@ impl Runtime {
                     let rhs = self.pop();
                     let lhs = self.pop();
 
-                    let relative_offset = inst.operands.as_ref().map_or_else(
-                        || {
-                            panic!(
-                             "Expected instruction to have parameters got None"
-                         )
-                        },
-                        |params| Self::get_relative_offset(params),
-                    );
+                    let relative_offset = Self::get_relative_offset(inst)?;
 
                     if let (Some(a), Some(b)) = (lhs, rhs) {
                         if a == b {
-                            self.jump(relative_offset)
+                            self.jump(relative_offset)?;
                         }
                         Ok(())
                     } else {
-                        Err(RuntimeError {
-                            kind: RuntimeErrorKind::InvalidValue,
-                        })
+                        Err(RuntimeError::new(RuntimeErrorKind::InvalidValue))
                     }
                 }
                 OPCode::IfICmpNe => {
                     let rhs = self.pop();
                     let lhs = self.pop();
 
-                    let relative_offset = inst.operands.as_ref().map_or_else(
-                        || {
-                            panic!(
-                             "Expected instruction to have parameters got None"
-                         )
-                        },
-                        |params| Self::get_relative_offset(params),
-                    );
+                    let relative_offset = Self::get_relative_offset(inst)?;
 
                     if let (Some(a), Some(b)) = (lhs, rhs) {
                         if a != b {
-                            self.jump(relative_offset)
+                            self.jump(relative_offset)?;
                         }
                         Ok(())
                     } else {
-                        Err(RuntimeError {
-                            kind: RuntimeErrorKind::InvalidValue,
-                        })
+                        Err(RuntimeError::new(RuntimeErrorKind::InvalidValue))
                     }
                 }
                 OPCode::IfICmpLt => {
                     let rhs = self.pop();
                     let lhs = self.pop();
 
-                    let relative_offset = inst.operands.as_ref().map_or_else(
-                        || {
-                            panic!(
-                             "Expected instruction to have parameters got None"
-                         )
-                        },
-                        |params| Self::get_relative_offset(params),
-                    );
+                    let relative_offset = Self::get_relative_offset(inst)?;
 
                     if let (Some(a), Some(b)) = (lhs, rhs) {
                         if a < b {
-                            self.jump(relative_offset)
+                            self.jump(relative_offset)?;
                         }
                         Ok(())
                     } else {
-                        Err(RuntimeError {
-                            kind: RuntimeErrorKind::InvalidValue,
-                        })
+                        Err(RuntimeError::new(RuntimeErrorKind::InvalidValue))
                     }
                 }
                 OPCode::IfICmpGt => {
                     let rhs = self.pop();
                     let lhs = self.pop();
 
-                    let relative_offset = inst.operands.as_ref().map_or_else(
-                        || {
-                            panic!(
-                             "Expected instruction to have parameters got None"
-                         )
-                        },
-                        |params| Self::get_relative_offset(params),
-                    );
+                    let relative_offset = Self::get_relative_offset(inst)?;
 
                     if let (Some(a), Some(b)) = (lhs, rhs) {
                         if a > b {
-                            self.jump(relative_offset)
+                            self.jump(relative_offset)?;
                         }
                         Ok(())
                     } else {
-                        Err(RuntimeError {
-                            kind: RuntimeErrorKind::InvalidValue,
-                        })
+                        Err(RuntimeError::new(RuntimeErrorKind::InvalidValue))
                     }
                 }
                 OPCode::IfICmpLe => {
                     let rhs = self.pop();
                     let lhs = self.pop();
 
-                    let relative_offset = inst.operands.as_ref().map_or_else(
-                        || {
-                            panic!(
-                             "Expected instruction to have parameters got None"
-                         )
-                        },
-                        |params| Self::get_relative_offset(params),
-                    );
+                    let relative_offset = Self::get_relative_offset(inst)?;
 
                     if let (Some(a), Some(b)) = (lhs, rhs) {
                         if a <= b {
-                            self.jump(relative_offset)
+                            self.jump(relative_offset)?;
                         }
                         Ok(())
                     } else {
-                        Err(RuntimeError {
-                            kind: RuntimeErrorKind::InvalidValue,
-                        })
+                        Err(RuntimeError::new(RuntimeErrorKind::InvalidValue))
                     }
                 }
                 OPCode::IfICmpGe => {
                     let rhs = self.pop();
                     let lhs = self.pop();
 
-                    let relative_offset = inst.operands.as_ref().map_or_else(
-                        || {
-                            panic!(
-                             "Expected instruction to have parameters got None"
-                         )
-                        },
-                        |params| Self::get_relative_offset(params),
-                    );
+                    let relative_offset = Self::get_relative_offset(inst)?;
 
                     if let (Some(a), Some(b)) = (lhs, rhs) {
                         if a >= b {
-                            self.jump(relative_offset)
+                            self.jump(relative_offset)?;
                         }
                         Ok(())
                     } else {
-                        Err(RuntimeError {
-                            kind: RuntimeErrorKind::InvalidValue,
-                        })
+                        Err(RuntimeError::new(RuntimeErrorKind::InvalidValue))
                     }
                 }
                 // Goto
                 OPCode::Goto => {
-                    let relative_offset = inst.operands.as_ref().map_or_else(
-                        || {
-                            panic!(
-                             "Expected instruction to have parameters got None"
-                         )
-                        },
-                        |params| Self::get_relative_offset(params),
-                    );
+                    let relative_offset = Self::get_relative_offset(inst)?;
 
-                    self.jump(relative_offset);
+                    self.jump(relative_offset)?;
                     Ok(())
                 }
+                OPCode::GotoW => match inst.nth(0) {
+                    // The offset is relative to the `goto_w` opcode which is
+                    // followed by 4 bytes of operands.
+                    Some(Value::Int(offset)) => {
+                        self.jump(offset - 5)?;
+                        Ok(())
+                    }
+                    Some(_) => Err(RuntimeError::new(
+                        RuntimeErrorKind::InvalidOperandType(inst.mnemonic),
+                    )),
+                    None => Err(RuntimeError::new(
+                        RuntimeErrorKind::MissingOperands(inst.mnemonic),
+                    )),
+                },
+                OPCode::TableSwitch | OPCode::LookupSwitch => {
+                    let Some(table) = inst.switch_table() else {
+                        return Err(RuntimeError::new(
+                            RuntimeErrorKind::MissingOperands(inst.mnemonic),
+                        ));
+                    };
+                    match self.pop() {
+                        Some(Value::Int(key)) => {
+                            let offset = table.offset(key);
+                            if let Some(frame) = self.frames.last_mut() {
+                                frame.pc.instruction_index = frame.opcode_index;
+                            }
+                            self.jump(offset)
+                        }
+                        _ => Err(RuntimeError::new(
+                            RuntimeErrorKind::InvalidValue,
+                        )),
+                    }
+                }
                 // Return with value.
                 OPCode::IReturn
                 | OPCode::LReturn
                 | OPCode::FReturn
                 | OPCode::DReturn => {
                     if let Some(mut frame) = self.frames.pop() {
-                        let value = frame.stack.pop().unwrap();
+                        let value =
+                            frame.stack.pop().ok_or(RuntimeError::new(
+                                RuntimeErrorKind::MissingOperands(
+                                    inst.mnemonic,
+                                ),
+                            ))?;
                         // This is for debugging purposes.
                         self.return_values.push(value);
                         self.push(value);
                         Ok(())
                     } else {
-                        Err(RuntimeError {
-                            kind: RuntimeErrorKind::InvalidValue,
-                        })
+                        Err(RuntimeError::new(RuntimeErrorKind::InvalidValue))
                     }
                 }
                 // Void return
@@ -1099,31 +2049,342 @@ impl Runtime {
                     self.frames.pop();
                     Ok(())
                 }
+                // Exceptions.
+                OPCode::AThrow => {
+                    let object = self.pop_object(inst.mnemonic)?;
+                    self.throw(object)
+                }
                 // Function calls.
                 OPCode::InvokeStatic => {
-                    let name_index = match &inst.operands {
-                        Some(params) => match params.get(0) {
-                            Some(Value::Int(index)) => index,
-                            _ => panic!(
-                                "InvokeStatic expected integer parameter"
+                    let method_index = match inst.nth(0) {
+                        Some(Value::Int(index)) => index as usize,
+                        Some(_) => {
+                            return Err(RuntimeError::new(
+                                RuntimeErrorKind::InvalidOperandType(
+                                    inst.mnemonic,
+                                ),
+                            ))
+                        }
+                        None => {
+                            return Err(RuntimeError::new(
+                                RuntimeErrorKind::MissingOperands(
+                                    inst.mnemonic,
+                                ),
+                            ))
+                        }
+                    };
+                    let class_name = self
+                        .program
+                        .method_class(method_index)
+                        .map(str::to_string);
+                    if let Some(class_name) = class_name {
+                        if self.initialize_class(&class_name)? {
+                            return Ok(());
+                        }
+                    }
+                    self.invoke(method_index, false, inst.mnemonic)
+                }
+                // Constructors and superclass methods of the loaded classes
+                // are invoked on the receiver below their arguments, the
+                // method of the referenced class is called whatever the type
+                // of the receiver.
+                OPCode::InvokeSpecial => {
+                    let method_ref = self.constant_pool_index(inst)?;
+                    self.load_method_class(method_ref)?;
+                    match self.program.method_ref(method_ref) {
+                        // Constructors of the JDK classes, which aren't loaded,
+                        // only consume their receiver and arguments.
+                        Some((class, "<init>", _))
+                            if class.starts_with("java/")
+                                && !self.program.has_class(class) =>
+                        {
+                            for _ in 0..=self.program.arg_count(method_ref) {
+                                self.pop().ok_or(RuntimeError::new(
+                                    RuntimeErrorKind::MissingOperands(
+                                        inst.mnemonic,
+                                    ),
+                                ))?;
+                            }
+                            Ok(())
+                        }
+                        Some((class, _, _))
+                            if self.program.has_class(class) =>
+                        {
+                            match self.program.find_method(method_ref) {
+                                Some(method_index) => self.invoke(
+                                    method_index,
+                                    true,
+                                    inst.mnemonic,
+                                ),
+                                None => Err(RuntimeError::new(
+                                    RuntimeErrorKind::UnsupportedInstruction(
+                                        inst.mnemonic,
+                                    ),
+                                )),
+                            }
+                        }
+                        _ => Err(RuntimeError::new(
+                            RuntimeErrorKind::UnsupportedInstruction(
+                                inst.mnemonic,
                             ),
-                        },
-                        _ => panic!("InvokeStatic expected parameters"),
+                        )),
+                    }
+                }
+                // Bootstrap methods aren't executed, the call sites they
+                // would link are special cased instead.
+                OPCode::InvokeDynamic => {
+                    let call_site_index = match inst.operands.as_deref() {
+                        Some([Value::Int(index)]) => *index as usize,
+                        _ => {
+                            return Err(RuntimeError::new(
+                                RuntimeErrorKind::MissingOperands(
+                                    inst.mnemonic,
+                                ),
+                            ))
+                        }
                     };
-                    self.invoke(*name_index as usize);
-                    Ok(())
+                    match self.program.resolve_call_site(call_site_index) {
+                        // Lambdas are represented by the index of the
+                        // method implementing them.
+                        Some(CallSite::Lambda { method_index }) => {
+                            self.push(Value::Int(method_index as i32));
+                            Ok(())
+                        }
+                        // Strings aren't supported yet, the concatenation
+                        // only consumes its operands and pushes a null
+                        // reference.
+                        Some(CallSite::StringConcat { arg_count }) => {
+                            for _ in 0..arg_count {
+                                self.pop();
+                            }
+                            self.push(Value::Reference(None));
+                            Ok(())
+                        }
+                        None => Err(RuntimeError::new(
+                            RuntimeErrorKind::InvalidOperandType(inst.mnemonic),
+                        )),
+                    }
+                }
+                // Currently only supports calling lambdas through their
+                // functional interface.
+                OPCode::InvokeInterface => {
+                    let arg_count = match inst.operands.as_deref() {
+                        Some([_, Value::Int(arg_count)]) => *arg_count as usize,
+                        _ => {
+                            return Err(RuntimeError::new(
+                                RuntimeErrorKind::MissingOperands(
+                                    inst.mnemonic,
+                                ),
+                            ))
+                        }
+                    };
+                    // The receiver sits below the arguments.
+                    let receiver = self.frames.last_mut().and_then(|frame| {
+                        let depth =
+                            frame.stack.len().checked_sub(arg_count + 1)?;
+                        Some(frame.stack.remove(depth))
+                    });
+                    match receiver {
+                        Some(Value::Int(method_index)) => self.invoke(
+                            method_index as usize,
+                            false,
+                            inst.mnemonic,
+                        ),
+                        _ => Err(RuntimeError::new(
+                            RuntimeErrorKind::InvalidValue,
+                        )),
+                    }
                 }
                 // Currently only supports System.out.println.
                 OPCode::InvokeVirtual => {
-                    let value = self.pop();
-                    println!("System.out.println : {value:?}");
+                    let method_ref = self.constant_pool_index(inst)?;
+                    match self.program.method_ref(method_ref) {
+                        Some((
+                            "java/io/PrintStream",
+                            "println",
+                            descriptor,
+                        )) => {
+                            let descriptor = descriptor.to_string();
+                            self.println(&descriptor)
+                        }
+                        _ => Err(RuntimeError::new(
+                            RuntimeErrorKind::UnsupportedInstruction(
+                                inst.mnemonic,
+                            ),
+                        )),
+                    }
+                }
+                OPCode::GetStatic => {
+                    let index = self.constant_pool_index(inst)?;
+                    let Some(class) = self
+                        .program
+                        .field_ref(index)
+                        .map(|(class, ..)| class.to_string())
+                    else {
+                        return Err(RuntimeError::new(
+                            RuntimeErrorKind::InvalidOperandType(inst.mnemonic),
+                        ));
+                    };
+                    if self.initialize_class(&class)? {
+                        return Ok(());
+                    }
+                    let Some((_, name, descriptor)) =
+                        self.program.field_ref(index)
+                    else {
+                        return Err(RuntimeError::new(
+                            RuntimeErrorKind::InvalidOperandType(inst.mnemonic),
+                        ));
+                    };
+                    // Fields that were never stored have their default value.
+                    let value = self
+                        .static_fields
+                        .get(&(class, name.to_string()))
+                        .copied()
+                        .unwrap_or_else(|| Value::default_for(descriptor));
+                    self.push(value);
+                    Ok(())
+                }
+                OPCode::PutStatic => {
+                    let index = self.constant_pool_index(inst)?;
+                    let Some(key) = self.program.field_ref(index).map(
+                        |(class, name, _)| {
+                            (class.to_string(), name.to_string())
+                        },
+                    ) else {
+                        return Err(RuntimeError::new(
+                            RuntimeErrorKind::InvalidOperandType(inst.mnemonic),
+                        ));
+                    };
+                    if self.initialize_class(&key.0)? {
+                        return Ok(());
+                    }
+                    let Some(value) = self.pop() else {
+                        return Err(RuntimeError::new(
+                            RuntimeErrorKind::MissingOperands(inst.mnemonic),
+                        ));
+                    };
+                    self.static_fields.insert(key, value);
+                    Ok(())
+                }
+                // Object operations.
+                OPCode::New => {
+                    let class_name = match inst.nth(0) {
+                        Some(Value::Int(index)) => {
+                            self.program.class_name(index as usize)
+                        }
+                        _ => None,
+                    };
+                    self.heap.push(Object {
+                        class_name: class_name.unwrap_or_default().to_string(),
+                        fields: HashMap::new(),
+                    });
+                    self.push(Value::Reference(Some(self.heap.len() - 1)));
+                    Ok(())
+                }
+                OPCode::GetField => {
+                    let index = self.constant_pool_index(inst)?;
+                    let Some((_, name, descriptor)) =
+                        self.program.field_ref(index)
+                    else {
+                        return Err(RuntimeError::new(
+                            RuntimeErrorKind::InvalidOperandType(inst.mnemonic),
+                        ));
+                    };
+                    let (name, default) =
+                        (name.to_string(), Value::default_for(descriptor));
+                    let object = self.pop_object(inst.mnemonic)?;
+                    // Fields that were never stored have their default value.
+                    let value = self.heap[object]
+                        .fields
+                        .get(&name)
+                        .copied()
+                        .unwrap_or(default);
+                    self.push(value);
+                    Ok(())
+                }
+                OPCode::PutField => {
+                    let index = self.constant_pool_index(inst)?;
+                    let Some((_, name, _)) = self.program.field_ref(index)
+                    else {
+                        return Err(RuntimeError::new(
+                            RuntimeErrorKind::InvalidOperandType(inst.mnemonic),
+                        ));
+                    };
+                    let name = name.to_string();
+                    let Some(value) = self.pop() else {
+                        return Err(RuntimeError::new(
+                            RuntimeErrorKind::MissingOperands(inst.mnemonic),
+                        ));
+                    };
+                    let object = self.pop_object(inst.mnemonic)?;
+                    self.heap[object].fields.insert(name, value);
+                    Ok(())
+                }
+                // Array operations.
+                OPCode::NewArray => {
+                    // The operand is the primitive type code of the array
+                    // elements, `T_FLOAT`, `T_DOUBLE` and `T_LONG` aren't
+                    // integers.
+                    let default = match inst.nth(0) {
+                        Some(Value::Int(6)) => Value::Float(0.),
+                        Some(Value::Int(7)) => Value::Double(0.),
+                        Some(Value::Int(11)) => Value::Long(0),
+                        Some(Value::Int(4 | 5 | 8 | 9 | 10)) => Value::Int(0),
+                        _ => {
+                            return Err(RuntimeError::new(
+                                RuntimeErrorKind::InvalidOperandType(
+                                    inst.mnemonic,
+                                ),
+                            ))
+                        }
+                    };
+                    let length = self.pop_int(inst.mnemonic)?;
+                    let Ok(length) = usize::try_from(length) else {
+                        return Err(RuntimeError::new(
+                            RuntimeErrorKind::InvalidOperandType(inst.mnemonic),
+                        ));
+                    };
+                    self.arrays.push(vec![default; length]);
+                    self.push(Value::Reference(Some(self.arrays.len() - 1)));
                     Ok(())
                 }
-                OPCode::GetStatic | OPCode::Nop | OPCode::Dup => Ok(()),
+                OPCode::ArrayLength => {
+                    let array = self.pop_array(inst.mnemonic)?;
+                    let length = self.arrays[array].len();
+                    self.push(Value::Int(length as i32));
+                    Ok(())
+                }
+                OPCode::IALoad => {
+                    let index = self.pop_int(inst.mnemonic)?;
+                    let array = self.pop_array(inst.mnemonic)?;
+                    let slot = self.array_slot(array, index)?;
+                    self.push(self.arrays[array][slot]);
+                    Ok(())
+                }
+                OPCode::IAStore => {
+                    let value = self.pop_int(inst.mnemonic)?;
+                    let index = self.pop_int(inst.mnemonic)?;
+                    let array = self.pop_array(inst.mnemonic)?;
+                    let slot = self.array_slot(array, index)?;
+                    self.arrays[array][slot] = Value::Int(value);
+                    Ok(())
+                }
+                OPCode::Dup => match self.pop() {
+                    Some(value) => {
+                        self.push(value);
+                        self.push(value);
+                        Ok(())
+                    }
+                    None => Err(RuntimeError::new(
+                        RuntimeErrorKind::MissingOperands(inst.mnemonic),
+                    )),
+                },
+                OPCode::Nop => Ok(()),
                 _ => todo!(),
             }
         } else {
-            Ok(println!("Reached last frame...leaving"))
+            println!("Reached last frame...leaving");
+            Ok(())
         }
     }
 
@@ -1134,167 +2395,553 @@ impl Runtime {
         arg as i32
     }
 
-    /// Returns the next bytecode value in the current method.
-    fn next(&mut self, frame: &mut Frame) -> u8 {
+    /// Returns the next bytecode value in the current method, fails if the
+    /// method has no code at the frame program counter.
+    fn next(&mut self, frame: &mut Frame) -> Result<u8, RuntimeError> {
         let method_index = frame.method_index();
-        let code = self.program.code(method_index);
-        let bc = code[frame.instruction_index()];
-        frame.max_locals = self.program.max_locals(method_index);
+        let missing_code =
+            RuntimeError::new(RuntimeErrorKind::MissingCode(frame.pc));
+        let bc = self
+            .program
+            .code(method_index)
+            .and_then(|code| code.get(frame.instruction_index()))
+            .copied()
+            .ok_or(missing_code.clone())?;
+        frame.max_locals =
+            self.program.max_locals(method_index).ok_or(missing_code)?;
         frame.inc_instruction_index();
-        bc
+        Ok(bc)
+    }
+
+    /// Returns the next 4 bytes in the bytecode as a big endian integer.
+    fn next_i32(&mut self, frame: &mut Frame) -> Result<i32, RuntimeError> {
+        let bytes = [
+            self.next(frame)?,
+            self.next(frame)?,
+            self.next(frame)?,
+            self.next(frame)?,
+        ];
+        Ok(i32::from_be_bytes(bytes))
+    }
+
+    /// Returns the relative offset from the branch instruction operands.
+    fn get_relative_offset(inst: &Instruction) -> Result<i32, RuntimeError> {
+        match inst.nth(0) {
+            Some(Value::Int(v)) => Ok(v - 3),
+            Some(_) => Err(RuntimeError::new(
+                RuntimeErrorKind::InvalidOperandType(inst.mnemonic),
+            )),
+            None => Err(RuntimeError::new(RuntimeErrorKind::MissingOperands(
+                inst.mnemonic,
+            ))),
+        }
+    }
+
+    /// Throw the exception `object`, frames are popped until one of them has
+    /// a handler catching it at its current instruction. The handler starts
+    /// with an operand stack holding only the exception.
+    ///
+    /// Returns `RuntimeErrorKind::UncaughtException` once all the frames
+    /// are popped.
+    fn throw(&mut self, object: usize) -> Result<(), RuntimeError> {
+        let class_name = self.heap[object].class_name.clone();
+        while let Some(frame) = self.frames.last_mut() {
+            if let Some(handler_pc) = self.program.exception_handler(
+                frame.method_index(),
+                frame.opcode_index,
+                &class_name,
+            ) {
+                frame.stack.clear();
+                frame.stack.push(Value::Reference(Some(object)));
+                frame.pc.instruction_index = handler_pc;
+                return Ok(());
+            }
+            self.frames.pop();
+        }
+        Err(RuntimeError::new(RuntimeErrorKind::UncaughtException(
+            class_name,
+        )))
+    }
+
+    /// Load the class declaring the method referenced at `method_ref` from
+    /// the class path if it isn't loaded yet, see `Runtime::load_class`.
+    fn load_method_class(
+        &mut self,
+        method_ref: usize,
+    ) -> Result<(), RuntimeError> {
+        let Some((class_name, ..)) = self.program.method_ref(method_ref) else {
+            return Ok(());
+        };
+        let class_name = class_name.to_string();
+        self.load_class(&class_name)
     }
 
-    /// Returns the relative offset from the mnemonics parameters list.
-    fn get_relative_offset(params: &[Value]) -> i32 {
-        match params.get(0) {
-            Some(Value::Int(v)) => v - 3,
-            _ => panic!("Expected parameter to be of type Value::Int"),
+    /// Load the class with the binary name `class_name` from the class path
+    /// if it isn't loaded yet, classes missing from the class path are
+    /// ignored.
+    fn load_class(&mut self, class_name: &str) -> Result<(), RuntimeError> {
+        let Some(class_path) = &self.class_path else {
+            return Ok(());
+        };
+        if self.program.has_class(class_name)
+            || self.missing_classes.contains(class_name)
+        {
+            return Ok(());
+        }
+        match self.program.load_from_class_path(class_path, class_name) {
+            Ok(true) => Ok(()),
+            Ok(false) => {
+                self.missing_classes.insert(class_name.to_string());
+                Ok(())
+            }
+            Err(_) => Err(RuntimeError::new(RuntimeErrorKind::ClassLoading(
+                class_name.to_string(),
+            ))),
+        }
+    }
+
+    /// Initialize the class with the binary name `class_name` the first time
+    /// it is used by running its static initializer (`<clinit>`), returns
+    /// whether the initializer was invoked.
+    ///
+    /// The instruction using the class is executed again once the
+    /// initializer returns. Classes are marked initialized before their
+    /// initializer runs so it can use their static members.
+    fn initialize_class(
+        &mut self,
+        class_name: &str,
+    ) -> Result<bool, RuntimeError> {
+        if self.initialized_classes.contains(class_name) {
+            return Ok(false);
+        }
+        self.load_class(class_name)?;
+        self.initialized_classes.insert(class_name.to_string());
+        let Some(clinit) = self.program.class_initializer(class_name) else {
+            return Ok(false);
+        };
+        if let Some(frame) = self.frames.last_mut() {
+            frame.pc.instruction_index = frame.opcode_index;
         }
+        // Static initializers take no arguments.
+        self.invoke(clinit, false, OPCode::InvokeStatic)?;
+        Ok(true)
     }
 
     /// Invoke a function by creating a new stack frame, building the locals
     /// and pushing the new frame into the runtime stack.
-    fn invoke(&mut self, method_name_index: usize) {
-        let method = &self.program.methods[method_name_index];
+    ///
+    /// Returns `RuntimeErrorKind::StackOverflow` if the runtime stack already
+    /// holds `RuntimeConfig::max_frames` frames and
+    /// `RuntimeErrorKind::MissingOperands` if the stack doesn't hold the
+    /// arguments of the method invoked by `opcode`.
+    fn invoke(
+        &mut self,
+        method_index: usize,
+        has_receiver: bool,
+        opcode: OPCode,
+    ) -> Result<(), RuntimeError> {
+        if self.frames.len() >= self.config.max_frames {
+            return Err(RuntimeError::new(RuntimeErrorKind::StackOverflow));
+        }
+        let Some(method) = self.program.methods.get(method_index) else {
+            return Err(RuntimeError::new(RuntimeErrorKind::MissingCode(
+                ProgramCounter::new(method_index, 0),
+            )));
+        };
         let max_locals = method.max_locals;
         let stack = vec![];
         let mut locals = HashMap::new();
         let arg_types = method.arg_types.clone();
+        // Instance methods receive the object they are invoked on in the
+        // first local.
         let mut key = arg_types.iter().map(|arg_type| arg_type.size()).sum();
+        key += usize::from(has_receiver);
 
+        let missing_operands =
+            RuntimeError::new(RuntimeErrorKind::MissingOperands(opcode));
         for arg_type in arg_types.iter().rev() {
             key -= arg_type.size();
-            let val = self.pop().unwrap();
+            let val = self.pop().ok_or(missing_operands.clone())?;
             locals.insert(key, val);
         }
-        assert_eq!(key, 0);
+        if has_receiver {
+            locals.insert(0, self.pop().ok_or(missing_operands)?);
+        }
         let pc = ProgramCounter {
             instruction_index: 0,
-            method_index: method_name_index,
+            method_index,
         };
         let frame = Frame {
             pc,
             stack,
             locals,
             max_locals,
+            opcode_index: 0,
         };
         self.frames.push(frame);
+        self.profiler.enter_method();
+        self.profiler.count_method_call(method_index);
+        Ok(())
+    }
+
+    /// Returns the program constant pool index encoded in the two operands
+    /// of a field or method instruction of the current method.
+    fn constant_pool_index(
+        &self,
+        inst: &Instruction,
+    ) -> Result<usize, RuntimeError> {
+        match (inst.nth(0), inst.nth(1), self.frames.last()) {
+            (Some(Value::Int(hi)), Some(Value::Int(lo)), Some(frame)) => {
+                let offset = self
+                    .program
+                    .constant_pool_offset(frame.method_index())
+                    .ok_or(RuntimeError::new(RuntimeErrorKind::MissingCode(
+                        frame.pc,
+                    )))?;
+                Ok((hi << 8 | lo) as usize + offset)
+            }
+            _ => Err(RuntimeError::new(RuntimeErrorKind::MissingOperands(
+                inst.mnemonic,
+            ))),
+        }
+    }
+
+    /// Print the argument of a `System.out.println` overload with the given
+    /// `descriptor` and pop the `System.out` receiver, `String` arguments
+    /// aren't supported yet.
+    fn println(&mut self, descriptor: &str) -> Result<(), RuntimeError> {
+        let missing_operands = || {
+            RuntimeError::new(RuntimeErrorKind::MissingOperands(
+                OPCode::InvokeVirtual,
+            ))
+        };
+        if descriptor == "()V" {
+            self.pop().ok_or_else(missing_operands)?;
+            println!("System.out.println :");
+            return Ok(());
+        }
+        let value = self.pop().ok_or_else(missing_operands)?;
+        self.pop().ok_or_else(missing_operands)?;
+        let text = match (descriptor, value) {
+            ("(I)V", Value::Int(_))
+            | ("(J)V", Value::Long(_))
+            | ("(F)V", Value::Float(_))
+            | ("(D)V", Value::Double(_)) => value.to_string(),
+            ("(Z)V", Value::Int(v)) => (v != 0).to_string(),
+            ("(C)V", Value::Int(v)) => char::from_u32(v as u32)
+                .unwrap_or(char::REPLACEMENT_CHARACTER)
+                .to_string(),
+            ("(I)V" | "(J)V" | "(F)V" | "(D)V" | "(Z)V" | "(C)V", _) => {
+                return Err(RuntimeError::new(
+                    RuntimeErrorKind::InvalidOperandType(OPCode::InvokeVirtual),
+                ))
+            }
+            _ => {
+                return Err(RuntimeError::new(
+                    RuntimeErrorKind::UnsupportedInstruction(
+                        OPCode::InvokeVirtual,
+                    ),
+                ))
+            }
+        };
+        println!("System.out.println : {text}");
+        Ok(())
     }
 
     /// Returns the next instruction to execute.
-    fn fetch(&mut self) -> Instruction {
+    fn fetch(&mut self) -> Result<Instruction, RuntimeError> {
         // Ugly hack, since we can't borrow frame as mutable more than once
         // we pop it out, do what we want then push it back.
-        let current_frame = self.frames.pop();
-        match current_frame {
-            Some(mut frame) => {
-                let mnemonic = OPCode::from(self.next(&mut frame));
-                let params = match mnemonic {
-                    OPCode::SiPush
-                    | OPCode::IfEq
-                    | OPCode::IfNe
-                    | OPCode::IfLt
-                    | OPCode::IfLe
-                    | OPCode::IfGt
-                    | OPCode::IfGe
-                    | OPCode::IfICmpEq
-                    | OPCode::IfICmpNe
-                    | OPCode::IfICmpLt
-                    | OPCode::IfICmpLe
-                    | OPCode::IfICmpGt
-                    | OPCode::IfICmpGe
-                    | OPCode::Goto => {
-                        let lo = self.next(&mut frame);
-                        let hi = self.next(&mut frame);
-                        let param = Self::encode_arg(lo, hi);
-                        Some(vec![Value::Int(param)])
-                    }
-                    OPCode::InvokeSpecial
-                    | OPCode::GetStatic
-                    | OPCode::InvokeVirtual
-                    | OPCode::IInc => {
-                        let first = i32::from(self.next(&mut frame));
-                        let second = i32::from(self.next(&mut frame));
-                        Some(vec![Value::Int(first), Value::Int(second)])
-                    }
-                    OPCode::BiPush
-                    | OPCode::ILoad
-                    | OPCode::FLoad
-                    | OPCode::LLoad
-                    | OPCode::DLoad
-                    | OPCode::IStore
-                    | OPCode::FStore
-                    | OPCode::LStore
-                    | OPCode::DStore => {
-                        let arg = i32::from(self.next(&mut frame));
-                        Some(vec![Value::Int(arg)])
-                    }
-                    OPCode::InvokeStatic => {
-                        let lo = self.next(&mut frame);
-                        let hi = self.next(&mut frame);
-                        let method_ref_index =
-                            Self::encode_arg(lo, hi) as usize;
-                        let method_name_index =
-                            self.program.find_method(method_ref_index);
-                        Some(vec![Value::Int(method_name_index)])
-                    }
-                    OPCode::Ldc2W => {
-                        let lo = self.next(&mut frame);
-                        let hi = self.next(&mut frame);
-                        let index = Self::encode_arg(lo, hi);
-                        let entry = &self.program.constant_pool[index as usize];
-
-                        match entry {
-                            CPInfo::ConstantDouble { hi_bytes, lo_bytes } => {
-                                let result = ((*hi_bytes as i64) << 32)
-                                    + (*lo_bytes as i64);
-                                Some(vec![Value::Double(result as f64)])
-                            }
-                            CPInfo::ConstantLong { hi_bytes, lo_bytes } => {
-                                let result = ((*hi_bytes as i64) << 32)
-                                    + (*lo_bytes as i64);
-                                Some(vec![Value::Long(result)])
-                            }
-                            _ => panic!("unexpected entry in constant pool"),
-                        }
-                    }
-                    OPCode::Ldc => {
-                        let index = self.next(&mut frame);
-                        let entry = &self.program.constant_pool[index as usize];
+        let Some(mut frame) = self.frames.pop() else {
+            panic!("no next instruction")
+        };
+        let inst = self.decode(&mut frame);
+        self.frames.push(frame);
+        inst
+    }
 
-                        match entry {
-                            CPInfo::ConstantFloat { bytes } => {
-                                Some(vec![Value::Float(*bytes as f32)])
-                            }
-                            CPInfo::ConstantInteger { bytes } => {
-                                Some(vec![Value::Int(*bytes as i32)])
-                            }
-                            _ => panic!("unexpected entry in constant pool"),
-                        }
+    /// Decode the instruction following a `wide` prefix, its local variable
+    /// index (and the `iinc` constant) are 16 bits wide.
+    fn decode_wide(
+        &mut self,
+        frame: &mut Frame,
+    ) -> Result<Instruction, RuntimeError> {
+        let mnemonic = OPCode::from(self.next(frame)?);
+        let index = u16::from_be_bytes([self.next(frame)?, self.next(frame)?]);
+        let params = match mnemonic {
+            OPCode::ILoad
+            | OPCode::FLoad
+            | OPCode::LLoad
+            | OPCode::DLoad
+            | OPCode::ALoad
+            | OPCode::IStore
+            | OPCode::FStore
+            | OPCode::LStore
+            | OPCode::DStore
+            | OPCode::AStore
+            | OPCode::Ret => vec![Value::Int(i32::from(index))],
+            OPCode::IInc => {
+                let constant =
+                    i16::from_be_bytes([self.next(frame)?, self.next(frame)?]);
+                vec![
+                    Value::Int(i32::from(index)),
+                    Value::Int(i32::from(constant)),
+                ]
+            }
+            _ => {
+                return Err(RuntimeError::new(
+                    RuntimeErrorKind::InvalidOperandType(OPCode::Wide),
+                ))
+            }
+        };
+        Ok(Instruction {
+            mnemonic,
+            operands: Some(params),
+        })
+    }
+
+    /// Decode the instruction at the program counter of `frame`.
+    fn decode(
+        &mut self,
+        frame: &mut Frame,
+    ) -> Result<Instruction, RuntimeError> {
+        // Constant pool indexes are relative to the constant pool of
+        // the class declaring the current method.
+        let offset = self
+            .program
+            .constant_pool_offset(frame.method_index())
+            .ok_or(RuntimeError::new(RuntimeErrorKind::MissingCode(
+                frame.pc,
+            )))?;
+        frame.opcode_index = frame.instruction_index();
+        let mnemonic = OPCode::from(self.next(frame)?);
+        if mnemonic == OPCode::Wide {
+            return self.decode_wide(frame);
+        }
+        let params = match mnemonic {
+            OPCode::SiPush
+            | OPCode::IfEq
+            | OPCode::IfNe
+            | OPCode::IfLt
+            | OPCode::IfLe
+            | OPCode::IfGt
+            | OPCode::IfGe
+            | OPCode::IfICmpEq
+            | OPCode::IfICmpNe
+            | OPCode::IfICmpLt
+            | OPCode::IfICmpLe
+            | OPCode::IfICmpGt
+            | OPCode::IfICmpGe
+            | OPCode::IfNull
+            | OPCode::IfNonNull
+            | OPCode::Goto => {
+                let lo = self.next(frame)?;
+                let hi = self.next(frame)?;
+                let param = Self::encode_arg(lo, hi);
+                Some(vec![Value::Int(param)])
+            }
+            OPCode::GotoW => Some(vec![Value::Int(self.next_i32(frame)?)]),
+            OPCode::TableSwitch | OPCode::LookupSwitch => {
+                // Operands start at the next multiple of 4 from the start
+                // of the method code.
+                while !frame.instruction_index().is_multiple_of(4) {
+                    self.next(frame)?;
+                }
+                // `tableswitch` : `[default, low, high, offsets...]` and
+                // `lookupswitch` : `[default, npairs, (match, offset)...]`.
+                let mut params = vec![
+                    Value::Int(self.next_i32(frame)?),
+                    Value::Int(self.next_i32(frame)?),
+                ];
+                let count = match (mnemonic, params[1]) {
+                    (OPCode::TableSwitch, Value::Int(low)) => {
+                        let high = self.next_i32(frame)?;
+                        params.push(Value::Int(high));
+                        i64::from(high) - i64::from(low) + 1
                     }
-                    _ => None,
+                    (_, Value::Int(npairs)) => 2 * i64::from(npairs),
+                    _ => unreachable!(),
                 };
-                self.frames.push(frame);
+                for _ in 0..count.max(0) {
+                    params.push(Value::Int(self.next_i32(frame)?));
+                }
+                Some(params)
+            }
+            OPCode::InvokeSpecial
+            | OPCode::GetStatic
+            | OPCode::PutStatic
+            | OPCode::GetField
+            | OPCode::PutField
+            | OPCode::InvokeVirtual => {
+                let first = i32::from(self.next(frame)?);
+                let second = i32::from(self.next(frame)?);
+                Some(vec![Value::Int(first), Value::Int(second)])
+            }
+            OPCode::IInc => {
+                // The local variable index is unsigned and the constant is
+                // a signed byte.
+                let index = i32::from(self.next(frame)?);
+                let constant = i32::from(self.next(frame)? as i8);
+                Some(vec![Value::Int(index), Value::Int(constant)])
+            }
+            // The pushed byte is signed.
+            OPCode::BiPush => {
+                let arg = i32::from(self.next(frame)? as i8);
+                Some(vec![Value::Int(arg)])
+            }
+            OPCode::NewArray
+            | OPCode::ILoad
+            | OPCode::FLoad
+            | OPCode::LLoad
+            | OPCode::DLoad
+            | OPCode::ALoad
+            | OPCode::IStore
+            | OPCode::FStore
+            | OPCode::LStore
+            | OPCode::DStore
+            | OPCode::AStore
+            | OPCode::Ret => {
+                let arg = i32::from(self.next(frame)?);
+                Some(vec![Value::Int(arg)])
+            }
+            OPCode::InvokeStatic => {
+                let lo = self.next(frame)?;
+                let hi = self.next(frame)?;
+                let method_ref_index =
+                    Self::encode_arg(lo, hi) as usize + offset;
+                self.load_method_class(method_ref_index)?;
+                // Methods of the classes that aren't loaded can't be
+                // invoked.
+                let method_index = self
+                    .program
+                    .find_method(method_ref_index)
+                    .ok_or(RuntimeError::new(
+                    RuntimeErrorKind::UnsupportedInstruction(mnemonic),
+                ))?;
+                Some(vec![Value::Int(method_index as i32)])
+            }
+            OPCode::New => {
+                let index =
+                    u16::from_be_bytes([self.next(frame)?, self.next(frame)?]);
+                Some(vec![Value::Int((usize::from(index) + offset) as i32)])
+            }
+            OPCode::InvokeDynamic => {
+                let lo = self.next(frame)?;
+                let hi = self.next(frame)?;
+                // The last two bytes are always zero.
+                self.next(frame)?;
+                self.next(frame)?;
+                let call_site_index =
+                    Self::encode_arg(lo, hi) as usize + offset;
+                Some(vec![Value::Int(call_site_index as i32)])
+            }
+            OPCode::InvokeInterface => {
+                let lo = self.next(frame)?;
+                let hi = self.next(frame)?;
+                // The count byte is redundant with the descriptor
+                // and the last byte is always zero.
+                self.next(frame)?;
+                self.next(frame)?;
+                let method_ref_index =
+                    Self::encode_arg(lo, hi) as usize + offset;
+                let arg_count = self.program.arg_count(method_ref_index);
+                Some(vec![
+                    Value::Int(method_ref_index as i32),
+                    Value::Int(arg_count as i32),
+                ])
+            }
+            OPCode::Ldc2W => {
+                let lo = self.next(frame)?;
+                let hi = self.next(frame)?;
+                let index = Self::encode_arg(lo, hi) as usize + offset;
+                let entry = &self.program.constant_pool[index];
 
-                Instruction {
-                    mnemonic,
-                    operands: params,
+                match entry {
+                    CPInfo::ConstantDouble { hi_bytes, lo_bytes } => {
+                        let result =
+                            ((*hi_bytes as i64) << 32) + (*lo_bytes as i64);
+                        Some(vec![Value::Double(result as f64)])
+                    }
+                    CPInfo::ConstantLong { hi_bytes, lo_bytes } => {
+                        let result =
+                            ((*hi_bytes as i64) << 32) + (*lo_bytes as i64);
+                        Some(vec![Value::Long(result)])
+                    }
+                    _ => panic!("unexpected entry in constant pool"),
                 }
             }
-            None => panic!("no next instruction"),
-        }
-    }
-}
+            OPCode::Ldc => {
+                let index = self.next(frame)? as usize + offset;
+                let entry = &self.program.constant_pool[index];
+
+                match entry {
+                    CPInfo::ConstantFloat { bytes } => {
+                        Some(vec![Value::Float(*bytes as f32)])
+                    }
+                    CPInfo::ConstantInteger { bytes } => {
+                        Some(vec![Value::Int(*bytes as i32)])
+                    }
+                    _ => panic!("unexpected entry in constant pool"),
+                }
+            }
+            _ => None,
+        };
+
+        // Catch malformed or undecoded bytecode before evaluation.
+        let operand_count = params.as_ref().map_or(0, Vec::len);
+        if mnemonic
+            .expected_operand_count()
+            .is_some_and(|expected| expected != operand_count)
+        {
+            return Err(RuntimeError::new(RuntimeErrorKind::InvalidValue));
+        }
+
+        Ok(Instruction {
+            mnemonic,
+            operands: params,
+        })
+    }
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::jvm::read_class_file;
-    use crate::jvm::JVMParser;
+    use crate::jvm::{ClassPath, JVMParser};
     use crate::program::Program;
     use std::env;
     use std::path::Path;
 
+    #[test]
+    fn values_are_displayed_like_java() {
+        let cases = [
+            (Value::Int(-42), "-42"),
+            (Value::Long(i64::MAX), "9223372036854775807"),
+            (Value::Float(3.25), "3.25"),
+            (Value::Float(1.), "1.0"),
+            (Value::Double(-0.5), "-0.5"),
+            (Value::Double(f64::NAN), "NaN"),
+            (Value::Float(f32::NEG_INFINITY), "-Infinity"),
+            (Value::Double(f64::INFINITY), "Infinity"),
+            (Value::Reference(None), "null"),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(value.to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn instructions_are_displayed_with_their_operands() {
+        let iinc = Instruction::new(
+            OPCode::IInc,
+            Some(vec![Value::Int(1), Value::Int(-3)]),
+        );
+        assert_eq!(iinc.to_string(), "iinc 1 -3");
+        assert_eq!(Instruction::new(OPCode::IAdd, None).to_string(), "iadd");
+        let ldc = Instruction::new(OPCode::Ldc, Some(vec![Value::Float(2.)]));
+        assert_eq!(ldc.to_string(), "ldc 2.0");
+    }
+
     #[test]
     fn can_handle_values() {
         let values = vec![
@@ -1304,10 +2951,10 @@ mod tests {
             Value::Double(1.),
         ];
 
-        // Tag is 1 byte since we are using `repr[C,u8]` the largest primitive
-        // type supported is float which is 8 bytes, accounting for alignment
-        // we end up with 16 bytes (9 bytes aren't aligned nearest is 16).
-        assert_eq!(std::mem::size_of::<Value>(), 16);
+        // Tag is 1 byte since we are using `repr[C,u8]` the largest variant
+        // is a reference which is 16 bytes (`Option<usize>` has no niche),
+        // accounting for alignment we end up with 24 bytes.
+        assert_eq!(std::mem::size_of::<Value>(), 24);
         unsafe {
             for val in values {
                 let mem = std::slice::from_raw_parts(
@@ -1319,25 +2966,26 @@ mod tests {
         }
     }
 
+    /// Returns a runtime for the class file at `class`, relative to the
+    /// crate root.
+    fn runtime_for(class: &str, config: RuntimeConfig) -> Runtime {
+        let env_var = env::var("CARGO_MANIFEST_DIR").unwrap();
+        let path = Path::new(&env_var).join(class);
+        let class_file_bytes = read_class_file(&path).unwrap_or_else(|_| {
+            panic!("Failed to parse file : {:?}", path.as_os_str())
+        });
+        let class_file = JVMParser::parse(&class_file_bytes).unwrap();
+        Runtime::new(Program::new(&class_file), config)
+    }
+
     // Macro to generate unit tests for the runtime.
     macro_rules! test_runtime_case {
         ($name: ident, $test_files:expr, $expected:expr) => {
             #[test]
             fn $name() {
                 for test_file in $test_files {
-                    let env_var = env::var("CARGO_MANIFEST_DIR").unwrap();
-                    let path = Path::new(&env_var).join(test_file);
-                    let class_file_bytes = read_class_file(&path)
-                        .unwrap_or_else(|_| {
-                            panic!(
-                                "Failed to parse file : {:?}",
-                                path.as_os_str()
-                            )
-                        });
-                    let class_file = JVMParser::parse(&class_file_bytes);
-                    assert!(class_file.is_ok());
-                    let program = Program::new(&class_file.unwrap());
-                    let mut runtime = Runtime::new(program);
+                    let mut runtime =
+                        runtime_for(test_file, RuntimeConfig::default());
                     assert!(runtime.run(false).is_ok());
                     assert_eq!(runtime.top_return_value(), $expected);
                 }
@@ -1370,15 +3018,1494 @@ mod tests {
         Some(Value::Int(500))
     );
 
+    // `add(int, int)`, `add(long, long)` and `add(int, int, int)` are
+    // distinct methods.
+    test_runtime_case!(
+        overloaded_methods,
+        ["support/tests/Overload.class"],
+        Some(Value::Int(16))
+    );
+
     test_runtime_case!(
         loops,
         ["support/tests/Loop.class"],
         Some(Value::Int(1000))
     );
 
+    test_runtime_case!(
+        wide_goto,
+        ["support/tests/GotoW.class"],
+        Some(Value::Int(1000))
+    );
+
     test_runtime_case!(
         loop_with_function_call,
         ["support/tests/MultiFuncCall.class"],
         Some(Value::Int(50))
     );
+
+    test_runtime_case!(
+        lambdas,
+        ["support/tests/Lambda.class"],
+        Some(Value::Int(42))
+    );
+
+    test_runtime_case!(
+        switch_statements,
+        ["support/tests/Switch.class"],
+        Some(Value::Int(40))
+    );
+
+    test_runtime_case!(
+        discarded_results,
+        ["support/tests/StackOps.class"],
+        Some(Value::Int(7))
+    );
+
+    test_runtime_case!(
+        bitwise_operations,
+        ["support/tests/Bitwise.class"],
+        Some(Value::Int(85))
+    );
+
+    test_runtime_case!(
+        increments_wrap_and_sign_extend,
+        ["support/tests/Increment.class"],
+        Some(Value::Int(7))
+    );
+
+    test_runtime_case!(
+        null_checks,
+        ["support/tests/NullCheck.class"],
+        Some(Value::Int(1))
+    );
+
+    test_runtime_case!(
+        object_fields,
+        ["support/tests/Point.class"],
+        Some(Value::Int(31))
+    );
+
+    test_runtime_case!(
+        int_arrays,
+        ["support/tests/ArraySum.class"],
+        Some(Value::Int(285))
+    );
+
+    test_runtime_case!(
+        wide_local_indexes,
+        ["support/tests/WideLocals.class"],
+        Some(Value::Int(1515))
+    );
+
+    #[test]
+    fn callers_resume_after_the_call_instruction() {
+        let mut runtime = runtime_for(
+            "support/tests/FuncCall.class",
+            RuntimeConfig::default(),
+        );
+        let main = runtime.frames[0].method_index();
+        let mut executed = Vec::new();
+        while let Some(frame) = runtime.frames.last() {
+            let pc = frame.pc;
+            let inst = runtime.fetch().unwrap();
+            executed.push((pc.get_instruction_index(), inst.mnemonic));
+            assert!(runtime.eval(&inst).is_ok());
+            // The callee's `ireturn` hands control back to `main` right
+            // after the 3 bytes `invokestatic`.
+            if inst.mnemonic == OPCode::IReturn && !runtime.frames.is_empty() {
+                assert_eq!(
+                    runtime.frames.last().unwrap().pc.method_index,
+                    main
+                );
+                assert_eq!(
+                    runtime.frames.last().unwrap().pc.get_instruction_index(),
+                    13
+                );
+            }
+        }
+        assert_eq!(
+            executed,
+            vec![
+                (0, OPCode::SiPush),
+                (3, OPCode::IStore1),
+                (4, OPCode::SiPush),
+                (7, OPCode::IStore2),
+                (8, OPCode::ILoad1),
+                (9, OPCode::ILoad2),
+                (10, OPCode::InvokeStatic),
+                (0, OPCode::ILoad0),
+                (1, OPCode::ILoad1),
+                (2, OPCode::IAdd),
+                (3, OPCode::IReturn),
+                (13, OPCode::IReturn),
+            ]
+        );
+        assert_eq!(runtime.top_return_value(), Some(Value::Int(500)));
+    }
+
+    #[test]
+    fn can_invoke_methods_from_loaded_classes() {
+        let env_var = env::var("CARGO_MANIFEST_DIR").unwrap();
+        let root = Path::new(&env_var);
+        let class_file_bytes =
+            read_class_file(&root.join("support/tests/CrossClass.class"))
+                .unwrap();
+        let class_file = JVMParser::parse(&class_file_bytes).unwrap();
+        let mut program = Program::new(&class_file);
+        assert!(program
+            .load_class(&root.join("support/tests/MathUtils.class"))
+            .is_ok());
+        let mut runtime = Runtime::new(program, RuntimeConfig::default());
+        assert!(runtime.run(false).is_ok());
+        assert_eq!(runtime.top_return_value(), Some(Value::Int(55)));
+    }
+
+    #[test]
+    fn methods_are_dispatched_to_the_class_they_are_called_on() {
+        let env_var = env::var("CARGO_MANIFEST_DIR").unwrap();
+        let root = Path::new(&env_var);
+        let class_file_bytes =
+            read_class_file(&root.join("support/tests/Dispatch.class"))
+                .unwrap();
+        let class_file = JVMParser::parse(&class_file_bytes).unwrap();
+        let mut program = Program::new(&class_file);
+        assert!(program
+            .load_class(&root.join("support/tests/DispatchHelper.class"))
+            .is_ok());
+        let mut runtime = Runtime::new(program, RuntimeConfig::default());
+        assert!(runtime.run(false).is_ok());
+        // `Dispatch.twice(3) + DispatchHelper.twice(4)`.
+        assert_eq!(runtime.top_return_value(), Some(Value::Int(46)));
+    }
+
+    #[test]
+    fn invokespecial_calls_the_method_of_the_referenced_class() {
+        let env_var = env::var("CARGO_MANIFEST_DIR").unwrap();
+        let root = Path::new(&env_var);
+        let class_file_bytes =
+            read_class_file(&root.join("support/tests/Derived.class")).unwrap();
+        let class_file = JVMParser::parse(&class_file_bytes).unwrap();
+        let mut runtime =
+            Runtime::new(Program::new(&class_file), RuntimeConfig::default())
+                .with_class_path(ClassPath::new(vec![
+                    root.join("support/tests")
+                ]));
+        assert!(runtime.run(false).is_ok());
+        // `Base` initializes `value` to 7 and `Derived` calls `super.base()`
+        // from its no-arg constructor, not its own override.
+        assert_eq!(runtime.top_return_value(), Some(Value::Int(21)));
+    }
+
+    test_runtime_case!(
+        thrown_exceptions_are_caught_by_callers,
+        ["support/tests/TryCatch.class"],
+        Some(Value::Int(42))
+    );
+
+    // Unwinds three frames into a `RuntimeException` handler, then runs the
+    // `finally` block.
+    test_runtime_case!(
+        exceptions_unwind_to_matching_handlers,
+        ["support/tests/Unwind.class"],
+        Some(Value::Int(15))
+    );
+
+    #[test]
+    fn uncaught_exceptions_fail_the_program() {
+        let mut runtime = runtime_for(
+            "support/tests/Uncaught.class",
+            RuntimeConfig::default(),
+        );
+        let err = runtime.run(false).unwrap_err();
+        assert_eq!(
+            err.kind(),
+            &RuntimeErrorKind::UncaughtException(
+                "java/lang/UnsupportedOperationException".to_string()
+            )
+        );
+        assert!(runtime.frames.is_empty());
+    }
+
+    #[test]
+    fn static_initializers_run_once_before_first_use() {
+        let env_var = env::var("CARGO_MANIFEST_DIR").unwrap();
+        let root = Path::new(&env_var);
+        let class_file_bytes =
+            read_class_file(&root.join("support/tests/StaticInit.class"))
+                .unwrap();
+        let class_file = JVMParser::parse(&class_file_bytes).unwrap();
+        let mut runtime =
+            Runtime::new(Program::new(&class_file), RuntimeConfig::default())
+                .with_class_path(ClassPath::new(vec![
+                    root.join("support/tests")
+                ]));
+        assert!(runtime.run(false).is_ok());
+        // `base` is set by the main class initializer, `StaticConfig` is
+        // initialized by its first `getstatic` and `loads` counts how many
+        // times its initializer ran.
+        assert_eq!(runtime.top_return_value(), Some(Value::Int(162)));
+    }
+
+    #[test]
+    fn classes_are_loaded_from_the_class_path_when_invoked() {
+        let env_var = env::var("CARGO_MANIFEST_DIR").unwrap();
+        let root = Path::new(&env_var);
+        let class_file_bytes =
+            read_class_file(&root.join("support/tests/Dispatch.class"))
+                .unwrap();
+        let class_file = JVMParser::parse(&class_file_bytes).unwrap();
+        let mut runtime =
+            Runtime::new(Program::new(&class_file), RuntimeConfig::default())
+                .with_class_path(ClassPath::new(vec![
+                    root.join("support/tests")
+                ]));
+        assert!(!runtime.program.has_class("DispatchHelper"));
+        assert!(runtime.run(false).is_ok());
+        assert!(runtime.program.has_class("DispatchHelper"));
+        assert_eq!(runtime.top_return_value(), Some(Value::Int(46)));
+    }
+
+    #[test]
+    fn can_load_classes_from_the_class_path() {
+        let env_var = env::var("CARGO_MANIFEST_DIR").unwrap();
+        let root = Path::new(&env_var);
+        let class_file_bytes =
+            read_class_file(&root.join("support/tests/CrossClass.class"))
+                .unwrap();
+        let class_file = JVMParser::parse(&class_file_bytes).unwrap();
+        for class_path in [
+            ClassPath::new(vec![root.join("support/tests")]),
+            ClassPath::new(vec![
+                root.join("support/jit"),
+                root.join("support/jar/CrossClass.jar"),
+            ]),
+        ] {
+            let mut program = Program::new(&class_file);
+            assert!(program.resolve_classes(&class_path).is_ok());
+            let mut runtime = Runtime::new(program, RuntimeConfig::default());
+            assert!(runtime.run(false).is_ok());
+            assert_eq!(runtime.top_return_value(), Some(Value::Int(55)));
+        }
+    }
+
+    #[test]
+    fn can_run_program_from_jar() {
+        let env_var = env::var("CARGO_MANIFEST_DIR").unwrap();
+        let path = Path::new(&env_var).join("support/jar/CrossClass.jar");
+        let program = Program::from_jar(&path).unwrap();
+        let mut runtime = Runtime::new(program, RuntimeConfig::default());
+        assert!(runtime.run(false).is_ok());
+        assert_eq!(runtime.top_return_value(), Some(Value::Int(55)));
+    }
+
+    #[test]
+    fn can_convert_values_from_and_to_primitives() {
+        assert_eq!(i32::try_from(Value::Int(42)), Ok(42));
+        assert_eq!(i64::try_from(Value::Long(-7)), Ok(-7));
+        assert_eq!(f32::try_from(Value::Float(1.5)), Ok(1.5));
+        assert_eq!(f64::try_from(Value::Double(2.5)), Ok(2.5));
+        assert_eq!(
+            i32::try_from(Value::Long(42)),
+            Err(RuntimeError::new(RuntimeErrorKind::InvalidValue))
+        );
+        assert!(f64::try_from(Value::Float(1.5)).is_err());
+        assert_eq!(Value::from(42), Value::Int(42));
+        assert_eq!(Value::from(42i64), Value::Long(42));
+        assert_eq!(Value::from(1.5f32), Value::Float(1.5));
+        assert_eq!(Value::from(2.5), Value::Double(2.5));
+    }
+
+    #[test]
+    fn can_use_named_value_constructors_and_accessors() {
+        assert_eq!(Value::from_i32(1), Value::Int(1));
+        assert_eq!(Value::from_i64(2), Value::Long(2));
+        assert_eq!(Value::from_f32(3.), Value::Float(3.));
+        assert_eq!(Value::from_f64(4.), Value::Double(4.));
+        assert_eq!(Value::Int(1).as_i32(), Some(1));
+        assert_eq!(Value::Long(2).as_i64(), Some(2));
+        assert_eq!(Value::Float(3.).as_f32(), Some(3.));
+        assert_eq!(Value::Double(4.).as_f64(), Some(4.));
+        assert_eq!(Value::Int(1).as_i64(), None);
+        assert_eq!(Value::Double(4.).as_f32(), None);
+    }
+
+    #[test]
+    fn instructions_compare_structurally() {
+        let nan = Instruction::new(
+            OPCode::Ldc2W,
+            Some(vec![Value::Double(f64::NAN)]),
+        );
+        assert_ne!(Value::Double(f64::NAN), Value::Double(f64::NAN));
+        assert_eq!(nan, nan.clone());
+        assert_ne!(
+            Instruction::new(OPCode::Ldc, Some(vec![Value::Int(1)])),
+            Instruction::new(OPCode::Ldc, Some(vec![Value::Float(1.)]))
+        );
+        assert_ne!(
+            Instruction::new(OPCode::Return, None),
+            Instruction::new(OPCode::Return, Some(vec![]))
+        );
+        let instructions = std::collections::HashSet::from([
+            nan.clone(),
+            nan,
+            Instruction::new(OPCode::IAdd, None),
+            Instruction::new(OPCode::IAdd, None),
+        ]);
+        assert_eq!(instructions.len(), 2);
+    }
+
+    #[test]
+    #[cfg(not(feature = "accurate_profiling"))]
+    fn side_exits_become_hot() {
+        let mut runtime = runtime_for(
+            "support/tests/SideExit.class",
+            RuntimeConfig {
+                jit_threshold: 1,
+                ..RuntimeConfig::default()
+            },
+        );
+        assert!(runtime.run(true).is_ok());
+        assert_eq!(runtime.top_return_value(), Some(Value::Int(275)));
+        // The inner loop is compiled and exits to the outer loop body on
+        // every iteration of the outer loop.
+        let inner_loop = *runtime.traces.keys().next().unwrap();
+        assert_eq!(inner_loop.get_instruction_index(), 11);
+        let side_exit = ProgramCounter::new(inner_loop.get_method_index(), 27);
+        assert!(runtime.profiler.is_hot(&side_exit));
+    }
+
+    #[test]
+    fn can_evict_traces() {
+        let mut runtime = runtime_for(
+            "support/jit/Loop10.class",
+            RuntimeConfig {
+                jit_threshold: 1,
+                ..RuntimeConfig::default()
+            },
+        );
+        assert!(runtime.run(true).is_ok());
+        let pc = *runtime.traces.keys().next().unwrap();
+        assert!(runtime.jit_cache.has_native_trace(pc));
+        assert!(runtime.recorder.is_compiled(&pc));
+
+        runtime.evict_trace(pc);
+        assert!(!runtime.jit_cache.has_native_trace(pc));
+        assert!(!runtime.traces.contains_key(&pc));
+        assert!(!runtime.recorder.is_compiled(&pc));
+    }
+
+    #[test]
+    fn can_step_through_programs() {
+        let config = RuntimeConfig {
+            jit_enabled: false,
+            ..RuntimeConfig::default()
+        };
+        let mut runtime = runtime_for("support/tests/Loop.class", config);
+        let entry = runtime.current_pc().unwrap();
+        assert_eq!(entry.get_instruction_index(), 0);
+        assert_eq!(runtime.step(), Ok(StepResult::Continue));
+        assert!(runtime.current_pc().unwrap().get_instruction_index() > 0);
+        assert_eq!(runtime.inspect_stack().len(), 1);
+        while runtime.step() == Ok(StepResult::Continue) {}
+        assert_eq!(runtime.current_pc(), None);
+        assert_eq!(runtime.step(), Ok(StepResult::Done));
+        let mut expected =
+            runtime_for("support/tests/Loop.class", RuntimeConfig::default());
+        assert!(expected.run(false).is_ok());
+        assert_eq!(runtime.top_return_value(), expected.top_return_value());
+    }
+
+    #[test]
+    fn locals_are_named_by_the_local_variable_table() {
+        let config = RuntimeConfig {
+            jit_enabled: false,
+            ..RuntimeConfig::default()
+        };
+        let mut runtime = runtime_for("support/tests/Locals.class", config);
+        assert_eq!(runtime.local_name(0, 0), Some("args"));
+        assert_eq!(runtime.local_name(0, 1), None);
+        while runtime.frames.len() < 2 {
+            assert_eq!(runtime.step(), Ok(StepResult::Continue));
+        }
+        assert_eq!(runtime.local_name(1, 0), Some("n"));
+        assert_eq!(runtime.local_name(1, 2), None);
+        while runtime.current_pc().unwrap().get_instruction_index() < 4 {
+            assert_eq!(runtime.step(), Ok(StepResult::Continue));
+        }
+        assert_eq!(runtime.local_name(1, 1), Some("total"));
+        assert_eq!(runtime.local_name(1, 2), Some("i"));
+        assert_eq!(runtime.local_name(2, 0), None);
+    }
+
+    #[test]
+    fn stepping_reports_native_trace_exits() {
+        let config = RuntimeConfig {
+            jit_threshold: 1,
+            ..RuntimeConfig::default()
+        };
+        let mut runtime = runtime_for("support/jit/Loop10.class", config);
+        let mut exits = vec![];
+        loop {
+            match runtime.step() {
+                Ok(StepResult::Continue) => {}
+                Ok(StepResult::JitTransferred { exit_pc }) => {
+                    assert_eq!(
+                        runtime.current_pc().unwrap().get_instruction_index(),
+                        exit_pc
+                    );
+                    exits.push(exit_pc);
+                }
+                Ok(StepResult::Done) => break,
+                Err(err) => panic!("{err}"),
+            }
+        }
+        assert_eq!(exits.len(), 1);
+    }
+
+    #[test]
+    fn disabled_jit_records_no_traces() {
+        let config = RuntimeConfig {
+            jit_threshold: 1,
+            jit_enabled: false,
+            ..RuntimeConfig::default()
+        };
+        let mut runtime = runtime_for("support/jit/Loop10.class", config);
+        assert!(runtime.run(true).is_ok());
+        assert!(runtime.traces.is_empty());
+    }
+
+    #[test]
+    fn bitwise_operations_are_evaluated() {
+        let mut runtime =
+            runtime_for("support/tests/Loop.class", RuntimeConfig::default());
+        let cases = [
+            (OPCode::Iand, Value::Int(12), Value::Int(10), Value::Int(8)),
+            (OPCode::IOr, Value::Int(12), Value::Int(10), Value::Int(14)),
+            (OPCode::IXor, Value::Int(12), Value::Int(10), Value::Int(6)),
+            (OPCode::IShl, Value::Int(12), Value::Int(2), Value::Int(48)),
+            (OPCode::IShr, Value::Int(-12), Value::Int(1), Value::Int(-6)),
+            (
+                OPCode::IUShr,
+                Value::Int(-12),
+                Value::Int(28),
+                Value::Int(15),
+            ),
+            (
+                OPCode::Land,
+                Value::Long(12),
+                Value::Long(10),
+                Value::Long(8),
+            ),
+            (
+                OPCode::LOr,
+                Value::Long(12),
+                Value::Long(10),
+                Value::Long(14),
+            ),
+            (
+                OPCode::LXor,
+                Value::Long(12),
+                Value::Long(10),
+                Value::Long(6),
+            ),
+            (
+                OPCode::LShl,
+                Value::Long(12),
+                Value::Int(40),
+                Value::Long(12 << 40),
+            ),
+            (
+                OPCode::LShr,
+                Value::Long(-12),
+                Value::Int(1),
+                Value::Long(-6),
+            ),
+            (
+                OPCode::LUShr,
+                Value::Long(-12),
+                Value::Int(60),
+                Value::Long(15),
+            ),
+        ];
+        for (opcode, value1, value2, expected) in cases {
+            runtime.push(value1);
+            runtime.push(value2);
+            assert!(runtime.eval(&Instruction::new(opcode, None)).is_ok());
+            assert_eq!(runtime.pop(), Some(expected), "{opcode}");
+        }
+    }
+
+    #[test]
+    fn switch_tables_match_keys_or_default() {
+        let table = Instruction::new(
+            OPCode::TableSwitch,
+            Some([40, 1, 3, 20, 28, 36].into_iter().map(Value::Int).collect()),
+        )
+        .switch_table()
+        .unwrap();
+        assert_eq!(table.targets, vec![(1, 20), (2, 28), (3, 36)]);
+        assert_eq!(table.offset(2), 28);
+        assert_eq!(table.offset(4), 40);
+        let lookup = Instruction::new(
+            OPCode::LookupSwitch,
+            Some(
+                [50, 2, 100, 60, 1000, 70]
+                    .into_iter()
+                    .map(Value::Int)
+                    .collect(),
+            ),
+        )
+        .switch_table()
+        .unwrap();
+        assert_eq!(lookup.targets, vec![(100, 60), (1000, 70)]);
+        assert_eq!(lookup.offset(1000), 70);
+        assert_eq!(lookup.offset(0), 50);
+        assert!(Instruction::new(OPCode::Goto, Some(vec![Value::Int(3)]))
+            .switch_table()
+            .is_none());
+    }
+
+    #[test]
+    fn stack_operations_are_evaluated() {
+        let mut runtime =
+            runtime_for("support/tests/Loop.class", RuntimeConfig::default());
+        let eval = |runtime: &mut Runtime, opcode| {
+            runtime.eval(&Instruction::new(opcode, None))
+        };
+        runtime.push(Value::Int(1));
+        runtime.push(Value::Int(2));
+        assert!(eval(&mut runtime, OPCode::Swap).is_ok());
+        assert_eq!(runtime.inspect_stack(), vec![Value::Int(2), Value::Int(1)]);
+        assert!(eval(&mut runtime, OPCode::Pop).is_ok());
+        assert_eq!(runtime.inspect_stack(), vec![Value::Int(2)]);
+        // `pop2` discards two category 1 values or a single long or double.
+        runtime.push(Value::Float(3.0));
+        runtime.push(Value::Long(4));
+        assert!(eval(&mut runtime, OPCode::Pop2).is_ok());
+        assert_eq!(
+            runtime.inspect_stack(),
+            vec![Value::Int(2), Value::Float(3.0)]
+        );
+        assert!(eval(&mut runtime, OPCode::Pop2).is_ok());
+        assert!(runtime.inspect_stack().is_empty());
+        assert_eq!(
+            eval(&mut runtime, OPCode::Pop),
+            Err(RuntimeError::new(RuntimeErrorKind::MissingOperands(
+                OPCode::Pop
+            )))
+        );
+    }
+
+    #[test]
+    fn duplication_forms_are_evaluated() {
+        let mut runtime =
+            runtime_for("support/tests/Loop.class", RuntimeConfig::default());
+        let (a, b, c, d) =
+            (Value::Int(1), Value::Int(2), Value::Int(3), Value::Int(4));
+        let (l, m) = (Value::Long(5), Value::Double(6.0));
+        let cases = [
+            (OPCode::DupX1, vec![a, b], vec![b, a, b]),
+            (OPCode::DupX2, vec![a, b, c], vec![c, a, b, c]),
+            (OPCode::DupX2, vec![l, a], vec![a, l, a]),
+            (OPCode::Dup2, vec![a, b], vec![a, b, a, b]),
+            (OPCode::Dup2, vec![l], vec![l, l]),
+            (OPCode::Dup2X1, vec![a, b, c], vec![b, c, a, b, c]),
+            (OPCode::Dup2X1, vec![a, l], vec![l, a, l]),
+            (OPCode::Dup2X2, vec![a, b, c, d], vec![c, d, a, b, c, d]),
+            (OPCode::Dup2X2, vec![a, b, l], vec![l, a, b, l]),
+            (OPCode::Dup2X2, vec![m, a, b], vec![a, b, m, a, b]),
+            (OPCode::Dup2X2, vec![m, l], vec![l, m, l]),
+        ];
+        for (opcode, stack, expected) in cases {
+            for value in stack {
+                runtime.push(value);
+            }
+            assert!(runtime.eval(&Instruction::new(opcode, None)).is_ok());
+            assert_eq!(runtime.inspect_stack(), expected, "{opcode}");
+            while runtime.pop().is_some() {}
+        }
+        // Two slot values can't be split.
+        runtime.push(Value::Int(1));
+        runtime.push(Value::Long(2));
+        assert_eq!(
+            runtime.eval(&Instruction::new(OPCode::DupX1, None)),
+            Err(RuntimeError::new(RuntimeErrorKind::InvalidOperandType(
+                OPCode::DupX1
+            )))
+        );
+    }
+
+    #[test]
+    fn integral_arithmetic_wraps_on_overflow() {
+        let (max, min) = (Value::Int(i32::MAX), Value::Int(i32::MIN));
+        assert_eq!(Value::mul(&max, &Value::Int(2)), Value::Int(-2));
+        assert_eq!(Value::sub(&min, &Value::Int(1)), max);
+        assert_eq!(Value::add(&max, &Value::Int(1)), min);
+        let (max, min) = (Value::Long(i64::MAX), Value::Long(i64::MIN));
+        assert_eq!(Value::mul(&max, &Value::Long(2)), Value::Long(-2));
+        assert_eq!(Value::sub(&min, &Value::Long(1)), max);
+        assert_eq!(Value::add(&max, &Value::Long(1)), min);
+    }
+
+    #[test]
+    fn negation_wraps_integral_values() {
+        assert_eq!(Value::neg(&Value::Int(42)), Value::Int(-42));
+        assert_eq!(Value::neg(&Value::Int(i32::MIN)), Value::Int(i32::MIN));
+        assert_eq!(Value::neg(&Value::Long(i64::MIN)), Value::Long(i64::MIN));
+        assert_eq!(Value::neg(&Value::Float(1.5)), Value::Float(-1.5));
+        assert_eq!(Value::neg(&Value::Double(-2.0)), Value::Double(2.0));
+    }
+
+    #[test]
+    fn shift_distances_are_masked() {
+        let cases = [
+            (Value::shl(&Value::Int(1), &Value::Int(33)), Value::Int(2)),
+            (Value::shl(&Value::Long(1), &Value::Int(65)), Value::Long(2)),
+            (
+                Value::shl(&Value::Long(1), &Value::Int(33)),
+                Value::Long(1 << 33),
+            ),
+            (
+                Value::shr(&Value::Int(-8), &Value::Int(-31)),
+                Value::Int(-4),
+            ),
+            (
+                Value::shr(&Value::Long(i64::MIN), &Value::Int(63)),
+                Value::Long(-1),
+            ),
+            (
+                Value::ushr(&Value::Int(-1), &Value::Int(60)),
+                Value::Int(0xf),
+            ),
+            (
+                Value::ushr(&Value::Long(-1), &Value::Int(60)),
+                Value::Long(0xf),
+            ),
+            (
+                Value::band(&Value::Long(0xff00), &Value::Long(0x0ff0)),
+                Value::Long(0x0f00),
+            ),
+            (
+                Value::bor(&Value::Long(0xff00), &Value::Long(0x0ff0)),
+                Value::Long(0xfff0),
+            ),
+            (
+                Value::bxor(&Value::Long(0xff00), &Value::Long(0x0ff0)),
+                Value::Long(0xf0f0),
+            ),
+        ];
+        for (result, expected) in cases {
+            assert_eq!(result, expected);
+        }
+    }
+
+    #[test]
+    fn integer_division_by_zero_fails() {
+        let division_by_zero =
+            RuntimeError::new(RuntimeErrorKind::DivisionByZero);
+        assert_eq!(
+            Value::div(&Value::Int(1), &Value::Int(0)),
+            Err(division_by_zero.clone())
+        );
+        assert_eq!(
+            Value::div(&Value::Long(1), &Value::Long(0)),
+            Err(division_by_zero.clone())
+        );
+        assert_eq!(
+            Value::div(&Value::Int(i32::MIN), &Value::Int(-1)),
+            Ok(Value::Int(i32::MIN))
+        );
+        assert_eq!(
+            Value::div(&Value::Float(1.), &Value::Float(0.)),
+            Ok(Value::Float(f32::INFINITY))
+        );
+        assert_eq!(
+            Value::div(&Value::Double(-1.), &Value::Double(0.)),
+            Ok(Value::Double(f64::NEG_INFINITY))
+        );
+        assert!(Value::div(&Value::Double(0.), &Value::Double(0.))
+            .is_ok_and(|value| value.is_nan()));
+
+        let mut runtime = runtime_for(
+            "support/tests/DivisionByZero.class",
+            RuntimeConfig::default(),
+        );
+        runtime.push(Value::Int(42));
+        runtime.push(Value::Int(0));
+        assert_eq!(
+            runtime.eval(&Instruction::new(OPCode::IDiv, None)),
+            Err(division_by_zero.clone())
+        );
+        let err = runtime.run(false).unwrap_err();
+        assert_eq!(err.kind(), division_by_zero.kind());
+        assert_eq!(err.line(), Some(3));
+        assert!(err.to_string().ends_with("at line 3"));
+    }
+
+    #[test]
+    fn deep_recursion_overflows_the_stack() {
+        let mut runtime = runtime_for(
+            "support/tests/Recursion.class",
+            RuntimeConfig::default(),
+        );
+        let err = runtime.run(false).unwrap_err();
+        assert_eq!(err.kind(), &RuntimeErrorKind::StackOverflow);
+        assert_eq!(runtime.frames.len(), DEFAULT_MAX_FRAMES);
+
+        // The same recursion fits within a larger limit.
+        let config = RuntimeConfig {
+            max_frames: 2048,
+            ..RuntimeConfig::default()
+        };
+        let mut runtime = runtime_for("support/tests/Recursion.class", config);
+        assert!(runtime.run(false).is_ok());
+        assert_eq!(runtime.top_return_value(), Some(Value::Int(1000)));
+    }
+
+    #[test]
+    fn runtime_errors_can_be_boxed() {
+        fn run(
+            runtime: &mut Runtime,
+        ) -> Result<(), Box<dyn std::error::Error>> {
+            runtime.run(false)?;
+            Ok(())
+        }
+
+        let mut runtime = runtime_for(
+            "support/tests/DivisionByZero.class",
+            RuntimeConfig::default(),
+        );
+        let err = run(&mut runtime).unwrap_err();
+        assert_eq!(err.to_string(), "Integer division by zero at line 3");
+        assert!(err.source().is_none());
+        assert_eq!(
+            err.downcast_ref::<RuntimeError>().map(RuntimeError::kind),
+            Some(&RuntimeErrorKind::DivisionByZero)
+        );
+    }
+
+    #[test]
+    fn float_remainders_truncate_the_quotient() {
+        let cases = [
+            (Value::Float(-7.), Value::Float(2.), Value::Float(-1.)),
+            // IEEE 754 `remainder` rounds 5 / 3 to 2 and returns -1.
+            (Value::Float(5.), Value::Float(3.), Value::Float(2.)),
+            (Value::Float(-5.), Value::Float(3.), Value::Float(-2.)),
+            (Value::Float(5.), Value::Float(-3.), Value::Float(2.)),
+            (Value::Double(5.5), Value::Double(2.), Value::Double(1.5)),
+            (Value::Double(-5.5), Value::Double(2.), Value::Double(-1.5)),
+            (Value::Double(1e20), Value::Double(3.), Value::Double(1.)),
+            (
+                Value::Double(3.),
+                Value::Double(f64::INFINITY),
+                Value::Double(3.),
+            ),
+        ];
+        for (lhs, rhs, expected) in cases {
+            assert_eq!(Value::rem(&lhs, &rhs), Ok(expected));
+        }
+        assert!(Value::rem(&Value::Float(1.), &Value::Float(0.))
+            .is_ok_and(|value| value.is_nan()));
+        assert!(
+            Value::rem(&Value::Double(f64::INFINITY), &Value::Double(2.))
+                .is_ok_and(|value| value.is_nan())
+        );
+    }
+
+    #[test]
+    fn integer_remainders_by_zero_fail() {
+        let division_by_zero =
+            RuntimeError::new(RuntimeErrorKind::DivisionByZero);
+        assert_eq!(
+            Value::rem(&Value::Int(1), &Value::Int(0)),
+            Err(division_by_zero.clone())
+        );
+        assert_eq!(
+            Value::rem(&Value::Long(1), &Value::Long(0)),
+            Err(division_by_zero.clone())
+        );
+        assert_eq!(
+            Value::rem(&Value::Int(i32::MIN), &Value::Int(-1)),
+            Ok(Value::Int(0))
+        );
+        assert_eq!(
+            Value::rem(&Value::Long(i64::MIN), &Value::Long(-1)),
+            Ok(Value::Long(0))
+        );
+
+        let mut runtime = runtime_for(
+            "support/tests/DivisionByZero.class",
+            RuntimeConfig::default(),
+        );
+        runtime.push(Value::Long(42));
+        runtime.push(Value::Long(0));
+        assert_eq!(
+            runtime.eval(&Instruction::new(OPCode::LRem, None)),
+            Err(division_by_zero)
+        );
+        runtime.push(Value::Int(i32::MIN));
+        runtime.push(Value::Int(-1));
+        assert!(runtime.eval(&Instruction::new(OPCode::IRem, None)).is_ok());
+        assert_eq!(runtime.pop(), Some(Value::Int(0)));
+    }
+
+    #[test]
+    fn instruction_parameters_can_be_set() {
+        let mut inst =
+            Instruction::new(OPCode::IfICmpGe, Some(vec![Value::Int(13)]));
+        // Flipping a branch rewrites its offset to fall through.
+        inst.set_mnemonic(OPCode::IfICmpLt);
+        inst.set_param(0, Value::Int(3));
+        assert_eq!(
+            inst,
+            Instruction::new(OPCode::IfICmpLt, Some(vec![Value::Int(3)]))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "iadd has no parameter at index 0")]
+    fn setting_missing_parameters_panics() {
+        Instruction::new(OPCode::IAdd, None).set_param(0, Value::Int(1));
+    }
+
+    #[test]
+    fn floating_point_comparisons_order_nan() {
+        let float = Value::Float(1.);
+        let nan = Value::Float(f32::NAN);
+        assert_eq!(Value::fcmpg(&float, &nan), 1);
+        assert_eq!(Value::fcmpg(&nan, &nan), 1);
+        assert_eq!(Value::fcmpl(&nan, &float), -1);
+        assert_eq!(Value::fcmpg(&float, &Value::Float(2.)), -1);
+        assert_eq!(Value::fcmpl(&float, &float), 0);
+        let double = Value::Double(1.);
+        let nan = Value::Double(f64::NAN);
+        assert_eq!(Value::dcmpg(&nan, &double), 1);
+        assert_eq!(Value::dcmpl(&double, &nan), -1);
+        assert_eq!(Value::dcmpl(&nan, &nan), -1);
+        assert_eq!(Value::dcmpg(&Value::Double(2.), &double), 1);
+        assert_eq!(Value::compare(&float, &Value::Float(f32::NAN)), 0);
+    }
+
+    #[test]
+    fn lcmp_only_compares_longs() {
+        let mut runtime =
+            runtime_for("support/tests/Loop.class", RuntimeConfig::default());
+        let cases = [(1, 2, -1), (2, 2, 0), (i64::MAX, i64::MIN, 1)];
+        for (lhs, rhs, expected) in cases {
+            runtime.push(Value::Long(lhs));
+            runtime.push(Value::Long(rhs));
+            let lcmp = Instruction::new(OPCode::LCmp, None);
+            assert!(runtime.eval(&lcmp).is_ok());
+            assert_eq!(runtime.pop(), Some(Value::Int(expected)));
+        }
+        runtime.push(Value::Long(1));
+        runtime.push(Value::Float(1.0));
+        assert_eq!(
+            runtime.eval(&Instruction::new(OPCode::LCmp, None)),
+            Err(RuntimeError::new(RuntimeErrorKind::InvalidOperandType(
+                OPCode::LCmp
+            )))
+        );
+    }
+
+    #[test]
+    fn narrowing_conversions_truncate_ints() {
+        assert_eq!(Value::Int(300).to_byte(), Value::Int(44));
+        assert_eq!(Value::Int(200).to_byte(), Value::Int(-56));
+        assert_eq!(Value::Int(-1).to_char(), Value::Int(65535));
+        assert_eq!(Value::Int(40000).to_short(), Value::Int(-25536));
+
+        let mut runtime =
+            runtime_for("support/tests/Loop.class", RuntimeConfig::default());
+        let cases = [
+            (OPCode::I2B, 300, 44),
+            (OPCode::I2C, 65601, 65),
+            (OPCode::I2S, 40000, -25536),
+        ];
+        for (opcode, value, expected) in cases {
+            runtime.push(Value::Int(value));
+            assert!(runtime.eval(&Instruction::new(opcode, None)).is_ok());
+            assert_eq!(runtime.pop(), Some(Value::Int(expected)));
+        }
+        runtime.push(Value::Long(300));
+        assert_eq!(
+            runtime.eval(&Instruction::new(OPCode::I2B, None)),
+            Err(RuntimeError::new(RuntimeErrorKind::InvalidOperandType(
+                OPCode::I2B
+            )))
+        );
+    }
+
+    #[test]
+    fn comparisons_follow_operand_order() {
+        let mut runtime =
+            runtime_for("support/tests/Loop.class", RuntimeConfig::default());
+        let cases = [
+            (OPCode::LCmp, Value::Long(2), Value::Long(1), 1),
+            (OPCode::LCmp, Value::Long(1), Value::Long(2), -1),
+            (OPCode::LCmp, Value::Long(2), Value::Long(2), 0),
+            (OPCode::FCmpL, Value::Float(2.), Value::Float(1.), 1),
+            (OPCode::FCmpG, Value::Float(1.), Value::Float(2.), -1),
+            (OPCode::DCmpL, Value::Double(1.), Value::Double(2.), -1),
+            (OPCode::DCmpG, Value::Double(2.), Value::Double(1.), 1),
+            (OPCode::FCmpL, Value::Float(f32::NAN), Value::Float(1.), -1),
+            (OPCode::FCmpG, Value::Float(1.), Value::Float(f32::NAN), 1),
+            (
+                OPCode::DCmpL,
+                Value::Double(f64::NAN),
+                Value::Double(1.),
+                -1,
+            ),
+            (OPCode::DCmpG, Value::Double(1.), Value::Double(f64::NAN), 1),
+        ];
+        for (opcode, value1, value2, expected) in cases {
+            runtime.push(value1);
+            runtime.push(value2);
+            assert!(runtime.eval(&Instruction::new(opcode, None)).is_ok());
+            assert_eq!(runtime.pop(), Some(Value::Int(expected)));
+        }
+    }
+
+    #[test]
+    #[cfg(not(feature = "accurate_profiling"))]
+    fn hot_loops_are_compiled_once() {
+        for (program, expected) in [
+            ("support/jit/Loop100.class", None),
+            ("support/tests/SideExit.class", Some(Value::Int(275))),
+        ] {
+            let mut runtime = runtime_for(program, RuntimeConfig::default());
+            assert!(runtime.run(true).is_ok());
+            assert_eq!(runtime.top_return_value(), expected);
+            assert_eq!(runtime.traces.len(), 1);
+            assert_eq!(runtime.jit_cache.compiled_count(), 1);
+        }
+    }
+
+    #[test]
+    fn aborted_recordings_are_not_compiled() {
+        let mut runtime = runtime_for(
+            "support/tests/HotLoop.class",
+            RuntimeConfig {
+                jit_threshold: 1,
+                max_trace_length: 2,
+                ..RuntimeConfig::default()
+            },
+        );
+        assert!(runtime.run(true).is_ok());
+        assert!(runtime.traces.is_empty());
+        assert_eq!(runtime.jit_cache.compiled_count(), 0);
+        assert_eq!(runtime.top_return_value(), Some(Value::Int(55)));
+    }
+
+    #[test]
+    #[cfg(all(feature = "disasm", target_arch = "x86_64"))]
+    fn native_traces_can_be_disassembled() {
+        let mut runtime = runtime_for(
+            "support/tests/HotLoop.class",
+            RuntimeConfig {
+                jit_threshold: 1,
+                ..RuntimeConfig::default()
+            },
+        );
+        assert!(runtime.run(true).is_ok());
+        let pc = *runtime.traces.keys().next().unwrap();
+        let listing = runtime.jit_cache.disassemble_trace(pc).unwrap();
+        let native_traces = runtime.native_traces();
+        assert_eq!(native_traces.len(), 1);
+        assert_eq!(native_traces[0].0, pc);
+        assert_eq!(native_traces[0].1.disassemble(), listing);
+        let instructions: Vec<&str> = listing
+            .lines()
+            .map(|line| line.split_once(' ').map_or(line, |(_, inst)| inst))
+            .collect();
+        // Loop guard `i <= 10`, the body and the exit to `pc` 22 of the
+        // method at index 1.
+        for expected in [
+            "cmp eax,0Ah",
+            "add ecx,r8d",
+            "add dword ptr [rdi+10h],1",
+            "mov rax,100000016h",
+            "ret",
+        ] {
+            assert!(instructions.contains(&expected), "{listing}");
+        }
+        // The prologue is emitted last and is the trace entry point.
+        let entry = instructions.iter().position(|inst| *inst == "entry:");
+        assert_eq!(
+            entry.map(|entry| instructions[entry + 1]),
+            Some("push rbp")
+        );
+    }
+
+    #[test]
+    fn invoking_unknown_methods_fails() {
+        let mut runtime =
+            runtime_for("support/tests/Loop.class", RuntimeConfig::default());
+        // There is no method past the loaded ones.
+        let method_index = runtime.program.methods.len();
+        assert_eq!(
+            runtime.invoke(method_index, false, OPCode::InvokeStatic),
+            Err(RuntimeError::new(RuntimeErrorKind::MissingCode(
+                ProgramCounter::new(method_index, 0)
+            )))
+        );
+    }
+
+    #[test]
+    fn jumps_before_method_start_are_rejected() {
+        let env_var = env::var("CARGO_MANIFEST_DIR").unwrap();
+        let path = Path::new(&env_var).join("support/tests/Loop.class");
+        let mut class_file_bytes = read_class_file(&path).unwrap();
+        // Patch the loop's `goto -13` at offset 19 into `goto -256`.
+        let goto = class_file_bytes
+            .windows(3)
+            .position(|bytes| bytes == [0xa7, 0xff, 0xf3])
+            .unwrap();
+        class_file_bytes[goto + 1..goto + 3]
+            .copy_from_slice(&(-256i16).to_be_bytes());
+        let class_file = JVMParser::parse(&class_file_bytes).unwrap();
+        let mut runtime =
+            Runtime::new(Program::new(&class_file), RuntimeConfig::default());
+        assert_eq!(
+            runtime.run(false).map_err(|err| err.kind().clone()),
+            Err(RuntimeErrorKind::InvalidValue)
+        );
+    }
+
+    #[test]
+    fn undecoded_operands_are_rejected() {
+        let env_var = env::var("CARGO_MANIFEST_DIR").unwrap();
+        let path = Path::new(&env_var).join("support/tests/Loop.class");
+        let mut class_file_bytes = read_class_file(&path).unwrap();
+        // Patch the loop's `goto -13` into an `if_acmpeq -13` which isn't
+        // decoded, its offset would be evaluated as the next opcodes.
+        let goto = class_file_bytes
+            .windows(3)
+            .position(|bytes| bytes == [0xa7, 0xff, 0xf3])
+            .unwrap();
+        class_file_bytes[goto] = 0xa5;
+        let class_file = JVMParser::parse(&class_file_bytes).unwrap();
+        let mut runtime =
+            Runtime::new(Program::new(&class_file), RuntimeConfig::default());
+        assert_eq!(
+            runtime.run(false).map_err(|err| err.kind().clone()),
+            Err(RuntimeErrorKind::InvalidValue)
+        );
+    }
+
+    #[test]
+    fn wide_gotos_are_relative_to_the_opcode() {
+        let mut runtime =
+            runtime_for("support/tests/Loop.class", RuntimeConfig::default());
+        let goto_w =
+            |offset| Instruction::new(OPCode::GotoW, Some(vec![offset]));
+        // A `goto_w` at index 5 has been decoded, the next instruction is at
+        // index 10.
+        for (offset, target) in [(-5, 0), (20, 25), (5, 10)] {
+            runtime.frames[0].pc.instruction_index = 10;
+            assert!(runtime.eval(&goto_w(Value::Int(offset))).is_ok());
+            assert_eq!(runtime.frames[0].pc.instruction_index, target);
+        }
+        runtime.frames[0].pc.instruction_index = 10;
+        assert_eq!(
+            runtime.eval(&goto_w(Value::Int(-6))),
+            Err(RuntimeError::new(RuntimeErrorKind::InvalidValue))
+        );
+        assert_eq!(
+            runtime.eval(&goto_w(Value::Long(5))),
+            Err(RuntimeError::new(RuntimeErrorKind::InvalidOperandType(
+                OPCode::GotoW
+            )))
+        );
+        assert_eq!(
+            runtime.eval(&Instruction::new(OPCode::GotoW, None)),
+            Err(RuntimeError::new(RuntimeErrorKind::MissingOperands(
+                OPCode::GotoW
+            )))
+        );
+    }
+
+    #[test]
+    fn println_overloads_are_dispatched_by_descriptor() {
+        let mut runtime = runtime_for(
+            "support/tests/Println.class",
+            RuntimeConfig::default(),
+        );
+        let program = &runtime.program;
+        let method_ref = |name: &str, descriptor: &str| {
+            let index = (0..program.constant_pool.len())
+                .find(|index| {
+                    program
+                        .method_ref(*index)
+                        .is_some_and(|(_, n, d)| n == name && d == descriptor)
+                })
+                .unwrap() as i32;
+            Instruction::new(
+                OPCode::InvokeVirtual,
+                Some(vec![Value::Int(index >> 8), Value::Int(index & 0xff)]),
+            )
+        };
+        let println_int = method_ref("println", "(I)V");
+        let println_double = method_ref("println", "(D)V");
+        let print = method_ref("print", "(I)V");
+        assert!(runtime_for(
+            "support/tests/Println.class",
+            RuntimeConfig::default()
+        )
+        .run(false)
+        .is_ok());
+
+        // `System.out` is pushed as the receiver before the argument.
+        let out = Value::Reference(None);
+        runtime.push(out);
+        runtime.push(Value::Int(1));
+        assert!(runtime.eval(&println_int).is_ok());
+        assert!(runtime.inspect_stack().is_empty());
+        runtime.push(out);
+        runtime.push(Value::Double(1.));
+        assert!(runtime.eval(&println_double).is_ok());
+        assert!(runtime.inspect_stack().is_empty());
+        runtime.push(out);
+        runtime.push(Value::Double(1.));
+        assert_eq!(
+            runtime.eval(&println_int),
+            Err(RuntimeError::new(RuntimeErrorKind::InvalidOperandType(
+                OPCode::InvokeVirtual
+            )))
+        );
+        assert_eq!(
+            runtime.eval(&println_int),
+            Err(RuntimeError::new(RuntimeErrorKind::MissingOperands(
+                OPCode::InvokeVirtual
+            )))
+        );
+        runtime.push(out);
+        runtime.push(Value::Int(1));
+        assert_eq!(
+            runtime.eval(&print),
+            Err(RuntimeError::new(RuntimeErrorKind::UnsupportedInstruction(
+                OPCode::InvokeVirtual
+            )))
+        );
+    }
+
+    #[test]
+    fn static_fields_default_to_zero_and_keep_stores() {
+        assert_eq!(Value::default_for("I"), Value::Int(0));
+        assert_eq!(Value::default_for("J"), Value::Long(0));
+        assert_eq!(Value::default_for("F"), Value::Float(0.));
+        assert_eq!(Value::default_for("D"), Value::Double(0.));
+        assert_eq!(
+            Value::default_for("Ljava/io/PrintStream;"),
+            Value::Reference(None)
+        );
+        assert_eq!(Value::default_for("[I"), Value::Reference(None));
+
+        let mut runtime = runtime_for(
+            "support/tests/Counter.class",
+            RuntimeConfig::default(),
+        );
+        assert!(runtime.run(false).is_ok());
+        assert_eq!(runtime.top_return_value(), Some(Value::Int(2)));
+        let field = |name: &str| {
+            runtime
+                .static_fields
+                .get(&("Counter".to_string(), name.to_string()))
+                .copied()
+        };
+        assert_eq!(field("counter"), Some(Value::Int(2)));
+        assert_eq!(field("total"), Some(Value::Double(3.)));
+    }
+
+    #[test]
+    fn objects_are_allocated_on_the_heap() {
+        let mut runtime =
+            runtime_for("support/tests/Point.class", RuntimeConfig::default());
+        let program = &runtime.program;
+        let field_ref = |name: &str| {
+            let index = (0..program.constant_pool.len())
+                .find(|index| {
+                    program
+                        .field_ref(*index)
+                        .is_some_and(|(_, field, _)| field == name)
+                })
+                .unwrap() as i32;
+            vec![Value::Int(index >> 8), Value::Int(index & 0xff)]
+        };
+        let get_x = Instruction::new(OPCode::GetField, Some(field_ref("x")));
+        let put_y = Instruction::new(OPCode::PutField, Some(field_ref("y")));
+        let get_y = Instruction::new(OPCode::GetField, Some(field_ref("y")));
+        let new = Instruction::new(OPCode::New, Some(vec![Value::Int(0)]));
+        assert!(runtime.eval(&new).is_ok());
+        assert!(runtime.eval(&new).is_ok());
+        assert_eq!(
+            runtime.inspect_stack(),
+            vec![Value::Reference(Some(0)), Value::Reference(Some(1))]
+        );
+        assert!(runtime.eval(&Instruction::new(OPCode::Dup, None)).is_ok());
+        runtime.push(Value::Int(5));
+        assert!(runtime.eval(&put_y).is_ok());
+        assert!(runtime.eval(&get_y).is_ok());
+        assert_eq!(runtime.pop(), Some(Value::Int(5)));
+        // The first object was never stored to.
+        assert!(runtime.eval(&get_y).is_ok());
+        assert_eq!(runtime.pop(), Some(Value::Int(0)));
+        assert!(runtime.inspect_stack().is_empty());
+
+        runtime.push(Value::Reference(None));
+        assert_eq!(
+            runtime.eval(&get_x),
+            Err(RuntimeError::new(RuntimeErrorKind::NullReference(
+                OPCode::GetField
+            )))
+        );
+        runtime.push(Value::Int(1));
+        assert_eq!(
+            runtime.eval(&get_x),
+            Err(RuntimeError::new(RuntimeErrorKind::InvalidOperandType(
+                OPCode::GetField
+            )))
+        );
+    }
+
+    #[test]
+    fn array_accesses_are_bounds_checked() {
+        let mut runtime = runtime_for(
+            "support/tests/ArraySum.class",
+            RuntimeConfig::default(),
+        );
+        // `newarray` with `T_INT` elements.
+        let new_array =
+            Instruction::new(OPCode::NewArray, Some(vec![Value::Int(10)]));
+        let length = Instruction::new(OPCode::ArrayLength, None);
+        let load = Instruction::new(OPCode::IALoad, None);
+        let store = Instruction::new(OPCode::IAStore, None);
+
+        runtime.push(Value::Int(3));
+        assert!(runtime.eval(&new_array).is_ok());
+        let array = runtime.pop().unwrap();
+        assert_eq!(array, Value::Reference(Some(0)));
+        runtime.push(array);
+        assert!(runtime.eval(&length).is_ok());
+        assert_eq!(runtime.pop(), Some(Value::Int(3)));
+
+        for value in [array, Value::Int(2), Value::Int(7)] {
+            runtime.push(value);
+        }
+        assert!(runtime.eval(&store).is_ok());
+        for index in [2, 0] {
+            runtime.push(array);
+            runtime.push(Value::Int(index));
+            assert!(runtime.eval(&load).is_ok());
+        }
+        assert_eq!(runtime.inspect_stack(), vec![Value::Int(7), Value::Int(0)]);
+        runtime.pop();
+        runtime.pop();
+
+        for index in [3, -1] {
+            runtime.push(array);
+            runtime.push(Value::Int(index));
+            assert_eq!(
+                runtime.eval(&load),
+                Err(RuntimeError::new(
+                    RuntimeErrorKind::ArrayIndexOutOfBounds(index)
+                ))
+            );
+        }
+        runtime.push(Value::Reference(None));
+        assert_eq!(
+            runtime.eval(&length),
+            Err(RuntimeError::new(RuntimeErrorKind::NullReference(
+                OPCode::ArrayLength
+            )))
+        );
+    }
+
+    #[test]
+    fn string_concatenations_push_a_reference() {
+        let mut runtime = runtime_for(
+            "support/tests/StringConcat.class",
+            RuntimeConfig::default(),
+        );
+        let program = &runtime.program;
+        let call_site = program
+            .constant_pool
+            .iter()
+            .position(|entry| {
+                matches!(entry, CPInfo::ConstantInvokeDynamic { .. })
+            })
+            .unwrap();
+        runtime.push(Value::Int(3));
+        let concat = Instruction::new(
+            OPCode::InvokeDynamic,
+            Some(vec![Value::Int(call_site as i32)]),
+        );
+        assert!(runtime.eval(&concat).is_ok());
+        assert_eq!(runtime.pop(), Some(Value::Reference(None)));
+        assert_eq!(runtime.pop(), None);
+    }
+
+    #[test]
+    fn malformed_calls_and_conversions_return_errors() {
+        let mut runtime =
+            runtime_for("support/tests/Loop.class", RuntimeConfig::default());
+        let error = |kind| Err(RuntimeError::new(kind));
+        assert_eq!(
+            runtime.eval(&Instruction::new(OPCode::L2I, None)),
+            error(RuntimeErrorKind::MissingOperands(OPCode::L2I))
+        );
+        runtime.push(Value::Reference(None));
+        assert_eq!(
+            runtime.eval(&Instruction::new(OPCode::I2D, None)),
+            error(RuntimeErrorKind::InvalidOperandType(OPCode::I2D))
+        );
+        assert_eq!(
+            runtime.eval(&Instruction::new(OPCode::InvokeStatic, None)),
+            error(RuntimeErrorKind::MissingOperands(OPCode::InvokeStatic))
+        );
+        assert_eq!(
+            runtime.eval(&Instruction::new(
+                OPCode::InvokeStatic,
+                Some(vec![Value::Float(1.)])
+            )),
+            error(RuntimeErrorKind::InvalidOperandType(OPCode::InvokeStatic))
+        );
+        // `main` takes the arguments array which isn't on the stack.
+        let main = runtime.frames[0].method_index();
+        assert_eq!(
+            runtime.eval(&Instruction::new(
+                OPCode::InvokeStatic,
+                Some(vec![Value::Int(main as i32)])
+            )),
+            error(RuntimeErrorKind::MissingOperands(OPCode::InvokeStatic))
+        );
+        assert_eq!(
+            runtime.eval(&Instruction::new(OPCode::IReturn, None)),
+            error(RuntimeErrorKind::MissingOperands(OPCode::IReturn))
+        );
+    }
+
+    #[test]
+    fn malformed_branches_return_errors() {
+        let mut runtime =
+            runtime_for("support/tests/Loop.class", RuntimeConfig::default());
+        let ifeq = Instruction::new(OPCode::IfEq, Some(vec![Value::Int(8)]));
+        let error = |kind| Err(RuntimeError::new(kind));
+        assert_eq!(
+            runtime.eval(&ifeq),
+            error(RuntimeErrorKind::MissingOperands(OPCode::IfEq))
+        );
+        runtime.push(Value::Float(0.0));
+        assert_eq!(
+            runtime.eval(&ifeq),
+            error(RuntimeErrorKind::InvalidOperandType(OPCode::IfEq))
+        );
+        assert_eq!(
+            runtime.eval(&Instruction::new(OPCode::Goto, None)),
+            error(RuntimeErrorKind::MissingOperands(OPCode::Goto))
+        );
+        runtime.push(Value::Int(0));
+        assert_eq!(
+            runtime.eval(&Instruction::new(
+                OPCode::IfNe,
+                Some(vec![Value::Long(8)])
+            )),
+            error(RuntimeErrorKind::InvalidOperandType(OPCode::IfNe))
+        );
+        runtime.push(Value::Int(0));
+        assert_eq!(
+            runtime.eval(&Instruction::new(
+                OPCode::IfNull,
+                Some(vec![Value::Int(8)])
+            )),
+            error(RuntimeErrorKind::InvalidOperandType(OPCode::IfNull))
+        );
+    }
+
+    #[test]
+    fn can_report_hot_loops() {
+        let mut runtime =
+            runtime_for("support/tests/Loop.class", RuntimeConfig::default());
+        assert!(runtime.run(false).is_ok());
+        let report = runtime.profiling_report();
+        let interpreted: u64 = runtime.opcode_histogram().values().sum();
+        assert_eq!(report.total_instructions as u64, interpreted);
+        let (header, count) = report.hot_pcs[0];
+        assert!(count >= 999);
+        assert!(report.hot_pcs.iter().all(|(_, other)| *other <= count));
+        assert!(runtime.profiler.is_hot(&header));
+    }
+
+    #[test]
+    fn method_calls_are_profiled() {
+        let mut runtime = runtime_for(
+            "support/tests/Fibonacci.class",
+            RuntimeConfig::default(),
+        );
+        let main = runtime.frames[0].method_index();
+        assert!(runtime.run(false).is_ok());
+        assert!(!runtime.profiler.is_hot_method(main));
+        // Only the recursive `fibonacci` method is hot.
+        let hot_methods = (0..runtime.program.methods.len())
+            .filter(|index| runtime.profiler.is_hot_method(*index))
+            .count();
+        assert_eq!(hot_methods, 1);
+    }
+
+    #[test]
+    fn can_collect_opcode_histogram() {
+        let mut runtime =
+            runtime_for("support/tests/Loop.class", RuntimeConfig::default());
+        assert!(runtime.run(false).is_ok());
+        let histogram = runtime.opcode_histogram();
+        assert_eq!(histogram.get(&OPCode::IReturn), Some(&1));
+        assert!(histogram.get(&OPCode::Goto).is_some_and(|count| *count > 1));
+        assert_eq!(histogram.get(&OPCode::Breakpoint), None);
+    }
+
+    #[test]
+    fn can_inspect_current_frame() {
+        let mut runtime =
+            runtime_for("support/tests/Loop.class", RuntimeConfig::default());
+        assert!(runtime.inspect_stack().is_empty());
+        assert!(runtime.inspect_locals().is_empty());
+
+        runtime.push(Value::Int(9));
+        runtime.store(1);
+        runtime.push(Value::Int(7));
+        runtime.push(Value::Long(8));
+        assert_eq!(
+            runtime.inspect_stack(),
+            vec![Value::Int(7), Value::Long(8)]
+        );
+        assert_eq!(
+            runtime.inspect_locals(),
+            HashMap::from([(1, Value::Int(9))])
+        );
+
+        runtime.frames.clear();
+        assert!(runtime.inspect_stack().is_empty());
+        assert!(runtime.inspect_locals().is_empty());
+    }
+
+    #[test]
+    fn reference_arguments_take_one_slot() {
+        let mut runtime = runtime_for(
+            "support/tests/StringArg.class",
+            RuntimeConfig::default(),
+        );
+        let program = &runtime.program;
+        let pick = program
+            .method_index("StringArg", "pick", "(Ljava/lang/String;I)I")
+            .unwrap();
+        // Strings aren't supported yet, push a placeholder for the reference.
+        runtime.push(Value::Int(0));
+        runtime.push(Value::Int(7));
+        runtime.invoke(pick, false, OPCode::InvokeStatic).unwrap();
+        assert_eq!(
+            runtime.inspect_locals(),
+            HashMap::from([(0, Value::Int(0)), (1, Value::Int(7))])
+        );
+    }
 }
@@ -1,6 +1,7 @@
 //! Runtime tracing module for coldbrew.
 use core::fmt;
 use std::collections::HashSet;
+use std::time::{Duration, Instant};
 
 use crate::bytecode::OPCode;
 use crate::runtime::{Instruction, ProgramCounter, Value};
@@ -16,8 +17,12 @@ pub struct Record {
 }
 
 impl Record {
-    pub fn instruction(&self) -> Instruction {
-        self.inst.clone()
+    pub fn new(pc: ProgramCounter, inst: Instruction) -> Self {
+        Self { pc, inst }
+    }
+
+    pub fn instruction(&self) -> &Instruction {
+        &self.inst
     }
 
     pub fn pc(&self) -> ProgramCounter {
@@ -34,18 +39,216 @@ impl fmt::Display for Record {
 #[derive(Debug, Clone)]
 pub struct Trace {
     pub start: ProgramCounter,
-    pub trace: Vec<Record>,
+    pub(crate) trace: Vec<Record>,
+}
+
+impl Trace {
+    /// Returns an iterator over the recorded entries.
+    pub fn iter(&self) -> std::slice::Iter<'_, Record> {
+        self.trace.iter()
+    }
+
+    /// Serializes the trace with one `{method}:{index} {mnemonic} [operands]`
+    /// line per entry, see `from_text` for the inverse.
+    ///
+    /// Operands are written as Java literals so their type survives the
+    /// round trip : `long`, `float` and `double` values are suffixed with
+    /// `L`, `f` and `d` respectively.
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for record in &self.trace {
+            let operands: Vec<String> = record
+                .inst
+                .get_params()
+                .iter()
+                .flatten()
+                .map(operand_to_text)
+                .collect();
+            text.push_str(&format!(
+                "{}:{} {} [{}]\n",
+                record.pc.get_method_index(),
+                record.pc.get_instruction_index(),
+                record.inst.get_mnemonic(),
+                operands.join(", ")
+            ));
+        }
+        text
+    }
+
+    /// Parses a trace serialized by `to_text`, the trace starts at the
+    /// program counter of its first entry. Blank lines are ignored.
+    pub fn from_text(text: &str) -> Result<Self, TraceParseError> {
+        let trace = text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(index, line)| {
+                parse_record(line.trim())
+                    .ok_or(TraceParseError::InvalidLine { line: index + 1 })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        match trace.first() {
+            Some(record) => Ok(Self {
+                start: record.pc,
+                trace,
+            }),
+            None => Err(TraceParseError::Empty),
+        }
+    }
+}
+
+/// `TraceParseError` represents the possible failures when parsing a trace
+/// from its text format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceParseError {
+    /// The text has no entries.
+    Empty,
+    /// The entry at `line` (1-based) isn't a valid record.
+    InvalidLine { line: usize },
+}
+
+impl fmt::Display for TraceParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "Trace has no entries"),
+            Self::InvalidLine { line } => {
+                write!(f, "Invalid trace entry at line {line}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for TraceParseError {}
+
+// Writes an operand as a Java literal.
+fn operand_to_text(operand: &Value) -> String {
+    match operand {
+        Value::Int(v) => format!("{v}"),
+        Value::Long(v) => format!("{v}L"),
+        Value::Float(v) => format!("{v:?}f"),
+        Value::Double(v) => format!("{v:?}d"),
+        Value::Reference(None) => "null".to_string(),
+        Value::Reference(Some(index)) => format!("object@{index}"),
+    }
+}
+
+// Parses an operand written by `operand_to_text`.
+fn operand_from_text(text: &str) -> Option<Value> {
+    if text == "null" {
+        Some(Value::Reference(None))
+    } else if let Some(index) = text.strip_prefix("object@") {
+        index
+            .parse()
+            .ok()
+            .map(|index| Value::Reference(Some(index)))
+    } else if let Some(v) = text.strip_suffix('L') {
+        v.parse().ok().map(Value::Long)
+    } else if let Some(v) = text.strip_suffix('f') {
+        v.parse().ok().map(Value::Float)
+    } else if let Some(v) = text.strip_suffix('d') {
+        v.parse().ok().map(Value::Double)
+    } else {
+        text.parse().ok().map(Value::Int)
+    }
+}
+
+// Parses a `{method}:{index} {mnemonic} [operands]` line.
+fn parse_record(line: &str) -> Option<Record> {
+    let (pc, rest) = line.split_once(' ')?;
+    let (method_index, instruction_index) = pc.split_once(':')?;
+    let pc = ProgramCounter::new(
+        method_index.parse().ok()?,
+        instruction_index.parse().ok()?,
+    );
+    let (mnemonic, operands) = rest.split_once(' ')?;
+    let mnemonic = *OPCode::ALL
+        .iter()
+        .find(|opcode| opcode.mnemonic() == mnemonic)?;
+    let operands = operands.strip_prefix('[')?.strip_suffix(']')?.trim();
+    // Instructions without operands are decoded with `None` operands.
+    let operands = if operands.is_empty() {
+        None
+    } else {
+        Some(
+            operands
+                .split(',')
+                .map(|operand| operand_from_text(operand.trim()))
+                .collect::<Option<Vec<_>>>()?,
+        )
+    };
+    Some(Record::new(pc, Instruction::new(mnemonic, operands)))
+}
+
+// Default upper bound on the time spent recording a single trace.
+const DEFAULT_MAX_RECORDING_DURATION: Duration = Duration::from_secs(1);
+
+/// Default upper bound on the number of instructions in a single trace.
+pub const DEFAULT_MAX_TRACE_LENGTH: usize = 512;
+
+/// `AbortReason` is the reason a recording was aborted, aborted recordings
+/// aren't compiled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AbortReason {
+    /// The recording took longer than the maximum recording duration.
+    RecordingTooSlow,
+    /// The trace has more instructions than the maximum trace length.
+    TraceTooLong,
+    /// The recorded method calls itself.
+    RecursiveCall,
+    /// The recorded code entered a native trace, its instructions aren't
+    /// recorded.
+    NativeTraceEntered,
+}
+
+impl fmt::Display for AbortReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::RecordingTooSlow => write!(f, "recording took too long"),
+            Self::TraceTooLong => write!(f, "trace is too long"),
+            Self::RecursiveCall => write!(f, "recursive call"),
+            Self::NativeTraceEntered => write!(f, "native trace entered"),
+        }
+    }
+}
+
+/// State of the recorder, the recorded entries and the branch targets only
+/// exist while a trace is being recorded.
+///
+/// There is a single recorder per runtime so the size of the `Idle` variant
+/// doesn't matter.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug)]
+enum RecordingState {
+    // No trace is being recorded.
+    Idle,
+    // A trace starting at `trace_start` is being recorded.
+    Recording {
+        trace_start: ProgramCounter,
+        loop_header: ProgramCounter,
+        trace: Vec<Record>,
+        inner_branch_targets: HashSet<ProgramCounter>,
+        outer_branch_targets: HashSet<ProgramCounter>,
+        // Default targets of the recorded `tableswitch` and `lookupswitch`.
+        switch_default_targets: HashSet<ProgramCounter>,
+        // Time at which the recording started.
+        recording_start: Instant,
+        // Methods of the recorded call chain, the innermost call is last.
+        recorded_methods: Vec<usize>,
+    },
+    // The last recording was aborted and wasn't collected yet.
+    Aborted(AbortReason),
 }
 
 /// Recorder is the runtime component responsible for recording traces.
 pub struct Recorder {
-    trace_start: ProgramCounter,
-    loop_header: ProgramCounter,
-    is_recording: bool,
-    last_instruction_was_branch: bool,
-    pub trace: Vec<Record>,
-    inner_branch_targets: HashSet<ProgramCounter>,
-    outer_branch_targets: HashSet<ProgramCounter>,
+    state: RecordingState,
+    // Recordings that don't close before this duration are aborted.
+    max_recording_duration: Duration,
+    // Recordings with more instructions than this are aborted.
+    max_trace_length: usize,
+    // Start of the traces recorded so far, entries are removed when their
+    // trace is invalidated.
+    compiled_pcs: HashSet<ProgramCounter>,
 }
 
 impl Default for Recorder {
@@ -57,44 +260,57 @@ impl Default for Recorder {
 impl Recorder {
     pub fn new() -> Self {
         Self {
-            trace_start: ProgramCounter::default(),
-            loop_header: ProgramCounter::default(),
-            is_recording: false,
-            last_instruction_was_branch: false,
-            trace: Vec::new(),
-            inner_branch_targets: HashSet::new(),
-            outer_branch_targets: HashSet::new(),
+            state: RecordingState::Idle,
+            max_recording_duration: DEFAULT_MAX_RECORDING_DURATION,
+            max_trace_length: DEFAULT_MAX_TRACE_LENGTH,
+            compiled_pcs: HashSet::new(),
+        }
+    }
+
+    /// Create a new recorder that aborts recordings taking longer than
+    /// `max_recording_duration`.
+    pub fn with_max_recording_duration(
+        max_recording_duration: Duration,
+    ) -> Self {
+        Self {
+            max_recording_duration,
+            ..Self::new()
+        }
+    }
+
+    /// Create a new recorder that aborts recordings with more than
+    /// `max_trace_length` instructions.
+    pub fn with_max_trace_length(max_trace_length: usize) -> Self {
+        Self {
+            max_trace_length,
+            ..Self::new()
         }
     }
 
     /// Check if we are recording a trace already.
     pub fn is_recording(&self) -> bool {
-        self.is_recording
+        matches!(self.state, RecordingState::Recording { .. })
     }
 
-    /// Check if we finished recording a trace.
+    /// Check if we finished recording a trace, aborted recordings are
+    /// finished as well and `Recorder::recording` returns why.
     pub fn is_done_recording(&mut self, pc: ProgramCounter) -> bool {
-        if self.trace.is_empty() {
+        let RecordingState::Recording {
+            trace, loop_header, ..
+        } = &self.state
+        else {
+            return matches!(self.state, RecordingState::Aborted(_));
+        };
+        let Some(entry) = trace.last() else {
             return false;
+        };
+        let is_return = entry.inst.get_mnemonic().is_return();
+        // If we found a recursive call we need to exit.
+        if is_return && pc.get_method_index() == entry.pc.get_method_index() {
+            self.abort(AbortReason::RecursiveCall);
+            return true;
         }
-        match self.trace.last() {
-            Some(entry) => match entry.inst.get_mnemonic() {
-                OPCode::Return
-                | OPCode::IReturn
-                | OPCode::LReturn
-                | OPCode::FReturn
-                | OPCode::DReturn => {
-                    // If we found a recursive call we need to exit.
-                    if pc.get_method_index() == entry.pc.get_method_index() {
-                        self.is_recording = false;
-                        return false;
-                    }
-                    pc == self.loop_header
-                }
-                _ => pc == self.loop_header,
-            },
-            None => false,
-        }
+        pc == *loop_header
     }
 
     /// Record the bytecode instruction at the given `pc` and `inst`
@@ -104,13 +320,59 @@ impl Recorder {
     /// During the recording phase if any aborting condition is met we stop
     /// recording and return. The aborting conditions are (1) jumps to outer
     /// branches, (2) function calls or (3) conditional branches.
+    ///
+    /// Recordings that exceed the maximum recording duration or trace length
+    /// are aborted.
     pub fn record(&mut self, pc: ProgramCounter, mut inst: Instruction) {
+        let RecordingState::Recording {
+            trace_start,
+            trace,
+            inner_branch_targets,
+            outer_branch_targets,
+            switch_default_targets,
+            recording_start,
+            recorded_methods,
+            ..
+        } = &mut self.state
+        else {
+            return;
+        };
+        if recording_start.elapsed() >= self.max_recording_duration {
+            self.abort(AbortReason::RecordingTooSlow);
+            return;
+        }
+        if trace.len() >= self.max_trace_length {
+            self.abort(AbortReason::TraceTooLong);
+            return;
+        }
+        // Calls are followed by the first instruction of the callee in its
+        // own method, and returns by the caller instruction following the
+        // call. Entering a method of the recorded call chain again means
+        // the recorded code is (mutually) recursive.
+        if let Some(last) = trace.last() {
+            let opcode = last.inst.get_mnemonic();
+            let method_index = pc.get_method_index();
+            if method_index != last.pc.get_method_index() {
+                if opcode.is_invoke() {
+                    if recorded_methods.contains(&method_index) {
+                        self.abort(AbortReason::RecursiveCall);
+                        return;
+                    }
+                    recorded_methods.push(method_index);
+                } else if opcode.is_return() {
+                    recorded_methods.pop();
+                }
+            }
+        }
         match inst.get_mnemonic() {
-            OPCode::Goto => {
+            OPCode::Goto | OPCode::GotoW => {
+                // `goto_w` offsets are decoded from 4 bytes instead of 2 but
+                // are otherwise handled like `goto`.
                 let offset = match inst.nth(0) {
                     Some(Value::Int(v)) => v,
                     _ => panic!(
-                        "Expected Goto to have at least one integer parameter"
+                        "Expected {} to have at least one integer parameter",
+                        inst.get_mnemonic()
                     ),
                 };
                 // Forward branch, aborting.
@@ -119,37 +381,42 @@ impl Recorder {
                 } else {
                     let mut branch_target = pc;
                     branch_target.inc_instruction_index(offset);
-                    if self.trace_start == branch_target {
-                        self.inner_branch_targets.insert(branch_target);
+                    // Jumps back to any recorded instruction stay within the
+                    // trace body.
+                    if *trace_start == branch_target
+                        || trace.iter().any(|entry| entry.pc == branch_target)
+                    {
+                        inner_branch_targets.insert(branch_target);
                     } else {
-                        self.outer_branch_targets.insert(branch_target);
+                        outer_branch_targets.insert(branch_target);
                     }
                 }
             }
-            OPCode::IfNe
-            | OPCode::IfEq
-            | OPCode::IfGt
-            | OPCode::IfICmpGe
-            | OPCode::IfICmpGt
-            | OPCode::IfICmpLt
-            | OPCode::IfICmpLe
-            | OPCode::IfICmpNe
-            | OPCode::IfICmpEq => {
-                self.last_instruction_was_branch = true;
+            OPCode::TableSwitch | OPCode::LookupSwitch => {
+                // Switch targets are typically forward jumps out of the loop
+                // body so they are all considered outer branch targets.
+                let (default, offsets) = Self::get_switch_offsets(&inst);
+                let mut default_target = pc;
+                default_target.inc_instruction_index(default);
+                switch_default_targets.insert(default_target);
+                for offset in offsets {
+                    let mut branch_target = pc;
+                    branch_target.inc_instruction_index(offset);
+                    outer_branch_targets.insert(branch_target);
+                }
             }
             OPCode::InvokeStatic => {
                 // Check for recursive function calls by comparing the invoked
-                // method index with the one we are currently recording.
+                // method index with the methods of the recorded call chain.
                 let method_index = match inst.nth(0) {
                     Some(Value::Int(v)) => v,
                     _ => panic!(
                         "Expected InvokeStatic to have at least one parameter"
                     ),
                 };
-                if self.trace_start.get_method_index() == method_index as usize
-                {
+                if recorded_methods.contains(&(method_index as usize)) {
                     // Found a recursive call, aborting.
-                    self.is_recording = false;
+                    self.abort(AbortReason::RecursiveCall);
                     return;
                 }
             }
@@ -191,6 +458,10 @@ impl Recorder {
             | OPCode::FStore1
             | OPCode::FStore2
             | OPCode::FStore3
+            | OPCode::LStore0
+            | OPCode::LStore1
+            | OPCode::LStore2
+            | OPCode::LStore3
             | OPCode::DStore0
             | OPCode::DStore1
             | OPCode::DStore2
@@ -204,13 +475,41 @@ impl Recorder {
             }
             _ => (),
         }
-        self.trace.push(Record {
-            pc,
-            inst: inst.clone(),
-        });
+        trace.push(Record { pc, inst });
     }
 
-    /// Returns an equivalent mnemonic from the given one.
+    /// Returns the default offset and the branch offsets of a switch, the
+    /// operands are laid out as in the bytecode without the padding :
+    ///
+    /// - `tableswitch` : `[default, low, high, offsets...]`.
+    /// - `lookupswitch` : `[default, npairs, (match, offset)...]`.
+    fn get_switch_offsets(inst: &Instruction) -> (i32, Vec<i32>) {
+        let Some(params) = inst.get_params() else {
+            panic!("Expected {} to have operands", inst.get_mnemonic())
+        };
+        let offsets: Vec<i32> = params
+            .iter()
+            .map(|param| match param {
+                Value::Int(v) => *v,
+                _ => panic!(
+                    "Expected {} operands to be integers",
+                    inst.get_mnemonic()
+                ),
+            })
+            .collect();
+        let (default, offsets) = offsets
+            .split_first()
+            .expect("Expected switch to have a default offset");
+        let targets = match inst.get_mnemonic() {
+            OPCode::TableSwitch => offsets.iter().skip(2).copied().collect(),
+            _ => offsets.iter().skip(2).step_by(2).copied().collect(),
+        };
+        (*default, targets)
+    }
+
+    /// Returns an equivalent mnemonic from the given one, the type of loads
+    /// and stores is preserved so two slot (long and double) accesses stay
+    /// distinguishable.
     fn get_mnemonic(opcode: OPCode) -> OPCode {
         match opcode {
             OPCode::ILoad0
@@ -318,27 +617,418 @@ impl Recorder {
         }
     }
 
-    /// Init a trace recording.
+    /// Init a trace recording, an ongoing recording of the same trace is
+    /// kept as is.
     pub fn init(&mut self, loop_header: ProgramCounter, start: ProgramCounter) {
-        if self.is_recording && self.trace_start == start {
-            return;
+        if let RecordingState::Recording { trace_start, .. } = &self.state {
+            if *trace_start == start {
+                return;
+            }
+        }
+        self.state = RecordingState::Recording {
+            trace_start: start,
+            loop_header,
+            trace: Vec::new(),
+            inner_branch_targets: HashSet::new(),
+            outer_branch_targets: HashSet::new(),
+            switch_default_targets: HashSet::new(),
+            recording_start: Instant::now(),
+            recorded_methods: vec![start.get_method_index()],
+        };
+    }
+
+    /// Abort the current recording for `reason` and drop the recorded
+    /// entries, nothing happens if no trace is being recorded.
+    pub fn abort(&mut self, reason: AbortReason) {
+        if self.is_recording() {
+            self.state = RecordingState::Aborted(reason);
         }
-        self.is_recording = true;
-        self.last_instruction_was_branch = false;
-        self.trace_start = start;
-        self.loop_header = loop_header;
-        // Clear existing traces.
-        self.trace.clear();
-        self.inner_branch_targets.clear();
-        self.outer_branch_targets.clear();
-    }
-
-    /// Return the last recorded trace.
-    pub fn recording(&mut self) -> Trace {
-        self.is_recording = false;
-        Trace {
-            start: self.trace_start,
-            trace: self.trace.clone(),
+    }
+
+    /// Check if a trace starting at `pc` was recorded and wasn't invalidated
+    /// since.
+    pub fn is_compiled(&self, pc: &ProgramCounter) -> bool {
+        self.compiled_pcs.contains(pc)
+    }
+
+    /// Invalidate the trace recorded at `pc` (i.e after its native trace was
+    /// evicted) so it can be recorded again.
+    pub fn invalidate(&mut self, pc: &ProgramCounter) {
+        self.compiled_pcs.remove(pc);
+    }
+
+    /// Return the last recorded trace and stop recording, or the reason the
+    /// last recording was aborted.
+    ///
+    /// # Errors
+    /// Returns `AbortReason` if the last recording was aborted.
+    ///
+    /// # Panics
+    /// Panics if no trace is being recorded.
+    pub fn recording(&mut self) -> Result<Trace, AbortReason> {
+        match std::mem::replace(&mut self.state, RecordingState::Idle) {
+            RecordingState::Recording {
+                trace_start, trace, ..
+            } => {
+                self.compiled_pcs.insert(trace_start);
+                Ok(Trace {
+                    start: trace_start,
+                    trace,
+                })
+            }
+            RecordingState::Aborted(reason) => Err(reason),
+            RecordingState::Idle => panic!("Expected a trace to be recorded"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Returns the entries recorded so far, empty if nothing is recorded.
+    fn recorded(recorder: &Recorder) -> &[Record] {
+        match &recorder.state {
+            RecordingState::Recording { trace, .. } => trace,
+            RecordingState::Idle | RecordingState::Aborted(_) => &[],
+        }
+    }
+
+    // Returns the inner, outer and switch default branch targets of the
+    // ongoing recording.
+    fn branch_targets(recorder: &Recorder) -> [&HashSet<ProgramCounter>; 3] {
+        let RecordingState::Recording {
+            inner_branch_targets,
+            outer_branch_targets,
+            switch_default_targets,
+            ..
+        } = &recorder.state
+        else {
+            panic!("Expected an ongoing recording")
+        };
+        [
+            inner_branch_targets,
+            outer_branch_targets,
+            switch_default_targets,
+        ]
+    }
+
+    #[test]
+    fn invalidated_traces_can_be_recorded_again() {
+        let pc = ProgramCounter::new(0, 2);
+        let mut recorder = Recorder::new();
+        recorder.init(pc, pc);
+        recorder.record(pc, Instruction::new(OPCode::IAdd, None));
+        assert!(!recorder.is_compiled(&pc));
+        let trace = recorder.recording().unwrap();
+        assert_eq!(trace.start, pc);
+        assert!(recorder.is_compiled(&pc));
+
+        recorder.invalidate(&pc);
+        assert!(!recorder.is_compiled(&pc));
+        recorder.init(pc, pc);
+        assert!(recorder.is_recording());
+        assert!(recorded(&recorder).is_empty());
+    }
+
+    #[test]
+    fn stack_manipulations_are_recorded() {
+        let pc = ProgramCounter::new(0, 2);
+        let mut recorder = Recorder::new();
+        recorder.init(pc, pc);
+        let opcodes = [OPCode::Dup, OPCode::Pop, OPCode::Pop2, OPCode::Swap];
+        for opcode in opcodes {
+            recorder.record(pc, Instruction::new(opcode, None));
+        }
+        let recorded: Vec<OPCode> = recorded(&recorder)
+            .iter()
+            .map(|entry| entry.instruction().get_mnemonic())
+            .collect();
+        assert_eq!(recorded, opcodes);
+    }
+
+    #[test]
+    fn recording_is_aborted_when_over_budget() {
+        let pc = ProgramCounter::new(0, 2);
+        let mut recorder = Recorder::new();
+        recorder.init(pc, pc);
+        recorder.record(pc, Instruction::new(OPCode::IAdd, None));
+        assert!(recorder.is_recording());
+        assert_eq!(recorded(&recorder).len(), 1);
+
+        let mut recorder =
+            Recorder::with_max_recording_duration(Duration::ZERO);
+        recorder.init(pc, pc);
+        recorder.record(pc, Instruction::new(OPCode::IAdd, None));
+        assert!(!recorder.is_recording());
+        assert!(recorded(&recorder).is_empty());
+        assert!(recorder.is_done_recording(pc));
+        assert_eq!(
+            recorder.recording().unwrap_err(),
+            AbortReason::RecordingTooSlow
+        );
+    }
+
+    #[test]
+    fn long_recordings_are_aborted() {
+        let pc = ProgramCounter::new(0, 2);
+        let mut recorder = Recorder::with_max_trace_length(2);
+        recorder.init(pc, pc);
+        recorder.record(pc, Instruction::new(OPCode::IAdd, None));
+        recorder.record(pc, Instruction::new(OPCode::IAdd, None));
+        assert!(recorder.is_recording());
+        recorder.record(pc, Instruction::new(OPCode::IAdd, None));
+        assert!(!recorder.is_recording());
+        assert!(recorder.is_done_recording(pc));
+        assert_eq!(
+            recorder.recording().unwrap_err(),
+            AbortReason::TraceTooLong
+        );
+        // A new recording starts from scratch.
+        recorder.init(pc, pc);
+        assert!(recorder.is_recording());
+        assert!(recorded(&recorder).is_empty());
+    }
+
+    #[test]
+    fn switch_targets_are_recorded_as_outer_branches() {
+        let pc = ProgramCounter::new(0, 10);
+        let mut recorder = Recorder::new();
+        recorder.init(pc, pc);
+        // tableswitch with low = 1, high = 3.
+        recorder.record(
+            pc,
+            Instruction::new(
+                OPCode::TableSwitch,
+                Some(vec![
+                    Value::Int(40),
+                    Value::Int(1),
+                    Value::Int(3),
+                    Value::Int(20),
+                    Value::Int(28),
+                    Value::Int(36),
+                ]),
+            ),
+        );
+        // lookupswitch with two pairs.
+        recorder.record(
+            pc,
+            Instruction::new(
+                OPCode::LookupSwitch,
+                Some(vec![
+                    Value::Int(50),
+                    Value::Int(2),
+                    Value::Int(100),
+                    Value::Int(60),
+                    Value::Int(1000),
+                    Value::Int(70),
+                ]),
+            ),
+        );
+        let targets = |offsets: &[usize]| -> HashSet<ProgramCounter> {
+            offsets
+                .iter()
+                .map(|offset| ProgramCounter::new(0, 10 + offset))
+                .collect()
+        };
+        assert_eq!(recorded(&recorder).len(), 2);
+        let [inner, outer, switch_defaults] = branch_targets(&recorder);
+        assert_eq!(switch_defaults, &targets(&[40, 50]));
+        assert_eq!(outer, &targets(&[20, 28, 36, 60, 70]));
+        assert!(inner.is_empty());
+    }
+
+    #[test]
+    fn wide_gotos_register_branch_targets() {
+        let start = ProgramCounter::new(0, 6);
+        let mut recorder = Recorder::new();
+        recorder.init(start, start);
+        let goto_w = |offset| {
+            Instruction::new(OPCode::GotoW, Some(vec![Value::Int(offset)]))
+        };
+        // Backward jump to the trace start.
+        recorder.record(ProgramCounter::new(0, 19), goto_w(-13));
+        // Backward jump before the trace start.
+        recorder.record(ProgramCounter::new(0, 30), goto_w(-28));
+        // Forward jumps aren't recorded.
+        recorder.record(ProgramCounter::new(0, 40), goto_w(70000));
+        assert_eq!(recorded(&recorder).len(), 2);
+        let [inner, outer, _] = branch_targets(&recorder);
+        assert_eq!(inner, &HashSet::from([start]));
+        assert_eq!(outer, &HashSet::from([ProgramCounter::new(0, 2)]));
+    }
+
+    #[test]
+    fn backward_jumps_to_recorded_instructions_are_inner() {
+        let start = ProgramCounter::new(0, 6);
+        let mut recorder = Recorder::new();
+        recorder.init(start, start);
+        recorder.record(start, Instruction::new(OPCode::ILoad0, None));
+        recorder.record(
+            ProgramCounter::new(0, 7),
+            Instruction::new(OPCode::ILoad1, None),
+        );
+        let goto = |offset| {
+            Instruction::new(OPCode::Goto, Some(vec![Value::Int(offset)]))
+        };
+        // Backward jump to a recorded instruction past the trace start.
+        recorder.record(ProgramCounter::new(0, 12), goto(-5));
+        // Backward jump to an instruction that wasn't recorded.
+        recorder.record(ProgramCounter::new(0, 15), goto(-7));
+        let [inner, outer, _] = branch_targets(&recorder);
+        assert_eq!(inner, &HashSet::from([ProgramCounter::new(0, 7)]));
+        assert_eq!(outer, &HashSet::from([ProgramCounter::new(0, 8)]));
+    }
+
+    #[test]
+    fn traces_round_trip_through_their_text_format() {
+        // `iload_0` is recorded as `iload 0`.
+        const TEXT: &str = "\
+0:2 iload [0]
+0:3 ldc [2L]
+0:5 ldc [1.5f]
+0:7 ldc [-0.25d]
+0:9 iinc [1, -1]
+0:12 aconst_null []
+1:0 aload [object@3]
+0:13 goto [-11]
+";
+        let start = ProgramCounter::new(0, 2);
+        let mut recorder = Recorder::new();
+        recorder.init(start, start);
+        let entries = [
+            (start, OPCode::ILoad0, None),
+            (ProgramCounter::new(0, 3), OPCode::Ldc, Some(Value::Long(2))),
+            (
+                ProgramCounter::new(0, 5),
+                OPCode::Ldc,
+                Some(Value::Float(1.5)),
+            ),
+            (
+                ProgramCounter::new(0, 7),
+                OPCode::Ldc,
+                Some(Value::Double(-0.25)),
+            ),
+        ];
+        for (pc, opcode, operand) in entries {
+            recorder
+                .record(pc, Instruction::new(opcode, operand.map(|v| vec![v])));
+        }
+        recorder.record(
+            ProgramCounter::new(0, 9),
+            Instruction::new(
+                OPCode::IInc,
+                Some(vec![Value::Int(1), Value::Int(-1)]),
+            ),
+        );
+        recorder.record(
+            ProgramCounter::new(0, 12),
+            Instruction::new(OPCode::AConstNull, None),
+        );
+        recorder.record(
+            ProgramCounter::new(1, 0),
+            Instruction::new(
+                OPCode::ALoad,
+                Some(vec![Value::Reference(Some(3))]),
+            ),
+        );
+        recorder.record(
+            ProgramCounter::new(0, 13),
+            Instruction::new(OPCode::Goto, Some(vec![Value::Int(-11)])),
+        );
+        let trace = recorder.recording().unwrap();
+        assert_eq!(trace.to_text(), TEXT);
+
+        let parsed = Trace::from_text(TEXT).unwrap();
+        assert_eq!(parsed.start, start);
+        assert_eq!(parsed.trace.len(), trace.trace.len());
+        for (parsed, recorded) in parsed.iter().zip(trace.iter()) {
+            assert_eq!(parsed.pc(), recorded.pc());
+            assert_eq!(parsed.instruction(), recorded.instruction());
+        }
+    }
+
+    #[test]
+    fn invalid_trace_text_is_rejected() {
+        assert_eq!(
+            Trace::from_text("\n  \n").map(|trace| trace.start),
+            Err(TraceParseError::Empty)
+        );
+        for line in [
+            "0:2 iload_0",
+            "0-2 iload_0 []",
+            "0:2 iload_zero []",
+            "0:2 ldc [2x]",
+        ] {
+            let text = format!("0:0 nop []\n\n{line}\n");
+            assert_eq!(
+                Trace::from_text(&text).map(|trace| trace.start),
+                Err(TraceParseError::InvalidLine { line: 3 })
+            );
+        }
+    }
+
+    #[test]
+    fn mutually_recursive_calls_abort_the_recording() {
+        let start = ProgramCounter::new(0, 2);
+        let mut recorder = Recorder::new();
+        recorder.init(start, start);
+        let invoke = |opcode, operands: Vec<i32>| {
+            Instruction::new(
+                opcode,
+                Some(operands.into_iter().map(Value::Int).collect()),
+            )
+        };
+        // Method 0 calls method 1 which calls method 0 through a virtual
+        // call, the recorder only sees the callee once it's entered.
+        recorder.record(start, invoke(OPCode::InvokeStatic, vec![1]));
+        recorder.record(
+            ProgramCounter::new(1, 0),
+            invoke(OPCode::InvokeVirtual, vec![0, 7]),
+        );
+        assert!(recorder.is_recording());
+        recorder.record(
+            ProgramCounter::new(0, 0),
+            Instruction::new(OPCode::ILoad0, None),
+        );
+        assert!(recorder.is_done_recording(start));
+        assert_eq!(
+            recorder.recording().unwrap_err(),
+            AbortReason::RecursiveCall
+        );
+
+        // Static calls to a method of the call chain abort before the call.
+        recorder.init(start, start);
+        recorder.record(start, invoke(OPCode::InvokeStatic, vec![1]));
+        recorder.record(
+            ProgramCounter::new(1, 0),
+            invoke(OPCode::InvokeStatic, vec![0]),
+        );
+        assert_eq!(
+            recorder.recording().unwrap_err(),
+            AbortReason::RecursiveCall
+        );
+    }
+
+    #[test]
+    fn methods_can_be_called_again_once_they_return() {
+        let start = ProgramCounter::new(0, 2);
+        let mut recorder = Recorder::new();
+        recorder.init(start, start);
+        let call =
+            Instruction::new(OPCode::InvokeStatic, Some(vec![Value::Int(1)]));
+        for pc in [2, 5] {
+            recorder.record(ProgramCounter::new(0, pc), call.clone());
+            recorder.record(
+                ProgramCounter::new(1, 0),
+                Instruction::new(OPCode::Return, None),
+            );
         }
+        recorder.record(
+            ProgramCounter::new(0, 8),
+            Instruction::new(OPCode::Goto, Some(vec![Value::Int(-6)])),
+        );
+        assert!(recorder.is_recording());
+        assert_eq!(recorded(&recorder).len(), 5);
     }
 }
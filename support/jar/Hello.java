@@ -0,0 +1,11 @@
+public class Hello {
+  public static int main(String[] args) {
+    int answer = answer();
+    System.out.println(answer);
+    return answer;
+  }
+
+  static int answer() {
+    return 42;
+  }
+}
@@ -0,0 +1,13 @@
+public class ArraySum {
+  public static int main(String[] args) {
+    int[] values = new int[10];
+    for (int i = 0; i < values.length; i++) {
+      values[i] = i * i;
+    }
+    int sum = 0;
+    for (int i = 0; i < values.length; i++) {
+      sum += values[i];
+    }
+    return sum;
+  }
+}
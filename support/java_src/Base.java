@@ -0,0 +1,7 @@
+public class Base {
+    int value = 7;
+
+    int base() {
+        return value * 2;
+    }
+}
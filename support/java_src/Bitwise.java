@@ -0,0 +1,13 @@
+public class Bitwise {
+  public static int main(String[] args) {
+    int a = 12;
+    int b = 10;
+    int and = a & b;
+    int or = a | b;
+    int xor = a ^ b;
+    int shl = a << 2;
+    int shr = -a >> 1;
+    int ushr = -a >>> 28;
+    return and + or + xor + shl + shr + ushr;
+  }
+}
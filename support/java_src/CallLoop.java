@@ -0,0 +1,13 @@
+public class CallLoop {
+    public static int twice(int x) {
+        return x + x;
+    }
+
+    public static int main(String[] args) {
+        int sum = 0;
+        for (int i = 0; i < 1000; i++) {
+            sum += twice(i);
+        }
+        return sum;
+    }
+}
@@ -0,0 +1,20 @@
+public class ChainedLoops {
+    public static int main(String[] args) {
+        int zero = 0;
+        int rounds = 5;
+        int total = 0;
+        for (int k = zero; k < rounds; k++) {
+            int i = zero;
+            int j = zero;
+            while (i < 10) {
+                total += i;
+                i++;
+            }
+            while (j < 10) {
+                total += j * k;
+                j++;
+            }
+        }
+        return total;
+    }
+}
@@ -0,0 +1,14 @@
+public class CountDown {
+    public static int main(String[] args) {
+        int low = 1;
+        int sum = 0;
+        for (int i = 10; i >= low; i--) {
+            sum += i;
+        }
+        int two = 2;
+        for (int i = 29; i / 10 == two; i--) {
+            sum += i;
+        }
+        return sum;
+    }
+}
@@ -0,0 +1,19 @@
+public class Counter {
+    static int counter;
+    static double total;
+
+    public static void increment() {
+        counter++;
+        total += counter;
+    }
+
+    public static int run() {
+        increment();
+        increment();
+        return (int) total == 3 ? counter : -1;
+    }
+
+    public static void main(String[] args) {
+        System.out.println(run());
+    }
+}
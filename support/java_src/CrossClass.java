@@ -0,0 +1,5 @@
+public class CrossClass {
+    public static int main() {
+        return MathUtils.square(7) + MathUtils.twice(3);
+    }
+}
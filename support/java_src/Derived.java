@@ -0,0 +1,16 @@
+public class Derived extends Base {
+    int total;
+
+    Derived() {
+        total = super.base() + value;
+    }
+
+    int base() {
+        return 0;
+    }
+
+    public static int main() {
+        Derived derived = new Derived();
+        return derived.total;
+    }
+}
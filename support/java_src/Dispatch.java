@@ -0,0 +1,9 @@
+public class Dispatch {
+    static int twice(int x) {
+        return x + x;
+    }
+
+    public static int main() {
+        return twice(3) + DispatchHelper.twice(4);
+    }
+}
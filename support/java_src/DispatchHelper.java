@@ -0,0 +1,5 @@
+public class DispatchHelper {
+    static int twice(int x) {
+        return 10 * x;
+    }
+}
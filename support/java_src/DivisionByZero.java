@@ -0,0 +1,9 @@
+public class DivisionByZero {
+    public static int divide(int a, int b) {
+        return a / b;
+    }
+
+    public static void main(String[] args) {
+        System.out.println(divide(42, 0));
+    }
+}
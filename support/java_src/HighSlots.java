@@ -0,0 +1,12 @@
+public class HighSlots {
+    public static int main(String[] args) {
+        int a = 1;
+        int b = 2;
+        int c = 3;
+        int sum = 0;
+        for (int i = 0; i < 20; i++) {
+            sum += i * c + a - b;
+        }
+        return sum;
+    }
+}
@@ -0,0 +1,13 @@
+public class Increment {
+    public static int compute() {
+        int i = Integer.MAX_VALUE;
+        i++;
+        int j = 10;
+        j -= 3;
+        return i == Integer.MIN_VALUE ? j : -1;
+    }
+
+    public static void main(String[] args) {
+        compute();
+    }
+}
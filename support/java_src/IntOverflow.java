@@ -0,0 +1,14 @@
+public class IntOverflow {
+    public static int main(String[] args) {
+        int hash = 7;
+        for (int i = 0; i < 100; i++) {
+            hash = hash * 31 + i;
+        }
+        int zero = 0;
+        int count = 0;
+        for (int i = 2147483600; i > zero; i += 10) {
+            count++;
+        }
+        return hash + count;
+    }
+}
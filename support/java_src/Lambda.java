@@ -0,0 +1,12 @@
+import java.util.function.IntBinaryOperator;
+
+public class Lambda {
+    public static int run() {
+        IntBinaryOperator add = (a, b) -> a + b;
+        return add.applyAsInt(20, 22);
+    }
+
+    public static void main(String[] args) {
+        System.out.println(run());
+    }
+}
@@ -0,0 +1,9 @@
+public class LongCounter {
+    public static long main(String[] args) {
+        long sum = 0;
+        for (long i = 0; i < 100; i++) {
+            sum += i;
+        }
+        return sum;
+    }
+}
@@ -0,0 +1,17 @@
+public class LongLoop {
+    public static long main(String[] args) {
+        long previous = 0;
+        long current = 1;
+        for (int i = 0; i < 90; i++) {
+            long next = previous + current;
+            previous = current;
+            current = next;
+        }
+        long three = 3;
+        long power = 1;
+        for (int i = 0; i < 40; i++) {
+            power = power * three - previous;
+        }
+        return previous + power;
+    }
+}
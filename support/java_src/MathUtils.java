@@ -0,0 +1,9 @@
+public class MathUtils {
+    public static int square(int x) {
+        return x * x;
+    }
+
+    public static int twice(int x) {
+        return 2 * x;
+    }
+}
@@ -0,0 +1,10 @@
+public class MinDivideLoop {
+    public static int main(String[] args) {
+        int sum = 0;
+        for (int i = 0; i < 1000; i++) {
+            int divisor = i % 2 * 2 - 1;
+            sum += Integer.MIN_VALUE / divisor + Integer.MIN_VALUE % divisor;
+        }
+        return sum;
+    }
+}
@@ -0,0 +1,6 @@
+public class NativeMethod {
+    public static native int answer();
+
+    public static void main(String[] args) {
+    }
+}
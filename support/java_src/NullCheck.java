@@ -0,0 +1,17 @@
+public class NullCheck {
+    public static int compute() {
+        Object object = null;
+        int result = 0;
+        if (object == null) {
+            result += 1;
+        }
+        if (object != null) {
+            result += 10;
+        }
+        return result;
+    }
+
+    public static void main(String[] args) {
+        compute();
+    }
+}
@@ -0,0 +1,17 @@
+public class Overload {
+  public static int main(String[] args) {
+    return add(1, 2) + (int) add(3L, 4L) + add(1, 2, 3);
+  }
+
+  static int add(int a, int b) {
+    return a + b;
+  }
+
+  static long add(long a, long b) {
+    return a + b;
+  }
+
+  static int add(int a, int b, int c) {
+    return a + b + c;
+  }
+}
@@ -0,0 +1,20 @@
+public class Point {
+    int x;
+    int y;
+
+    Point(int x, int y) {
+        this.x = x;
+        this.y = y;
+    }
+
+    public static int run() {
+        Point point = new Point(3, 4);
+        point.y = point.x + 4;
+        Point other = new Point(10, 0);
+        return point.x * point.y + other.x + other.y;
+    }
+
+    public static void main(String[] args) {
+        run();
+    }
+}
@@ -0,0 +1,16 @@
+public class Println {
+    public static void print() {
+        System.out.print(0);
+    }
+
+    public static void main(String[] args) {
+        int three = 3;
+        System.out.println(42);
+        System.out.println(7L);
+        System.out.println((float) three / (three - 1));
+        System.out.println((double) three / (three + 1));
+        System.out.println(true);
+        System.out.println('c');
+        System.out.println();
+    }
+}
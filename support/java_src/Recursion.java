@@ -0,0 +1,12 @@
+public class Recursion {
+    static int depth(int n) {
+        if (n == 0) {
+            return 0;
+        }
+        return 1 + depth(n - 1);
+    }
+
+    public static int main(String[] args) {
+        return depth(1000);
+    }
+}
@@ -0,0 +1,9 @@
+public class ShiftLoop {
+    public static int main(String[] args) {
+        int sum = 0;
+        for (int i = 0; i < 1000; i++) {
+            sum += i << 1;
+        }
+        return sum;
+    }
+}
@@ -0,0 +1,15 @@
+public class SideExit {
+    public static int run() {
+        int sum = 0;
+        for (int i = 1; i <= 5; i++) {
+            for (int j = 1; j <= 10; j++) {
+                sum = sum + j;
+            }
+        }
+        return sum;
+    }
+
+    public static void main(String[] args) {
+        System.out.println(run());
+    }
+}
@@ -0,0 +1,22 @@
+public class Spill {
+    public static int main(String[] args) {
+        int a = 1;
+        int b = 2;
+        int c = 3;
+        int d = 4;
+        int e = 5;
+        int f = 6;
+        int g = 7;
+        int h = 8;
+        int j = 9;
+        int k = 10;
+        int l = 11;
+        int m = 12;
+        int n = 13;
+        int sum = 0;
+        for (int i = 0; i < 10; i++) {
+            sum += a + (b * (c + (d - (e + (f * (g + (h - (j + (k * (l + (m - (n + i))))))))))));
+        }
+        return sum;
+    }
+}
@@ -0,0 +1,16 @@
+public class StackOps {
+  public static int main(String[] args) {
+    // Discarded results are popped from the stack.
+    one();
+    two();
+    return 7;
+  }
+
+  static int one() {
+    return 1;
+  }
+
+  static long two() {
+    return 1L;
+  }
+}
@@ -0,0 +1,12 @@
+public class StaticConfig {
+    static int loads;
+    static int scale = 2;
+
+    static {
+        loads++;
+    }
+
+    static int offset() {
+        return scale * 10;
+    }
+}
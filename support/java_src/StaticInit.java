@@ -0,0 +1,12 @@
+public class StaticInit {
+    static int base;
+
+    static {
+        base = 40;
+    }
+
+    public static int main() {
+        return base + StaticConfig.scale + StaticConfig.offset()
+            + StaticConfig.loads * 100;
+    }
+}
@@ -0,0 +1,9 @@
+public class StringArg {
+  public static int main(String[] args) {
+    return pick("ignored", 7);
+  }
+
+  public static int pick(String s, int x) {
+    return x;
+  }
+}
@@ -0,0 +1,7 @@
+public class StringConcat {
+    public static int main(String[] args) {
+        int n = 3;
+        String message = "n = " + n;
+        return message == null ? 0 : n;
+    }
+}
@@ -0,0 +1,37 @@
+public class Switch {
+  public static int main(String[] args) {
+    int sum = 0;
+    for (int i = 0; i < 5; i++) {
+      sum += dense(i) + sparse(i * 100);
+    }
+    return sum;
+  }
+
+  static int dense(int x) {
+    switch (x) {
+      case 0:
+        return 1;
+      case 1:
+        return 2;
+      case 2:
+        return 3;
+      case 3:
+        return 4;
+      default:
+        return 0;
+    }
+  }
+
+  static int sparse(int x) {
+    switch (x) {
+      case 0:
+        return 10;
+      case 100:
+        return 20;
+      case 1000:
+        return 30;
+      default:
+        return 0;
+    }
+  }
+}
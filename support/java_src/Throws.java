@@ -0,0 +1,11 @@
+import java.io.IOException;
+
+public class Throws {
+    public static int read(int value) throws IOException {
+        return value;
+    }
+
+    public static void main(String[] args) throws IOException {
+        read(7);
+    }
+}
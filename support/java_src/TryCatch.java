@@ -0,0 +1,18 @@
+public class TryCatch {
+  public static int main(String[] args) {
+    int result = 0;
+    try {
+      result = check(-5);
+    } catch (IllegalArgumentException e) {
+      result = 42;
+    }
+    return result;
+  }
+
+  static int check(int value) {
+    if (value < 0) {
+      throw new IllegalArgumentException();
+    }
+    return value;
+  }
+}
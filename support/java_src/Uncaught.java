@@ -0,0 +1,13 @@
+public class Uncaught {
+    static int fail() {
+        throw new UnsupportedOperationException();
+    }
+
+    public static int main() {
+        try {
+            return fail();
+        } catch (IllegalArgumentException e) {
+            return 1;
+        }
+    }
+}
@@ -0,0 +1,20 @@
+public class Unwind {
+    static int depth(int n) {
+        if (n == 0) {
+            throw new IllegalStateException();
+        }
+        return depth(n - 1) + 1;
+    }
+
+    public static int main() {
+        int result = 0;
+        try {
+            result = depth(3);
+        } catch (RuntimeException e) {
+            result = 10;
+        } finally {
+            result += 5;
+        }
+        return result;
+    }
+}
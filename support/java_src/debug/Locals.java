@@ -0,0 +1,13 @@
+public class Locals {
+    public static int sum(int n) {
+        int total = 0;
+        for (int i = 0; i < n; i++) {
+            total += i;
+        }
+        return total;
+    }
+
+    public static void main(String[] args) {
+        int result = sum(4);
+    }
+}
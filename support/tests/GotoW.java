@@ -0,0 +1,13 @@
+// `GotoW.class` is hand-crafted from the output of javac, the backward `goto`
+// of the loop is rewritten into a `goto_w` and the branch offsets, code length
+// and debug tables are adjusted accordingly.
+public class GotoW {
+  public static int main(String[] args) {
+      int sum = 0;
+      int i =0;
+      for (i=0;i < 1000;i++) {
+          sum += 1;
+      }
+      return sum;
+  }
+}